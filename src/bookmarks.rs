@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::state_dir;
+
+/// a saved position within a log stream
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Bookmark {
+    #[serde(rename = "logGroup")]
+    pub log_group: String,
+
+    #[serde(rename = "logStream")]
+    pub log_stream: String,
+
+    /// epoch millis
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+}
+
+/// `bookmarks.json` in the state dir
+pub fn bookmarks_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("bookmarks.json"))
+}
+
+/// all bookmarks, by name
+pub fn load_bookmarks() -> Result<BTreeMap<String, Bookmark>, String> {
+    let path = match bookmarks_path() {
+        Some(path) if path.exists() => path,
+        _ => return Ok(BTreeMap::new()),
+    };
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("unable to read bookmarks {}: {e}", path.display()))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("invalid bookmarks file {}: {e}", path.display()))
+}
+
+/// add or replace a bookmark
+pub fn save_bookmark(name: &str, bookmark: Bookmark) -> Result<(), String> {
+    let path = bookmarks_path().ok_or_else(|| "unable to locate bookmarks file".to_string())?;
+    let mut bookmarks = load_bookmarks()?;
+    bookmarks.insert(name.to_string(), bookmark);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("unable to create {}: {e}", dir.display()))?;
+    }
+    let json = serde_json::to_string_pretty(&bookmarks)
+        .map_err(|e| format!("unable to serialize bookmarks: {e}"))?;
+    std::fs::write(&path, json + "\n")
+        .map_err(|e| format!("unable to write bookmarks {}: {e}", path.display()))
+}
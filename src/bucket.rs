@@ -0,0 +1,44 @@
+use std::str::FromStr;
+
+use jiff::Timestamp;
+
+use crate::Event;
+
+/// size of the time buckets events are routed into with --bucket-by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BucketBy {
+    Hour,
+    Day,
+}
+
+impl FromStr for BucketBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hour" => Ok(BucketBy::Hour),
+            "day" => Ok(BucketBy::Day),
+            _ => Err(format!("unknown bucket '{s}', expected one of: hour, day")),
+        }
+    }
+}
+
+impl BucketBy {
+    /// UTC bucket name for a timestamp, used as the file name: 2024-05-01T13 or 2024-05-01
+    pub fn bucket_name(&self, timestamp_ms: i64) -> String {
+        let t = Timestamp::from_millisecond(timestamp_ms).unwrap_or(Timestamp::UNIX_EPOCH);
+        let format = match self {
+            BucketBy::Hour => "%Y-%m-%dT%H",
+            BucketBy::Day => "%Y-%m-%d",
+        };
+        t.strftime(format).to_string()
+    }
+
+    /// split time sorted events into (bucket name, events) runs
+    pub fn split<'a>(&self, events: &'a [Event]) -> Vec<(String, &'a [Event])> {
+        events
+            .chunk_by(|a, b| self.bucket_name(a.timestamp) == self.bucket_name(b.timestamp))
+            .map(|chunk| (self.bucket_name(chunk[0].timestamp), chunk))
+            .collect()
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::cache_dir;
+use rust_aws_logs::timestamps::now_millis;
+
+/// cached listings younger than this are used as-is
+pub const LISTING_TTL_MS: i64 = 10 * 60 * 1000;
+
+/// a cached list of log group or log stream names
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Listing {
+    /// epoch millis
+    #[serde(rename = "fetchedAt")]
+    pub fetched_at: i64,
+
+    #[serde(rename = "names")]
+    pub names: Vec<String>,
+}
+
+impl Listing {
+    pub fn is_fresh(&self) -> bool {
+        now_millis() - self.fetched_at < LISTING_TTL_MS
+    }
+}
+
+/// listings differ per account and region, so the key covers the profile (default $AWS_PROFILE)
+/// and region as well as the listing itself, e.g. `log-groups` or `log-streams` of a group
+pub fn listing_key(
+    profile: Option<&str>,
+    region: Option<&str>,
+    listing: &str,
+    log_group: Option<&str>,
+) -> String {
+    let profile = profile
+        .map(|p| p.to_string())
+        .unwrap_or_else(|| std::env::var("AWS_PROFILE").unwrap_or_default());
+    let mut hasher = Sha256::new();
+    for part in [
+        profile.as_str(),
+        region.unwrap_or(""),
+        listing,
+        log_group.unwrap_or(""),
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{listing}-{}", &hex::encode(hasher.finalize())[..16])
+}
+
+/// `listings/<key>.json` in the cache dir
+fn listing_path(key: &str) -> Option<PathBuf> {
+    Some(cache_dir()?.join("listings").join(format!("{key}.json")))
+}
+
+/// a previously stored listing, fresh or not
+pub fn load_listing(key: &str) -> Option<Listing> {
+    let path = listing_path(key)?;
+    let contents = std::fs::read_to_string(&path).ok()?;
+    serde_json::from_str(&contents)
+        .map_err(|e| debug!("ignoring invalid listing cache {}: {e}", path.display()))
+        .ok()
+}
+
+pub fn store_listing(key: &str, names: &[String]) -> Result<(), String> {
+    let path = listing_path(key).ok_or_else(|| "unable to locate cache dir".to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("unable to create {}: {e}", dir.display()))?;
+    }
+    let listing = Listing {
+        fetched_at: now_millis(),
+        names: names.to_vec(),
+    };
+    let json =
+        serde_json::to_string(&listing).map_err(|e| format!("unable to serialize listing: {e}"))?;
+    debug!("caching {} names in {}", names.len(), path.display());
+    std::fs::write(&path, json)
+        .map_err(|e| format!("unable to write listing cache {}: {e}", path.display()))
+}
@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// how often a running download saves its checkpoint
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// how far a download into an output file got, saved next to it (`out.log.checkpoint.json`)
+/// while it runs so an interrupted download can carry on from there with --resume
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Checkpoint {
+    #[serde(rename = "logGroup")]
+    pub log_group: String,
+
+    #[serde(rename = "logStream")]
+    pub log_stream: String,
+
+    /// GetLogEvents token of the first page not yet in the file
+    #[serde(rename = "forwardToken")]
+    pub forward_token: String,
+
+    /// length of the output file once everything before forward_token was written, anything
+    /// after it was written after the checkpoint and is fetched again
+    #[serde(rename = "bytes")]
+    pub bytes: u64,
+
+    #[serde(rename = "events")]
+    pub events: usize,
+
+    /// timestamp of the first event in the file, for relative time formats
+    #[serde(rename = "firstEventMs")]
+    pub first_event_ms: Option<i64>,
+}
+
+/// the checkpoint file of an output file
+pub fn checkpoint_path(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_owned();
+    name.push(".checkpoint.json");
+    PathBuf::from(name)
+}
+
+impl Checkpoint {
+    /// the checkpoint left by an interrupted download into output, None when it finished or
+    /// never started
+    pub fn load(output: &Path) -> Result<Option<Checkpoint>, String> {
+        let path = checkpoint_path(output);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map(Some)
+                .map_err(|e| format!("unable to parse {}: {e}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("unable to read {}: {e}", path.display())),
+        }
+    }
+
+    /// written to a temp file and renamed, so an interruption never leaves it half written
+    pub fn save(&self, output: &Path) -> Result<(), String> {
+        let path = checkpoint_path(output);
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("unable to serialize checkpoint: {e}"))?;
+        std::fs::write(&tmp, json)
+            .and_then(|_| std::fs::rename(&tmp, &path))
+            .map_err(|e| format!("unable to write {}: {e}", path.display()))
+    }
+
+    /// forget the checkpoint once the download has finished
+    pub fn remove(output: &Path) -> Result<(), String> {
+        let path = checkpoint_path(output);
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("unable to remove {}: {e}", path.display())),
+        }
+    }
+}
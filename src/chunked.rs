@@ -0,0 +1,257 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use futures::TryStreamExt;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AwsLogsError;
+use crate::timestamps::{format_rfc3339, now_millis};
+use crate::{stream_log_pages, Event, Pagination, LAST_EVENT_TIME_LAG_MS};
+
+/// progress of a chunked export, kept next to the chunks so an interrupted export resumes
+const PROGRESS_FILE: &str = "progress.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ChunkProgress {
+    /// chunk start, epoch millis
+    #[serde(rename = "start")]
+    start: i64,
+
+    #[serde(rename = "file")]
+    file: String,
+
+    #[serde(rename = "events")]
+    events: usize,
+
+    /// events up to (not including) this time were fetched. short of the chunk end when the
+    /// stream could still have had newer events, so a resume fetches the chunk again
+    #[serde(rename = "coveredUntil")]
+    covered_until: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Progress {
+    #[serde(rename = "logGroup")]
+    log_group: String,
+
+    #[serde(rename = "logStream")]
+    log_stream: String,
+
+    #[serde(rename = "windowMs")]
+    window_ms: i64,
+
+    #[serde(rename = "chunks")]
+    chunks: Vec<ChunkProgress>,
+}
+
+impl Progress {
+    fn load(dir: &Path) -> Result<Option<Progress>, AwsLogsError> {
+        let path = dir.join(PROGRESS_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map(Some)
+                .map_err(|e| AwsLogsError::Io(format!("unable to parse {}: {e}", path.display()))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(AwsLogsError::Io(format!(
+                "unable to read {}: {e}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// written to a temp file and renamed, so an interruption never leaves it half written
+    fn save(&self, dir: &Path) -> Result<(), AwsLogsError> {
+        let path = dir.join(PROGRESS_FILE);
+        let tmp = dir.join(format!("{PROGRESS_FILE}.tmp"));
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| AwsLogsError::Io(format!("unable to serialize export progress: {e}")))?;
+        std::fs::write(&tmp, json)
+            .and_then(|_| std::fs::rename(&tmp, &path))
+            .map_err(|e| AwsLogsError::Io(format!("unable to write {}: {e}", path.display())))
+    }
+}
+
+/// the first and last event times of a stream as DescribeLogStreams reports them, None for a
+/// stream without events. the last can be up to LAST_EVENT_TIME_LAG_MS behind
+async fn stream_time_range(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    log_stream: &str,
+) -> Result<Option<(i64, i64)>, AwsLogsError> {
+    let response = client
+        .describe_log_streams()
+        .log_group_name(log_group)
+        .log_stream_name_prefix(log_stream)
+        .send()
+        .await
+        .map_err(|e| {
+            AwsLogsError::sdk(&format!("failed to describe log stream {log_stream}"), &e)
+        })?;
+    let stream = response
+        .log_streams
+        .unwrap_or_default()
+        .into_iter()
+        .find(|s| s.log_stream_name.as_deref() == Some(log_stream))
+        .ok_or_else(|| {
+            AwsLogsError::InvalidArgument(format!(
+                "no log stream named {log_stream} in {log_group}"
+            ))
+        })?;
+    Ok(stream
+        .first_event_timestamp
+        .zip(stream.last_event_timestamp))
+}
+
+/// a chunk export_chunked finished writing
+#[derive(Debug, Clone)]
+pub struct ChunkWritten<'a> {
+    /// counted from 1, out of total
+    pub number: usize,
+    pub total: usize,
+    pub file_name: &'a str,
+    pub events: usize,
+}
+
+/// chunk file name from its start time, e.g. 2024-05-01T13-00-00Z.log
+fn chunk_file_name(start: i64) -> String {
+    format!("{}.log", format_rfc3339(start).replace(':', "-"))
+}
+
+/// fetch the events in [start, end) a page at a time, writing the lines render makes of each
+/// page to file. returns the number of lines written
+async fn export_chunk(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    log_stream: &str,
+    start: i64,
+    end: i64,
+    file: &mut std::fs::File,
+    render: &impl Fn(Vec<Event>) -> Vec<String>,
+) -> Result<usize, AwsLogsError> {
+    let pagination = Pagination::default();
+    let pages = stream_log_pages(
+        client,
+        log_group,
+        log_stream,
+        Some(start),
+        Some(end),
+        &pagination,
+    );
+    let mut pages = std::pin::pin!(pages);
+    let mut count = 0;
+    while let Some(page) = pages.try_next().await? {
+        let lines = render(page.events);
+        if !lines.is_empty() {
+            count += lines.len();
+            file.write_all((lines.join("\n") + "\n").as_bytes())
+                .map_err(|e| AwsLogsError::Io(format!("unable to write chunk: {e}")))?;
+        }
+    }
+    Ok(count)
+}
+
+/// export log_stream into dir as one text file per window of its time range, recording each
+/// finished chunk in progress.json. render turns each fetched page into the lines to write,
+/// dropping any it filters out, and on_chunk is told about each chunk written. the chunks run
+/// up to LAST_EVENT_TIME_LAG_MS past the reported last event (or now), since it may not be the
+/// last one yet. rerunning with the same dir skips finished chunks and refetches the ones that
+/// ran into that. returns the chunk files, none for a stream without events
+pub async fn export_chunked(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    log_stream: &str,
+    window: Duration,
+    dir: &Path,
+    render: impl Fn(Vec<Event>) -> Vec<String>,
+    mut on_chunk: impl FnMut(ChunkWritten),
+) -> Result<Vec<PathBuf>, AwsLogsError> {
+    let window_ms = window.as_millis() as i64;
+    if window_ms == 0 {
+        return Err(AwsLogsError::InvalidArgument(
+            "the chunk window must be longer than 0".to_string(),
+        ));
+    }
+    std::fs::create_dir_all(dir).map_err(|e| {
+        AwsLogsError::Io(format!("unable to create directory {}: {e}", dir.display()))
+    })?;
+    let mut progress = match Progress::load(dir)? {
+        Some(p) if p.log_group != log_group || p.log_stream != log_stream => {
+            return Err(AwsLogsError::InvalidArgument(format!(
+                "{} holds an export of {} / {}, use another directory",
+                dir.display(),
+                p.log_group,
+                p.log_stream
+            )))
+        }
+        Some(p) if p.window_ms != window_ms => {
+            return Err(AwsLogsError::InvalidArgument(format!(
+                "{} was exported in {} chunks, resume with the same window",
+                dir.display(),
+                humantime::format_duration(Duration::from_millis(p.window_ms as u64))
+            )))
+        }
+        Some(p) => {
+            info!("resuming export, {} chunks already done", p.chunks.len());
+            p
+        }
+        None => Progress {
+            log_group: log_group.to_string(),
+            log_stream: log_stream.to_string(),
+            window_ms,
+            chunks: vec![],
+        },
+    };
+    let Some((first, last)) = stream_time_range(client, log_group, log_stream).await? else {
+        debug!("{log_stream} has no events");
+        return Ok(vec![]);
+    };
+    // events written since the reported last one can be up to the lag past it
+    let until = (last + LAST_EVENT_TIME_LAG_MS)
+        .min(now_millis())
+        .max(last + 1);
+    // chunks are aligned to the window so their boundaries don't depend on when the export ran
+    let chunk_starts = (first - first.rem_euclid(window_ms)..until)
+        .step_by(window_ms as usize)
+        .collect::<Vec<i64>>();
+    let total = chunk_starts.len();
+    let mut files = vec![];
+    for (i, start) in chunk_starts.into_iter().enumerate() {
+        let end = start + window_ms;
+        let file_name = chunk_file_name(start);
+        let path = dir.join(&file_name);
+        let done = progress.chunks.iter().find(|c| c.start == start);
+        if done.is_some_and(|c| c.covered_until >= end) {
+            debug!("skipping finished chunk {file_name}");
+            files.push(path);
+            continue;
+        }
+        // written under a temp name and renamed when complete
+        let part = dir.join(format!("{file_name}.part"));
+        let mut file = std::fs::File::create(&part)
+            .map_err(|e| AwsLogsError::Io(format!("unable to create {}: {e}", part.display())))?;
+        let events = export_chunk(
+            client, log_group, log_stream, start, end, &mut file, &render,
+        )
+        .await?;
+        std::fs::rename(&part, &path)
+            .map_err(|e| AwsLogsError::Io(format!("unable to write {}: {e}", path.display())))?;
+        progress.chunks.retain(|c| c.start != start);
+        progress.chunks.push(ChunkProgress {
+            start,
+            file: file_name.clone(),
+            events,
+            covered_until: end.min(until),
+        });
+        progress.save(dir)?;
+        on_chunk(ChunkWritten {
+            number: i + 1,
+            total,
+            file_name: &file_name,
+            events,
+        });
+        files.push(path);
+    }
+    Ok(files)
+}
@@ -0,0 +1,85 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use log::info;
+
+/// don't put more than this on the clipboard, pasting megabytes of logs into a ticket helps no one
+pub const MAX_CLIPBOARD_BYTES: usize = 1024 * 1024;
+
+/// keep at most max_bytes of text, dropping whole lines from the start so the most recent
+/// events are kept. returns the kept text and whether anything was dropped
+pub fn truncate_to_last_lines(text: &str, max_bytes: usize) -> (&str, bool) {
+    if text.len() <= max_bytes {
+        return (text, false);
+    }
+    let mut start = text.len() - max_bytes;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    // start at the next full line if there is one
+    if let Some(newline) = text[start..].find('\n') {
+        if start + newline + 1 < text.len() {
+            start += newline + 1;
+        }
+    }
+    (&text[start..], true)
+}
+
+/// the clipboard command available on this system
+fn clipboard_command() -> Option<Command> {
+    let has = |program: &str| {
+        Command::new("which")
+            .arg(program)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    };
+    if cfg!(target_os = "macos") {
+        return Some(Command::new("pbcopy"));
+    }
+    if cfg!(target_os = "windows") {
+        return Some(Command::new("clip"));
+    }
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() && has("wl-copy") {
+        return Some(Command::new("wl-copy"));
+    }
+    if has("xclip") {
+        let mut cmd = Command::new("xclip");
+        cmd.args(["-selection", "clipboard"]);
+        return Some(cmd);
+    }
+    if has("xsel") {
+        let mut cmd = Command::new("xsel");
+        cmd.args(["--clipboard", "--input"]);
+        return Some(cmd);
+    }
+    None
+}
+
+/// put text on the system clipboard
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let mut cmd = clipboard_command()
+        .ok_or_else(|| "no clipboard tool found (pbcopy, wl-copy, xclip or xsel)".to_string())?;
+    let program = cmd.get_program().to_string_lossy().to_string();
+    info!(
+        "copying {} bytes to the clipboard with {program}",
+        text.len()
+    );
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("unable to run {program}: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("unable to write to {program}: {e}"))?;
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("failed waiting for {program}: {e}"))?;
+    if !status.success() {
+        return Err(format!("{program} exited with {status}"));
+    }
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use rust_aws_logs::level::{detect_level, Level};
+
+/// styles per level as written in the config file, e.g.
+///
+/// ```toml
+/// [theme]
+/// error = "bold red"
+/// warn = "yellow"
+/// debug = "dim"
+/// ```
+///
+/// a style is a space separated list of colors (black, red, green, yellow, blue, magenta,
+/// cyan, white, optionally prefixed with bright_) and modifiers (bold, dim, italic, underline).
+/// an empty string leaves that level unstyled
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct ThemeConfig {
+    #[serde(rename = "trace")]
+    pub trace: Option<String>,
+
+    #[serde(rename = "debug")]
+    pub debug: Option<String>,
+
+    #[serde(rename = "info")]
+    pub info: Option<String>,
+
+    #[serde(rename = "warn")]
+    pub warn: Option<String>,
+
+    #[serde(rename = "error")]
+    pub error: Option<String>,
+
+    #[serde(rename = "fatal")]
+    pub fatal: Option<String>,
+}
+
+/// ANSI escape sequences to wrap lines of each level with
+#[derive(Debug, Clone)]
+pub struct Theme {
+    trace: String,
+    debug: String,
+    info: String,
+    warn: String,
+    error: String,
+    fatal: String,
+}
+
+const RESET: &str = "\x1b[0m";
+
+fn sgr_code(word: &str) -> Option<u8> {
+    let (bright, name) = match word.strip_prefix("bright_") {
+        Some(name) => (true, name),
+        None => (false, word),
+    };
+    let color = match name {
+        "black" => 0,
+        "red" => 1,
+        "green" => 2,
+        "yellow" => 3,
+        "blue" => 4,
+        "magenta" => 5,
+        "cyan" => 6,
+        "white" => 7,
+        _ if bright => return None,
+        "bold" => return Some(1),
+        "dim" => return Some(2),
+        "italic" => return Some(3),
+        "underline" => return Some(4),
+        _ => return None,
+    };
+    Some(if bright { 90 + color } else { 30 + color })
+}
+
+/// turn a style like "bold red" into its escape sequence
+fn parse_style(style: &str) -> Result<String, String> {
+    let codes = style
+        .split_whitespace()
+        .map(|word| {
+            sgr_code(&word.to_ascii_lowercase())
+                .map(|code| code.to_string())
+                .ok_or_else(|| format!("unknown color or style in theme: '{word}'"))
+        })
+        .collect::<Result<Vec<String>, String>>()?;
+    if codes.is_empty() {
+        return Ok(String::new());
+    }
+    Ok(format!("\x1b[{}m", codes.join(";")))
+}
+
+impl Theme {
+    /// the default theme with any levels set in the config overriding it
+    pub fn from_config(config: &ThemeConfig) -> Result<Theme, String> {
+        let style = |configured: &Option<String>, default: &str| {
+            parse_style(configured.as_deref().unwrap_or(default))
+        };
+        Ok(Theme {
+            trace: style(&config.trace, "dim")?,
+            debug: style(&config.debug, "dim")?,
+            info: style(&config.info, "")?,
+            warn: style(&config.warn, "yellow")?,
+            error: style(&config.error, "red")?,
+            fatal: style(&config.fatal, "bold red")?,
+        })
+    }
+
+    fn style_for(&self, level: Level) -> &str {
+        match level {
+            Level::Trace => &self.trace,
+            Level::Debug => &self.debug,
+            Level::Info => &self.info,
+            Level::Warn => &self.warn,
+            Level::Error => &self.error,
+            Level::Fatal => &self.fatal,
+        }
+    }
+
+    /// style a line according to the level detected in its message
+    pub fn paint(&self, line: &str, message: &str) -> String {
+        match detect_level(message).map(|level| self.style_for(level)) {
+            Some(style) if !style.is_empty() => format!("{style}{line}{RESET}"),
+            _ => line.to_string(),
+        }
+    }
+
+    /// mark a line that matched a highlight rule: the style of the rule's severity, in reverse video
+    pub fn highlight(&self, line: &str, severity: Level) -> String {
+        format!("{}\x1b[7m{line}{RESET}", self.style_for(severity))
+    }
+}
+
+/// only color when writing to a terminal, and respect https://no-color.org
+pub fn stdout_supports_color() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    !no_color && std::io::stdout().is_terminal()
+}
+
+/// whether lines printed to stdout are colored by level, set with --color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// color when stdout is a terminal and NO_COLOR isn't set
+    Auto,
+    /// color even when piped, e.g. into `less -R`
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "unknown color choice '{s}', expected one of: auto, always, never"
+            )),
+        }
+    }
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => stdout_supports_color(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
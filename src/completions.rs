@@ -0,0 +1,243 @@
+use std::str::FromStr;
+
+/// shells completion scripts can be generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!(
+                "unknown shell '{s}', expected one of: bash, zsh, fish"
+            )),
+        }
+    }
+}
+
+/// what the completion scripts ask the binary to list with --complete
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    LogGroup,
+    LogStream,
+}
+
+impl FromStr for CompletionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "log-group" => Ok(CompletionKind::LogGroup),
+            "log-stream" => Ok(CompletionKind::LogStream),
+            _ => Err(format!(
+                "unknown completion '{s}', expected one of: log-group, log-stream"
+            )),
+        }
+    }
+}
+
+/// a visible flag of the command line, as the completion scripts need it
+struct Flag {
+    long: String,
+    short: Option<char>,
+    help: String,
+    takes_value: bool,
+}
+
+fn flags(cmd: &mut clap::Command) -> Vec<Flag> {
+    cmd.build();
+    cmd.get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .filter_map(|arg| {
+            Some(Flag {
+                long: arg.get_long()?.to_string(),
+                short: arg.get_short(),
+                help: arg
+                    .get_help()
+                    .map(|h| h.to_string())
+                    .unwrap_or_default()
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .to_string(),
+                takes_value: arg.get_action().takes_values(),
+            })
+        })
+        .collect()
+}
+
+/// completion script for `program`. --log-group and --log-stream values are completed by calling
+/// back into `program --complete ...`, which answers from the cached listings of the --profile
+/// already on the command line
+pub fn script(shell: Shell, program: &str, cmd: &mut clap::Command) -> String {
+    let flags = flags(cmd);
+    match shell {
+        Shell::Bash => bash_script(program, &flags),
+        Shell::Zsh => zsh_script(program, &flags),
+        Shell::Fish => fish_script(program, &flags),
+    }
+}
+
+fn bash_script(program: &str, flags: &[Flag]) -> String {
+    let words = flags
+        .iter()
+        .flat_map(|f| {
+            f.short
+                .map(|s| format!("-{s}"))
+                .into_iter()
+                .chain([format!("--{}", f.long)])
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    let func = format!("_{}", program.replace('-', "_"));
+    // names go through read/printf rather than compgen -W, which would expand the $LATEST in
+    // lambda stream names
+    format!(
+        r#"{func}_names() {{
+    local name
+    while IFS= read -r name; do
+        [[ $name == "$cur"* ]] && COMPREPLY+=("$(printf '%q' "$name")")
+    done < <({program} --complete "$@" ${{profile:+--profile "$profile"}} 2>/dev/null)
+}}
+
+{func}() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" prev="${{COMP_WORDS[COMP_CWORD-1]}}" group="" profile="" i
+    COMPREPLY=()
+    for ((i = 1; i < COMP_CWORD - 1; i++)); do
+        case "${{COMP_WORDS[i]}}" in
+            -g|--log-group) group="${{COMP_WORDS[i+1]}}" ;;
+            --profile) profile="${{COMP_WORDS[i+1]}}" ;;
+        esac
+    done
+    case "$prev" in
+        -g|--log-group)
+            {func}_names log-group
+            return ;;
+        -s|--log-stream)
+            [ -n "$group" ] && {func}_names log-stream --log-group "$group"
+            return ;;
+    esac
+    COMPREPLY=($(compgen -W "{words}" -- "$cur"))
+}}
+complete -o default -F {func} {program}
+"#
+    )
+}
+
+/// escape help text for a zsh _arguments spec
+fn zsh_escape(help: &str) -> String {
+    help.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh_script(program: &str, flags: &[Flag]) -> String {
+    let specs = flags
+        .iter()
+        .map(|f| {
+            let value = match f.long.as_str() {
+                "log-group" => ":log group:_alog_log_groups",
+                "log-stream" => ":log stream:_alog_log_streams",
+                _ if f.takes_value => ":value: ",
+                _ => "",
+            };
+            let help = zsh_escape(&f.help);
+            match f.short {
+                Some(s) => format!(
+                    "    '(-{s} --{long})'{{-{s},--{long}}}'[{help}]{value}' \\\n",
+                    long = f.long
+                ),
+                None => format!("    '--{}[{help}]{value}' \\\n", f.long),
+            }
+        })
+        .collect::<String>();
+    format!(
+        r#"#compdef {program}
+
+_alog_profile_args() {{
+    [[ -n ${{opt_args[--profile]}} ]] && reply=(--profile "${{opt_args[--profile]}}") || reply=()
+}}
+
+_alog_log_groups() {{
+    local -a groups reply
+    _alog_profile_args
+    groups=(${{(f)"$({program} --complete log-group $reply 2>/dev/null)"}})
+    compadd -a groups
+}}
+
+_alog_log_streams() {{
+    local group=${{opt_args[-g]:-${{opt_args[--log-group]}}}}
+    [[ -n $group ]] || return 1
+    local -a streams reply
+    _alog_profile_args
+    streams=(${{(f)"$({program} --complete log-stream --log-group "$group" $reply 2>/dev/null)"}})
+    compadd -a streams
+}}
+
+_arguments -s \
+{specs}    && return 0
+"#
+    )
+}
+
+fn fish_script(program: &str, flags: &[Flag]) -> String {
+    let mut script = format!(
+        r#"function __alog_profile_args
+    set -l tokens (commandline -opc)
+    for i in (seq (math (count $tokens) - 1))
+        if test $tokens[$i] = --profile
+            echo --profile
+            echo $tokens[(math $i + 1)]
+            return
+        end
+    end
+end
+
+function __alog_log_streams
+    set -l tokens (commandline -opc)
+    for i in (seq (math (count $tokens) - 1))
+        if contains -- $tokens[$i] -g --log-group
+            {program} --complete log-stream --log-group $tokens[(math $i + 1)] (__alog_profile_args) 2>/dev/null
+            return
+        end
+    end
+end
+
+"#
+    );
+    for f in flags {
+        let mut line = format!("complete -c {program}");
+        if let Some(s) = f.short {
+            line += &format!(" -s {s}");
+        }
+        line += &format!(" -l {}", f.long);
+        match f.long.as_str() {
+            "log-group" => {
+                line += &format!(
+                    " -x -a '({program} --complete log-group (__alog_profile_args) 2>/dev/null)'"
+                )
+            }
+            "log-stream" => line += " -x -a '(__alog_log_streams)'",
+            _ if f.takes_value => line += " -r",
+            _ => {}
+        }
+        if !f.help.is_empty() {
+            line += &format!(
+                " -d '{}'",
+                f.help.replace('\\', "\\\\").replace('\'', "\\'")
+            );
+        }
+        script += &line;
+        script.push('\n');
+    }
+    script
+}
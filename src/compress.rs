@@ -0,0 +1,149 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use log::info;
+
+/// compression given with --compress or picked from the --output-file extension, done in
+/// process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" => Ok(Compression::Gzip),
+            "zstd" => Ok(Compression::Zstd),
+            "xz" => Ok(Compression::Xz),
+            _ => Err(format!(
+                "unknown compression '{s}', expected one of: gzip, zstd, xz"
+            )),
+        }
+    }
+}
+
+impl Compression {
+    /// None for extensions that aren't compressed formats
+    pub fn from_path(path: &Path) -> Option<Compression> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            "xz" => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+}
+
+/// zstd's and xz's default levels, what their command line tools use
+const ZSTD_LEVEL: i32 = 3;
+const XZ_LEVEL: u32 = 6;
+
+enum Encoder {
+    Gzip(flate2::write::GzEncoder<File>),
+    Zstd(zstd::stream::write::Encoder<'static, File>),
+    Xz(xz2::write::XzEncoder<File>),
+}
+
+/// a file written compressed through Write
+pub struct Compressor {
+    encoder: Encoder,
+    path: String,
+}
+
+impl Compressor {
+    /// start compressing into path, creating it
+    pub fn create(path: &Path, compression: Compression) -> Result<Compressor, String> {
+        info!("compressing {} with {compression:?}", path.display());
+        let file =
+            File::create(path).map_err(|e| format!("unable to create {}: {e}", path.display()))?;
+        let encoder = match compression {
+            Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                file,
+                flate2::Compression::default(),
+            )),
+            Compression::Zstd => Encoder::Zstd(
+                zstd::stream::write::Encoder::new(file, ZSTD_LEVEL)
+                    .map_err(|e| format!("unable to compress {}: {e}", path.display()))?,
+            ),
+            Compression::Xz => Encoder::Xz(xz2::write::XzEncoder::new(file, XZ_LEVEL)),
+        };
+        Ok(Compressor {
+            encoder,
+            path: path.display().to_string(),
+        })
+    }
+
+    /// write the end of the compressed stream and flush the file
+    pub fn finish(self) -> Result<(), String> {
+        let file = match self.encoder {
+            Encoder::Gzip(encoder) => encoder.finish(),
+            Encoder::Zstd(encoder) => encoder.finish(),
+            Encoder::Xz(encoder) => encoder.finish(),
+        };
+        file.and_then(|mut file| file.flush())
+            .map_err(|e| format!("unable to write {}: {e}", self.path))
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self.encoder {
+            Encoder::Gzip(ref mut w) => w,
+            Encoder::Zstd(ref mut w) => w,
+            Encoder::Xz(ref mut w) => w,
+        }
+    }
+}
+
+impl Write for Compressor {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer().flush()
+    }
+}
+
+/// write contents to path, compressed
+pub fn write_compressed(
+    path: &Path,
+    contents: &[u8],
+    compression: Compression,
+) -> Result<(), String> {
+    let mut compressor = Compressor::create(path, compression)?;
+    compressor
+        .write_all(contents)
+        .map_err(|e| format!("unable to write {}: {e}", path.display()))?;
+    compressor.finish()
+}
+
+/// path read back, decompressed when its extension is that of a compressed format
+pub fn open(path: &Path) -> Result<Box<dyn Read>, String> {
+    let file = File::open(path).map_err(|e| format!("unable to open {}: {e}", path.display()))?;
+    match Compression::from_path(path) {
+        Some(compression) => decoder(file, compression),
+        None => Ok(Box::new(file)),
+    }
+}
+
+/// a reader of what reader holds decompressed, e.g. the gzipped objects of an S3 export.
+/// concatenated members (as gzip -c appends) are read one after the other
+pub fn decoder<'a>(
+    reader: impl Read + 'a,
+    compression: Compression,
+) -> Result<Box<dyn Read + 'a>, String> {
+    Ok(match compression {
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(
+            zstd::stream::read::Decoder::new(reader)
+                .map_err(|e| format!("unable to start zstd decompression: {e}"))?,
+        ),
+        Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+    })
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use log::debug;
+use serde::Deserialize;
+
+use crate::color::ThemeConfig;
+
+/// user config, read from `$XDG_CONFIG_HOME/aws-logs/config.toml`
+/// (falling back to `~/.config/aws-logs/config.toml`)
+#[derive(Deserialize, Debug, Default)]
+pub struct Config {
+    /// per-level output styles
+    #[serde(rename = "theme", default)]
+    pub theme: ThemeConfig,
+
+    /// defaults applied whenever a log group is targeted, keyed by log group name
+    #[serde(rename = "groups", default)]
+    pub groups: HashMap<String, GroupDefaults>,
+
+    /// the context used when --context isn't given
+    #[serde(rename = "context")]
+    pub context: Option<String>,
+
+    /// defaults for every invocation, a context's settings override them
+    #[serde(rename = "defaults", default)]
+    pub defaults: ContextDefaults,
+
+    /// named sets of defaults, e.g. one per account, picked with --context or `context`
+    #[serde(rename = "contexts", default)]
+    pub contexts: HashMap<String, ContextDefaults>,
+
+    /// saved fetches and queries, run with `alog run NAME`
+    #[serde(rename = "presets", default)]
+    pub presets: HashMap<String, Preset>,
+}
+
+/// what to fetch and how, each used only when its flag isn't given, e.g.
+///
+/// ```toml
+/// [presets.payment-errors]
+/// log_group = "/ecs/payments"
+/// stream_regex = "^ecs/payments/"
+/// filter_pattern = "ERROR"
+/// since = "2h"
+/// ```
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct Preset {
+    /// like --log-group
+    #[serde(rename = "log_group")]
+    pub log_group: Option<String>,
+
+    /// like --log-stream
+    #[serde(rename = "log_stream")]
+    pub log_stream: Option<String>,
+
+    /// like --stream-regex
+    #[serde(rename = "stream_regex")]
+    pub stream_regex: Option<String>,
+
+    /// like --filter-pattern
+    #[serde(rename = "filter_pattern")]
+    pub filter_pattern: Option<String>,
+
+    /// like --grep
+    #[serde(rename = "grep")]
+    pub grep: Option<String>,
+
+    /// like --level
+    #[serde(rename = "level")]
+    pub level: Option<String>,
+
+    /// like --since
+    #[serde(rename = "since")]
+    pub since: Option<String>,
+
+    /// like --format
+    #[serde(rename = "format")]
+    pub format: Option<String>,
+
+    /// like --query
+    #[serde(rename = "query")]
+    pub query: Option<String>,
+}
+
+/// where to look and how to print when the flags don't say, e.g.
+///
+/// ```toml
+/// context = "prod"
+///
+/// [defaults]
+/// format = "text"
+///
+/// [contexts.prod]
+/// profile = "prod"
+/// region = "eu-west-1"
+/// log_group = "/ecs/payments"
+/// ```
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct ContextDefaults {
+    /// like --region
+    #[serde(rename = "region")]
+    pub region: Option<String>,
+
+    /// like --profile
+    #[serde(rename = "profile")]
+    pub profile: Option<String>,
+
+    /// like --log-group
+    #[serde(rename = "log_group")]
+    pub log_group: Option<String>,
+
+    /// like --format
+    #[serde(rename = "format")]
+    pub format: Option<String>,
+
+    /// like --preview-lines
+    #[serde(rename = "preview_lines")]
+    pub preview_lines: Option<u32>,
+
+    /// like --preview-streams
+    #[serde(rename = "preview_streams")]
+    pub preview_streams: Option<u32>,
+}
+
+impl ContextDefaults {
+    /// these settings, with the ones missing taken from fallback
+    fn or(self, fallback: &ContextDefaults) -> ContextDefaults {
+        ContextDefaults {
+            region: self.region.or_else(|| fallback.region.clone()),
+            profile: self.profile.or_else(|| fallback.profile.clone()),
+            log_group: self.log_group.or_else(|| fallback.log_group.clone()),
+            format: self.format.or_else(|| fallback.format.clone()),
+            preview_lines: self.preview_lines.or(fallback.preview_lines),
+            preview_streams: self.preview_streams.or(fallback.preview_streams),
+        }
+    }
+}
+
+impl Config {
+    /// the defaults of the named context (or the configured `context`) over `[defaults]`
+    pub fn context_defaults(&self, name: Option<&str>) -> Result<ContextDefaults, String> {
+        let Some(name) = name.or(self.context.as_deref()) else {
+            return Ok(self.defaults.clone());
+        };
+        let context = self
+            .contexts
+            .get(name)
+            .ok_or_else(|| format!("no context named '{name}' in the config file"))?;
+        debug!("using config context: {name}");
+        Ok(context.clone().or(&self.defaults))
+    }
+}
+
+/// settings for one log group, each used only when its flag isn't given, e.g.
+///
+/// ```toml
+/// [groups."/ecs/payments"]
+/// redact_rules = "/etc/aws-logs/pan.yaml"
+/// level = "info+"
+/// format = "ndjson"
+/// ```
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct GroupDefaults {
+    /// like --format, over the context's
+    #[serde(rename = "format")]
+    pub format: Option<String>,
+
+    /// like --filter-pattern
+    #[serde(rename = "filter_pattern")]
+    pub filter_pattern: Option<String>,
+
+    /// like --grep
+    #[serde(rename = "grep")]
+    pub grep: Option<String>,
+
+    /// like --jq
+    #[serde(rename = "jq")]
+    pub jq: Option<String>,
+
+    /// like --extract
+    #[serde(rename = "extract")]
+    pub extract: Option<String>,
+
+    /// like --time-format
+    #[serde(rename = "time_format")]
+    pub time_format: Option<String>,
+
+    /// like --timezone
+    #[serde(rename = "timezone")]
+    pub timezone: Option<String>,
+
+    /// like --level
+    #[serde(rename = "level")]
+    pub level: Option<String>,
+
+    /// like --redact-rules
+    #[serde(rename = "redact_rules")]
+    pub redact_rules: Option<String>,
+
+    /// like --rules
+    #[serde(rename = "rules")]
+    pub rules: Option<String>,
+
+    /// like --stitch-split-events
+    #[serde(rename = "stitch_split_events")]
+    pub stitch_split_events: Option<bool>,
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("aws-logs").join("config.toml"))
+}
+
+/// where locally recorded data (history, bookmarks, ...) lives: `$XDG_STATE_HOME/aws-logs`,
+/// falling back to `~/.local/state/aws-logs`
+pub fn state_dir() -> Option<PathBuf> {
+    let state_dir = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?)
+            .join(".local")
+            .join("state"),
+    };
+    Some(state_dir.join("aws-logs"))
+}
+
+/// where disposable data (cached listings, ...) lives: `$XDG_CACHE_HOME/aws-logs`,
+/// falling back to `~/.cache/aws-logs`
+pub fn cache_dir() -> Option<PathBuf> {
+    let cache_dir = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(cache_dir.join("aws-logs"))
+}
+
+/// the event store of --cache, `alog import`, `alog search` and --offline unless --archive-dir
+/// says otherwise: `events.sqlite` in the cache dir
+pub fn event_store_path() -> Option<PathBuf> {
+    Some(cache_dir()?.join("events.sqlite"))
+}
+
+/// load the config file, a missing file is the same as an empty one
+pub fn load_config() -> Result<Config, String> {
+    let path = match config_path() {
+        Some(path) if path.exists() => path,
+        _ => {
+            debug!("no config file found, using defaults");
+            return Ok(Config::default());
+        }
+    };
+    debug!("loading config file: {}", path.display());
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("unable to read config file {}: {e}", path.display()))?;
+    toml::from_str(&contents).map_err(|e| format!("invalid config file {}: {e}", path.display()))
+}
@@ -0,0 +1,61 @@
+use std::process::Command;
+use std::time::Duration;
+
+use log::info;
+
+/// the console fragment encodes values twice: percent-encode, then replace `%` with `$25`
+fn console_escape(value: &str) -> String {
+    let mut out = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("$25{byte:02X}")),
+        }
+    }
+    out
+}
+
+/// CloudWatch console URL for a log group, or for a single stream's events when one is given
+pub fn log_console_url(
+    region: &str,
+    log_group: &str,
+    log_stream: Option<&str>,
+    since: Option<Duration>,
+) -> String {
+    let mut fragment = format!("logsV2:log-groups/log-group/{}", console_escape(log_group));
+    if log_stream.is_some() || since.is_some() {
+        fragment.push_str("/log-events");
+    }
+    if let Some(stream) = log_stream {
+        fragment.push_str(&format!("/{}", console_escape(stream)));
+    }
+    if let Some(since) = since {
+        // relative start, in negative millis
+        fragment.push_str(&format!("$3Fstart$3D-{}", since.as_millis()));
+    }
+    format!("https://{region}.console.aws.amazon.com/cloudwatch/home?region={region}#{fragment}")
+}
+
+/// open a url with the system browser
+pub fn open_in_browser(url: &str) -> Result<(), String> {
+    info!("opening: {url}");
+    let mut cmd = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else {
+        Command::new("xdg-open")
+    };
+    let status = cmd
+        .arg(url)
+        .status()
+        .map_err(|e| format!("unable to launch browser: {e}"))?;
+    if !status.success() {
+        return Err(format!("browser launcher exited with {status}"));
+    }
+    Ok(())
+}
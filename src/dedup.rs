@@ -0,0 +1,30 @@
+use crate::Event;
+
+/// collapse runs of consecutive events with the same message (and group and stream) into the first of
+/// them, annotated with how many there were like `uniq -c`, e.g. `retrying request ×38`
+pub fn dedup_events(events: Vec<Event>) -> Vec<Event> {
+    let mut out: Vec<Event> = Vec::with_capacity(events.len());
+    let mut count = 0;
+    for event in events {
+        if let Some(last) = out.last() {
+            if last.log_group == event.log_group
+                && last.log_stream == event.log_stream
+                && last.message.trim() == event.message.trim()
+            {
+                count += 1;
+                continue;
+            }
+        }
+        annotate(out.last_mut(), count);
+        out.push(event);
+        count = 1;
+    }
+    annotate(out.last_mut(), count);
+    out
+}
+
+fn annotate(event: Option<&mut Event>, count: usize) {
+    if let (Some(event), true) = (event, count > 1) {
+        event.message = format!("{} ×{count}", event.message.trim_end());
+    }
+}
@@ -0,0 +1,133 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+use log::info;
+
+/// who output files get encrypted to. age recipients are recognized by their prefix,
+/// anything else is treated as a gpg key id / fingerprint / email
+#[derive(Debug, Clone)]
+pub enum Recipients {
+    Age(Vec<String>),
+    Gpg(Vec<String>),
+}
+
+fn is_age_recipient(recipient: &str) -> bool {
+    recipient.starts_with("age1")
+        || recipient.starts_with("ssh-ed25519 ")
+        || recipient.starts_with("ssh-rsa ")
+}
+
+impl Recipients {
+    /// build from the --encrypt-to values, None when encryption wasn't requested
+    pub fn from_args(recipients: &[String]) -> Result<Option<Recipients>, String> {
+        if recipients.is_empty() {
+            return Ok(None);
+        }
+        let age_count = recipients.iter().filter(|r| is_age_recipient(r)).count();
+        if age_count == recipients.len() {
+            Ok(Some(Recipients::Age(recipients.to_vec())))
+        } else if age_count == 0 {
+            Ok(Some(Recipients::Gpg(recipients.to_vec())))
+        } else {
+            Err("--encrypt-to cannot mix age recipients and gpg keys".to_string())
+        }
+    }
+
+    fn command(&self, path: &Path) -> Command {
+        match self {
+            Recipients::Age(recipients) => {
+                let mut cmd = Command::new("age");
+                cmd.arg("--encrypt");
+                for r in recipients {
+                    cmd.arg("--recipient").arg(r);
+                }
+                cmd.arg("--output").arg(path);
+                cmd
+            }
+            Recipients::Gpg(recipients) => {
+                let mut cmd = Command::new("gpg");
+                cmd.args(["--batch", "--yes", "--encrypt"]);
+                for r in recipients {
+                    cmd.arg("--recipient").arg(r);
+                }
+                cmd.arg("--output").arg(path);
+                cmd
+            }
+        }
+    }
+
+    fn program(&self) -> &'static str {
+        match self {
+            Recipients::Age(_) => "age",
+            Recipients::Gpg(_) => "gpg",
+        }
+    }
+}
+
+/// a file being written through `age` or `gpg`, plaintext never touches the disk.
+/// call finish() to flush and wait for the encryption to complete
+pub struct EncryptedFile {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    program: &'static str,
+}
+
+impl EncryptedFile {
+    pub fn create(path: &Path, recipients: &Recipients) -> Result<EncryptedFile, String> {
+        let program = recipients.program();
+        info!("encrypting {} with {program}", path.display());
+        let mut child = recipients
+            .command(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("unable to run {program} for --encrypt-to: {e}"))?;
+        let stdin = child.stdin.take();
+        Ok(EncryptedFile {
+            child,
+            stdin,
+            program,
+        })
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        // closing stdin signals end of input
+        drop(self.stdin.take());
+        let status = self
+            .child
+            .wait()
+            .map_err(|e| format!("failed waiting for {}: {e}", self.program))?;
+        if !status.success() {
+            return Err(format!("{} exited with {status}", self.program));
+        }
+        Ok(())
+    }
+}
+
+impl Write for EncryptedFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.stdin.as_mut() {
+            Some(stdin) => stdin.write(buf),
+            None => Err(std::io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.stdin.as_mut() {
+            Some(stdin) => stdin.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// write contents to path, encrypted to the given recipients
+pub fn write_encrypted(
+    path: &Path,
+    contents: &[u8],
+    recipients: &Recipients,
+) -> Result<(), String> {
+    let mut file = EncryptedFile::create(path, recipients)?;
+    file.write_all(contents)
+        .map_err(|e| format!("unable to write {}: {e}", path.display()))?;
+    file.finish()
+}
@@ -0,0 +1,79 @@
+use std::error::Error;
+
+use aws_smithy_types::error::display::DisplayErrorContext;
+use thiserror::Error;
+
+/// what the SDK's error chain says when the IAM Identity Center (SSO) session behind a profile
+/// has expired or `aws sso login` was never run for it
+const SESSION_ERRORS: &[&str] = &[
+    "SSO token",
+    "SSO session",
+    "UnauthorizedException",
+    "Session token not found or invalid",
+];
+
+/// whether error, or anything it was caused by, is an expired or missing SSO session
+pub fn is_session_error(error: &(dyn Error + 'static)) -> bool {
+    let mut cause = Some(error);
+    while let Some(e) = cause {
+        let message = e.to_string();
+        if SESSION_ERRORS.iter().any(|marker| message.contains(marker)) {
+            return true;
+        }
+        cause = e.source();
+    }
+    false
+}
+
+/// why fetching or listing logs failed, each kind exits with its own code
+#[derive(Debug, Error)]
+pub enum AwsLogsError {
+    /// an AWS call failed, or answered with something unusable
+    #[error("{0}")]
+    Sdk(String),
+
+    /// the IAM Identity Center (SSO) session behind the credentials has expired
+    #[error("{0}: the AWS SSO session has expired, run `aws sso login` (with --profile for a named profile) and try again")]
+    SsoSessionExpired(String),
+
+    /// a field CloudWatch Logs always sends was missing from a response
+    #[error("{0} missing from the CloudWatch Logs response")]
+    MissingField(&'static str),
+
+    /// an argument that can't be used as given
+    #[error("{0}")]
+    InvalidArgument(String),
+
+    /// the local event cache (--cache) couldn't be read or written
+    #[error("{0}")]
+    Cache(String),
+
+    /// a local file, the terminal or a program run along the way failed
+    #[error("{0}")]
+    Io(String),
+}
+
+impl AwsLogsError {
+    /// a failed SDK call, context says what was being done. the message includes the whole
+    /// chain of causes, an SDK error's own message is often just "service error"
+    pub fn sdk(context: &str, error: &(dyn Error + 'static)) -> AwsLogsError {
+        if is_session_error(error) {
+            AwsLogsError::SsoSessionExpired(context.to_string())
+        } else {
+            AwsLogsError::Sdk(format!("{context}: {}", DisplayErrorContext(error)))
+        }
+    }
+
+    /// 1 for failed calls, cache and local errors, 2 for invalid arguments (like clap), 3 for an
+    /// expired SSO session
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AwsLogsError::Sdk(_)
+            | AwsLogsError::MissingField(_)
+            | AwsLogsError::Cache(_)
+            | AwsLogsError::Io(_) => 1,
+            AwsLogsError::InvalidArgument(_) => 2,
+            AwsLogsError::SsoSessionExpired(_) => 3,
+        }
+    }
+}
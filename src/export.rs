@@ -0,0 +1,348 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use aws_sdk_cloudwatchlogs::types::ExportTaskStatusCode;
+use log::info;
+
+use crate::compress::{decoder, Compression};
+use crate::error::AwsLogsError;
+use crate::timestamps::format_display;
+use crate::Event;
+
+/// how often a running export task is checked on
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// where `alog export` writes to, `s3://bucket` or `s3://bucket/prefix`
+#[derive(Debug, Clone)]
+pub struct S3Destination {
+    pub bucket: String,
+    pub prefix: Option<String>,
+}
+
+impl FromStr for S3Destination {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = s
+            .strip_prefix("s3://")
+            .ok_or_else(|| format!("expected an s3://bucket/prefix destination, got '{s}'"))?;
+        let (bucket, prefix) = match path.split_once('/') {
+            Some((bucket, prefix)) => (bucket, Some(prefix.trim_end_matches('/'))),
+            None => (path, None),
+        };
+        if bucket.is_empty() {
+            return Err(format!("no bucket in '{s}'"));
+        }
+        Ok(S3Destination {
+            bucket: bucket.to_string(),
+            prefix: prefix.filter(|p| !p.is_empty()).map(str::to_string),
+        })
+    }
+}
+
+impl S3Destination {
+    /// the key prefix CloudWatch puts the objects of an export task under, followed by
+    /// `<stream>/000000.gz`, `<stream>/000001.gz`, ...
+    fn task_prefix(&self, task_id: &str) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}/{task_id}/"),
+            // CloudWatch's default when no prefix is given
+            None => format!("exportedlogs/{task_id}/"),
+        }
+    }
+
+    pub fn task_url(&self, task_id: &str) -> String {
+        format!("s3://{}/{}", self.bucket, self.task_prefix(task_id))
+    }
+}
+
+/// an export task `alog import` reads: its s3:// url as `alog export` prints it (the
+/// destination followed by the task id), or a directory its objects were copied to, e.g. with
+/// `aws s3 sync`
+#[derive(Debug, Clone)]
+pub enum ExportLocation {
+    S3(S3Destination),
+    Dir(PathBuf),
+}
+
+impl FromStr for ExportLocation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.starts_with("s3://") {
+            return Ok(ExportLocation::Dir(PathBuf::from(s)));
+        }
+        let task = s.parse::<S3Destination>()?;
+        if task.prefix.is_none() {
+            return Err(format!(
+                "expected the url of an export task, e.g. s3://{}/exportedlogs/<task id>, got '{s}'",
+                task.bucket
+            ));
+        }
+        Ok(ExportLocation::S3(task))
+    }
+}
+
+/// start exporting the events of a log group in [from, to) (epoch millis) to destination, only
+/// those of streams starting with stream_prefix when given. returns the task id. the bucket
+/// policy has to let the CloudWatch Logs service write to it, and an account can only run one
+/// export task at a time
+pub async fn create_export(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    stream_prefix: Option<&str>,
+    from: i64,
+    to: i64,
+    destination: &S3Destination,
+) -> Result<String, AwsLogsError> {
+    let task_name = format!(
+        "alog {log_group} {} to {}",
+        format_display(from),
+        format_display(to)
+    );
+    info!("creating export task: {task_name}");
+    let response = client
+        .create_export_task()
+        .task_name(task_name)
+        .log_group_name(log_group)
+        .set_log_stream_name_prefix(stream_prefix.map(str::to_string))
+        .from(from)
+        .to(to)
+        .destination(&destination.bucket)
+        .set_destination_prefix(destination.prefix.clone())
+        .send()
+        .await
+        .map_err(|e| AwsLogsError::sdk("failed to create export task", &e))?;
+    response.task_id.ok_or(AwsLogsError::MissingField("taskId"))
+}
+
+/// poll an export task until it has completed, an error if it failed or was cancelled.
+/// on_status is told each status the task moves to
+pub async fn wait_for_export(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    task_id: &str,
+    mut on_status: impl FnMut(&ExportTaskStatusCode),
+) -> Result<(), AwsLogsError> {
+    let mut last_code = None;
+    loop {
+        let response = client
+            .describe_export_tasks()
+            .task_id(task_id)
+            .send()
+            .await
+            .map_err(|e| AwsLogsError::sdk("failed to describe export task", &e))?;
+        let status = response
+            .export_tasks
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .and_then(|task| task.status)
+            .ok_or(AwsLogsError::MissingField("exportTasks"))?;
+        let code = status.code.ok_or(AwsLogsError::MissingField("status"))?;
+        let message = status.message.unwrap_or_default();
+        if last_code.as_ref() != Some(&code) {
+            on_status(&code);
+        }
+        match code {
+            ExportTaskStatusCode::Completed => return Ok(()),
+            ExportTaskStatusCode::Failed | ExportTaskStatusCode::Cancelled => {
+                return Err(AwsLogsError::Sdk(format!(
+                    "export task {task_id} {}: {message}",
+                    code.as_str().to_lowercase()
+                )));
+            }
+            _ => {}
+        }
+        last_code = Some(code);
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// the events of an exported object, lines like `2024-03-13T16:28:28.412Z message`. lines that
+/// don't start with a timestamp continue the message before them
+fn parse_export_object(text: &str, log_stream: &str) -> Vec<Event> {
+    let mut events: Vec<Event> = vec![];
+    for line in text.lines() {
+        let timestamp = line.split_once(' ').and_then(|(time, message)| {
+            let time = time.parse::<jiff::Timestamp>().ok()?;
+            Some((time.as_millisecond(), message))
+        });
+        match (timestamp, events.last_mut()) {
+            (Some((timestamp, message)), _) => events.push(Event {
+                timestamp,
+                message: message.to_string(),
+                // exports don't keep the ingestion time
+                ingestion_time: timestamp,
+                log_stream: Some(log_stream.to_string()),
+                log_group: None,
+            }),
+            (None, Some(event)) => {
+                event.message.push('\n');
+                event.message.push_str(line);
+            }
+            (None, None) => {}
+        }
+    }
+    events
+}
+
+/// the events of an exported object of log_stream, named name in errors, in time order
+fn read_export_object(
+    object: impl Read,
+    log_stream: &str,
+    name: &str,
+) -> Result<Vec<Event>, AwsLogsError> {
+    let mut text = vec![];
+    decoder(object, Compression::Gzip)
+        .map_err(AwsLogsError::Io)?
+        .read_to_end(&mut text)
+        .map_err(|e| AwsLogsError::Io(format!("unable to decompress {name}: {e}")))?;
+    let mut events = parse_export_object(&String::from_utf8_lossy(&text), log_stream);
+    // stable, events with the same timestamp keep their order
+    events.sort_by_key(|e| e.timestamp);
+    Ok(events)
+}
+
+/// download the gzipped objects of a completed export task one at a time, handing the events
+/// of each to write, labeled with their stream and in time order. the objects of a stream
+/// follow each other, streams one after the other. on_download is told the number (counted
+/// from 1) of each object as its download starts, the number of objects and its key. returns
+/// how many events there were
+pub async fn download_export<F>(
+    s3: &aws_sdk_s3::Client,
+    destination: &S3Destination,
+    task_id: &str,
+    write: F,
+    on_download: impl FnMut(usize, usize, &str),
+) -> Result<usize, AwsLogsError>
+where
+    F: FnMut(Vec<Event>) -> Result<(), AwsLogsError>,
+{
+    let task_prefix = destination.task_prefix(task_id);
+    download_objects(s3, &destination.bucket, &task_prefix, write, on_download).await
+}
+
+/// download_export for the url of an export task, its destination's prefix ending in the task id
+pub async fn download_export_task<F>(
+    s3: &aws_sdk_s3::Client,
+    task: &S3Destination,
+    write: F,
+    on_download: impl FnMut(usize, usize, &str),
+) -> Result<usize, AwsLogsError>
+where
+    F: FnMut(Vec<Event>) -> Result<(), AwsLogsError>,
+{
+    let task_prefix = format!("{}/", task.prefix.as_deref().unwrap_or_default());
+    download_objects(s3, &task.bucket, &task_prefix, write, on_download).await
+}
+
+async fn download_objects<F>(
+    s3: &aws_sdk_s3::Client,
+    bucket: &str,
+    task_prefix: &str,
+    mut write: F,
+    mut on_download: impl FnMut(usize, usize, &str),
+) -> Result<usize, AwsLogsError>
+where
+    F: FnMut(Vec<Event>) -> Result<(), AwsLogsError>,
+{
+    let mut keys = vec![];
+    let mut continuation_token = None;
+    loop {
+        let response = s3
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(task_prefix)
+            .set_continuation_token(continuation_token.take())
+            .send()
+            .await
+            .map_err(|e| {
+                AwsLogsError::sdk(&format!("failed to list s3://{bucket}/{task_prefix}"), &e)
+            })?;
+        keys.extend(
+            response
+                .contents
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|object| object.key)
+                .filter(|key| key.ends_with(".gz")),
+        );
+        continuation_token = response.next_continuation_token;
+        if continuation_token.is_none() {
+            break;
+        }
+    }
+    // the objects of a stream are numbered in order, so sorting keys keeps each stream in order
+    keys.sort();
+    let mut written = 0;
+    for (i, key) in keys.iter().enumerate() {
+        let Some((log_stream, _)) = key[task_prefix.len()..].rsplit_once('/') else {
+            continue;
+        };
+        on_download(i + 1, keys.len(), key);
+        let url = format!("s3://{bucket}/{key}");
+        let context = format!("failed to download {url}");
+        let body = s3
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| AwsLogsError::sdk(&context, &e))?
+            .body
+            .collect()
+            .await
+            .map_err(|e| AwsLogsError::sdk(&context, &e))?
+            .into_bytes();
+        let events = read_export_object(&body[..], log_stream, &url)?;
+        written += events.len();
+        write(events)?;
+    }
+    Ok(written)
+}
+
+/// the `.gz` files under dir, relative to it with `/` between their parts
+fn export_files(dir: &Path, under: &str, files: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = format!("{under}{}", entry.file_name().to_string_lossy());
+        if entry.file_type()?.is_dir() {
+            export_files(&entry.path(), &format!("{name}/"), files)?;
+        } else if name.ends_with(".gz") {
+            files.push(name);
+        }
+    }
+    Ok(())
+}
+
+/// download_export for an export task copied to dir, its streams' directories in it. on_read is
+/// told the number of each object as it's read, the number of objects and its path
+pub fn read_export_dir<F>(
+    dir: &Path,
+    mut write: F,
+    mut on_read: impl FnMut(usize, usize, &str),
+) -> Result<usize, AwsLogsError>
+where
+    F: FnMut(Vec<Event>) -> Result<(), AwsLogsError>,
+{
+    let mut files = vec![];
+    export_files(dir, "", &mut files)
+        .map_err(|e| AwsLogsError::Io(format!("unable to read {}: {e}", dir.display())))?;
+    files.sort();
+    let mut written = 0;
+    for (i, file) in files.iter().enumerate() {
+        let Some((log_stream, _)) = file.rsplit_once('/') else {
+            continue;
+        };
+        let path = dir.join(file);
+        on_read(i + 1, files.len(), &path.display().to_string());
+        let object = std::fs::File::open(&path)
+            .map_err(|e| AwsLogsError::Io(format!("unable to open {}: {e}", path.display())))?;
+        let events = read_export_object(object, log_stream, &path.display().to_string())?;
+        written += events.len();
+        write(events)?;
+    }
+    Ok(written)
+}
@@ -0,0 +1,97 @@
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::records::csv_field;
+
+/// the comma separated fields of --extract, each a dotted path into the message like
+/// `request.user.id`
+#[derive(Debug, Clone)]
+pub struct Fields(pub Vec<Vec<String>>);
+
+impl FromStr for Fields {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s
+            .split(',')
+            .map(|field| {
+                let path = field
+                    .trim()
+                    .split('.')
+                    .map(str::to_string)
+                    .collect::<Vec<String>>();
+                if path.iter().any(String::is_empty) {
+                    return Err(format!("invalid field '{}'", field.trim()));
+                }
+                Ok(path)
+            })
+            .collect::<Result<Vec<Vec<String>>, String>>()?;
+        Ok(Fields(fields))
+    }
+}
+
+/// what goes between the extracted values of a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    Tab,
+    /// values are quoted like csv fields when they need it
+    Comma,
+}
+
+impl FromStr for Separator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tab" => Ok(Separator::Tab),
+            "comma" => Ok(Separator::Comma),
+            other => Err(format!(
+                "unknown separator '{other}', expected tab or comma"
+            )),
+        }
+    }
+}
+
+/// which fields of json messages to output instead of the whole message
+#[derive(Debug, Clone)]
+pub struct Extraction {
+    pub fields: Fields,
+    pub separator: Separator,
+}
+
+/// the value at path, strings as they are and anything else as json. array elements are
+/// picked by index, e.g. `items.0.id`
+fn lookup<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, key| match value {
+        Value::Object(object) => object.get(key),
+        Value::Array(array) => array.get(key.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+impl Extraction {
+    /// the selected fields of a json object message, empty for fields it doesn't have. a message
+    /// that isn't a json object is returned as it is
+    pub fn extract(&self, message: &str) -> String {
+        let value = match serde_json::from_str::<Value>(message.trim()) {
+            Ok(value @ Value::Object(_)) => value,
+            _ => return message.to_string(),
+        };
+        let values = self.fields.0.iter().map(|path| match lookup(&value, path) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        });
+        match self.separator {
+            Separator::Tab => values
+                .map(|v| v.replace(['\t', '\n'], " "))
+                .collect::<Vec<String>>()
+                .join("\t"),
+            Separator::Comma => values
+                .map(|v| csv_field(&v))
+                .collect::<Vec<String>>()
+                .join(","),
+        }
+    }
+}
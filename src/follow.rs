@@ -0,0 +1,80 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use log::debug;
+
+use crate::error::AwsLogsError;
+use crate::timestamps::now_millis;
+use crate::{fetch_single_log_page, Event};
+
+/// polls a log stream for events newer than the ones already returned, keeping the forward
+/// token between polls so nothing is fetched twice
+pub struct Follower<'a> {
+    client: &'a aws_sdk_cloudwatchlogs::Client,
+    log_group: &'a str,
+    log_stream: &'a str,
+    start_time: i64,
+    interval: Duration,
+    token: Option<String>,
+    /// whether the last page reached the end of the stream, so the next poll waits first
+    caught_up: bool,
+    interrupted: Pin<Box<dyn Future<Output = std::io::Result<()>> + Send>>,
+}
+
+impl<'a> Follower<'a> {
+    /// start_time is where following begins (epoch millis), now when None
+    pub fn new(
+        client: &'a aws_sdk_cloudwatchlogs::Client,
+        log_group: &'a str,
+        log_stream: &'a str,
+        start_time: Option<i64>,
+        interval: Duration,
+    ) -> Follower<'a> {
+        Follower {
+            client,
+            log_group,
+            log_stream,
+            start_time: start_time.unwrap_or_else(now_millis),
+            interval,
+            token: None,
+            caught_up: false,
+            interrupted: Box::pin(tokio::signal::ctrl_c()),
+        }
+    }
+
+    /// the next non-empty batch of events, waiting for them as long as it takes.
+    /// None once Ctrl-C is pressed
+    pub async fn next_batch(&mut self) -> Result<Option<Vec<Event>>, AwsLogsError> {
+        loop {
+            if self.caught_up {
+                tokio::select! {
+                    _ = tokio::time::sleep(self.interval) => {}
+                    _ = &mut self.interrupted => return Ok(None),
+                }
+            }
+            let request = fetch_single_log_page(
+                self.client,
+                self.log_group,
+                self.log_stream,
+                self.token.as_deref(),
+                None,
+                None,
+                Some(self.start_time),
+                None,
+            );
+            let page = tokio::select! {
+                page = request => page?,
+                _ = &mut self.interrupted => return Ok(None),
+            };
+            // the forward token repeats once the end of the stream is reached
+            self.caught_up = page.events.is_empty()
+                || self.token.as_deref() == Some(page.next_forward_token.as_str());
+            self.token = Some(page.next_forward_token);
+            debug!("follow poll: {} new events", page.events.len());
+            if !page.events.is_empty() {
+                return Ok(Some(page.events));
+            }
+        }
+    }
+}
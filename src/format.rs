@@ -0,0 +1,80 @@
+use std::str::FromStr;
+
+/// what shape results are printed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// human readable text
+    #[default]
+    Text,
+    /// Grafana JSON datasource table response, for query results
+    Grafana,
+    /// machine readable JSON, for stream listings and previews
+    Json,
+    /// one JSON object per line, for fetched events
+    Ndjson,
+    /// comma separated values with a header row, for fetched events
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "grafana" => Ok(OutputFormat::Grafana),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!(
+                "unknown format '{s}', expected one of: text, grafana, json, ndjson, csv"
+            )),
+        }
+    }
+}
+
+/// human readable byte size, e.g. 1.5MB
+pub fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1}{}", UNITS[unit])
+}
+
+/// a byte size like 100MB, 1.5G or 4096, in the same 1024-based units as format_bytes
+pub fn parse_bytes(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{s}', expected e.g. 100MB"))?;
+    let exponent = match unit
+        .trim()
+        .to_ascii_uppercase()
+        .trim_end_matches("IB")
+        .trim_end_matches('B')
+    {
+        "" => 0,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        _ => {
+            return Err(format!(
+                "unknown unit in size '{s}', expected B, KB, MB, GB or TB"
+            ))
+        }
+    };
+    let bytes = value * 1024f64.powi(exponent);
+    if bytes < 1.0 {
+        return Err(format!("size '{s}' must be at least 1 byte"));
+    }
+    Ok(bytes as u64)
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use crate::timestamps::{format_display, format_duration};
+use crate::Event;
+
+/// a stretch with no events, between the events at start and end (epoch millis), or between
+/// an edge of the time window and the event closest to it
+#[derive(Debug, Clone, Copy)]
+pub struct Gap {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Gap {
+    /// length without the sign format_duration puts on deltas
+    fn length(&self) -> String {
+        format_duration(self.end - self.start)
+            .trim_start_matches('+')
+            .to_string()
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "no events for {} ({} to {})",
+            self.length(),
+            format_display(self.start),
+            format_display(self.end)
+        )
+    }
+}
+
+/// gaps longer than threshold between consecutive time sorted events, and between the edges of
+/// the time window they were fetched from (epoch millis), when known, and the first and last
+/// event. a window without any events is one gap
+pub fn find_gaps(
+    events: &[Event],
+    threshold: Duration,
+    window_start: Option<i64>,
+    window_end: Option<i64>,
+) -> Vec<Gap> {
+    let threshold_ms = threshold.as_millis() as i64;
+    let times = window_start
+        .into_iter()
+        .chain(events.iter().map(|e| e.timestamp))
+        .chain(window_end)
+        .collect::<Vec<i64>>();
+    times
+        .windows(2)
+        .filter(|pair| pair[1] - pair[0] > threshold_ms)
+        .map(|pair| Gap {
+            start: pair[0],
+            end: pair[1],
+        })
+        .collect()
+}
+
+fn marker(gap: &Gap) -> Event {
+    Event {
+        timestamp: gap.start,
+        message: format!("----- {} -----", gap.describe()),
+        ingestion_time: gap.start,
+        log_stream: None,
+        log_group: None,
+    }
+}
+
+/// events with a marker line, timestamped at the gap's start, placed before the first event
+/// after each gap. the gaps can come from a larger set of events than the ones shown, e.g.
+/// before filtering, so silence is measured on everything the stream logged
+pub fn mark_gaps(events: Vec<Event>, gaps: &[Gap]) -> Vec<Event> {
+    let mut marked = Vec::with_capacity(events.len() + gaps.len());
+    let mut gaps = gaps.iter().peekable();
+    for event in events {
+        while let Some(gap) = gaps.next_if(|gap| gap.end <= event.timestamp) {
+            marked.push(marker(gap));
+        }
+        marked.push(event);
+    }
+    marked.extend(gaps.map(marker));
+    marked
+}
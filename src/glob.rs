@@ -0,0 +1,48 @@
+use regex::Regex;
+
+use crate::error::AwsLogsError;
+use crate::{get_sorted_log_group_names, DEFAULT_MAX_GROUP_PAGES};
+
+/// whether a log group name is a glob like `/aws/lambda/orders-*`. `*` and `?` can't be part of
+/// an actual group name
+pub fn is_glob(name: &str) -> bool {
+    name.contains(['*', '?'])
+}
+
+/// a regex matching whole names against a glob, `*` for any run of characters (slashes too) and
+/// `?` for any one
+pub fn glob_regex(pattern: &str) -> Regex {
+    let mut re = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            c => re.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    re.push('$');
+    Regex::new(&re).expect("an escaped glob is a valid regex")
+}
+
+/// the part of a glob before its first wildcard, which every match starts with
+pub fn literal_prefix(pattern: &str) -> Option<&str> {
+    let end = pattern.find(['*', '?']).unwrap_or(pattern.len());
+    Some(&pattern[..end]).filter(|prefix| !prefix.is_empty())
+}
+
+/// the names of the log groups matching a glob, sorted. only groups starting with the glob's
+/// literal prefix are listed
+pub async fn matching_log_groups(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    pattern: &str,
+) -> Result<Vec<String>, AwsLogsError> {
+    let re = glob_regex(pattern);
+    let names = get_sorted_log_group_names(
+        client,
+        literal_prefix(pattern),
+        DEFAULT_MAX_GROUP_PAGES,
+        None,
+    )
+    .await?;
+    Ok(names.into_iter().filter(|name| re.is_match(name)).collect())
+}
@@ -0,0 +1,103 @@
+use jiff::civil::DateTime;
+use jiff::tz::TimeZone;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::insights::{field, Row};
+
+/// one table in a Grafana JSON datasource response, see
+/// https://grafana.com/grafana/plugins/simpod-json-datasource/
+#[derive(Serialize, Debug)]
+pub struct Table {
+    #[serde(rename = "type")]
+    pub table_type: &'static str,
+
+    #[serde(rename = "columns")]
+    pub columns: Vec<Column>,
+
+    #[serde(rename = "rows")]
+    pub rows: Vec<Vec<Value>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Column {
+    #[serde(rename = "text")]
+    pub text: String,
+
+    #[serde(rename = "type")]
+    pub column_type: &'static str,
+}
+
+/// Insights renders timestamps like `2024-05-01 13:05:00.000`, in UTC
+const INSIGHTS_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S.%f";
+
+/// parse an Insights timestamp into epoch millis
+fn parse_insights_time(value: &str) -> Option<i64> {
+    let t = DateTime::strptime(INSIGHTS_TIME_FORMAT, value).ok()?;
+    Some(TimeZone::UTC.to_timestamp(t).ok()?.as_millisecond())
+}
+
+fn column_values<'a>(rows: &'a [Row], name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    rows.iter().map(move |row| field(row, name).unwrap_or(""))
+}
+
+/// convert Insights query result rows into a Grafana table. a column is typed as time when
+/// every value is an Insights timestamp, as number when every value is numeric, and string otherwise
+pub fn table_from_rows(rows: &[Row]) -> Table {
+    let names = rows
+        .first()
+        .map(|row| {
+            row.iter()
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+    let column_types = names
+        .iter()
+        .map(|name| {
+            if column_values(rows, name).all(|v| parse_insights_time(v).is_some()) {
+                "time"
+            } else if column_values(rows, name).all(|v| v.parse::<f64>().is_ok()) {
+                "number"
+            } else {
+                "string"
+            }
+        })
+        .collect::<Vec<&'static str>>();
+    let table_rows = rows
+        .iter()
+        .map(|row| {
+            names
+                .iter()
+                .zip(&column_types)
+                .map(|(name, column_type)| {
+                    let value = field(row, name).unwrap_or("");
+                    match *column_type {
+                        "time" => parse_insights_time(value).into(),
+                        "number" => value
+                            .parse::<i64>()
+                            .map(Value::from)
+                            .or_else(|_| value.parse::<f64>().map(Value::from))
+                            .unwrap_or(Value::Null),
+                        _ => value.into(),
+                    }
+                })
+                .collect::<Vec<Value>>()
+        })
+        .collect::<Vec<Vec<Value>>>();
+    Table {
+        table_type: "table",
+        columns: names
+            .into_iter()
+            .zip(column_types)
+            .map(|(text, column_type)| Column { text, column_type })
+            .collect(),
+        rows: table_rows,
+    }
+}
+
+/// the full response body: a list of tables
+pub fn to_json(rows: &[Row]) -> String {
+    let tables = vec![table_from_rows(rows)];
+    serde_json::to_string_pretty(&tables).expect("grafana table serializes")
+}
@@ -0,0 +1,242 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use log::info;
+
+use crate::error::AwsLogsError;
+
+/// a metric filter's transformation into a CloudWatch metric
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricTransformationConfig {
+    pub metric_name: String,
+    pub metric_namespace: String,
+    pub metric_value: String,
+    pub default_value: Option<f64>,
+    pub unit: Option<String>,
+    pub dimensions: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricFilterConfig {
+    pub name: String,
+    pub pattern: String,
+    pub transformations: Vec<MetricTransformationConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriptionFilterConfig {
+    pub name: String,
+    pub pattern: String,
+    pub destination_arn: String,
+    pub role_arn: Option<String>,
+    pub distribution: Option<String>,
+}
+
+/// the configuration of a log group, as opposed to its contents
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupConfig {
+    pub name: String,
+    pub arn: Option<String>,
+    pub retention_in_days: Option<i32>,
+    pub kms_key_id: Option<String>,
+    pub log_group_class: Option<String>,
+    pub tags: BTreeMap<String, String>,
+    pub subscription_filters: Vec<SubscriptionFilterConfig>,
+    pub metric_filters: Vec<MetricFilterConfig>,
+}
+
+/// fetch the retention, encryption, tags, subscription filters and metric filters of a log group
+pub async fn fetch_group_config(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+) -> Result<GroupConfig, AwsLogsError> {
+    info!("fetch configuration of log group: {log_group}");
+    // there's no single-group describe call, so page through the prefix matches for an exact one
+    let mut next_token: Option<String> = None;
+    let group = loop {
+        let response = client
+            .describe_log_groups()
+            .log_group_name_prefix(log_group)
+            .set_next_token(next_token)
+            .send()
+            .await
+            .map_err(|e| {
+                AwsLogsError::sdk(&format!("failed to describe log group {log_group}"), &e)
+            })?;
+        let found = response
+            .log_groups
+            .unwrap_or_default()
+            .into_iter()
+            .find(|g| g.log_group_name.as_deref() == Some(log_group));
+        next_token = response.next_token;
+        if found.is_some() || next_token.is_none() {
+            break found;
+        }
+    };
+    let group = group
+        .ok_or_else(|| AwsLogsError::InvalidArgument(format!("log group {log_group} not found")))?;
+
+    let tags = match group.log_group_arn {
+        Some(ref arn) => client
+            .list_tags_for_resource()
+            .resource_arn(arn)
+            .send()
+            .await
+            .map_err(|e| AwsLogsError::sdk(&format!("failed to list tags of {log_group}"), &e))?
+            .tags
+            .unwrap_or_default()
+            .into_iter()
+            .collect(),
+        None => BTreeMap::new(),
+    };
+
+    let subscription_filters = client
+        .describe_subscription_filters()
+        .log_group_name(log_group)
+        .send()
+        .await
+        .map_err(|e| {
+            AwsLogsError::sdk(
+                &format!("failed to describe subscription filters of {log_group}"),
+                &e,
+            )
+        })?
+        .subscription_filters
+        .unwrap_or_default()
+        .into_iter()
+        .map(|f| SubscriptionFilterConfig {
+            name: f.filter_name.unwrap_or_default(),
+            pattern: f.filter_pattern.unwrap_or_default(),
+            destination_arn: f.destination_arn.unwrap_or_default(),
+            role_arn: f.role_arn,
+            distribution: f.distribution.map(|d| d.as_str().to_string()),
+        })
+        .collect();
+
+    let mut metric_filters = vec![];
+    let mut next_token: Option<String> = None;
+    loop {
+        let response = client
+            .describe_metric_filters()
+            .log_group_name(log_group)
+            .set_next_token(next_token)
+            .send()
+            .await
+            .map_err(|e| {
+                AwsLogsError::sdk(
+                    &format!("failed to describe metric filters of {log_group}"),
+                    &e,
+                )
+            })?;
+        metric_filters.extend(
+            response
+                .metric_filters
+                .unwrap_or_default()
+                .into_iter()
+                .map(|f| MetricFilterConfig {
+                    name: f.filter_name.unwrap_or_default(),
+                    pattern: f.filter_pattern.unwrap_or_default(),
+                    transformations: f
+                        .metric_transformations
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|t| MetricTransformationConfig {
+                            metric_name: t.metric_name,
+                            metric_namespace: t.metric_namespace,
+                            metric_value: t.metric_value,
+                            default_value: t.default_value,
+                            unit: t.unit.map(|u| u.as_str().to_string()),
+                            dimensions: t.dimensions.unwrap_or_default().into_iter().collect(),
+                        })
+                        .collect(),
+                }),
+        );
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(GroupConfig {
+        name: log_group.to_string(),
+        arn: group.log_group_arn,
+        retention_in_days: group.retention_in_days,
+        kms_key_id: group.kms_key_id,
+        log_group_class: group.log_group_class.map(|c| c.as_str().to_string()),
+        tags,
+        subscription_filters,
+        metric_filters,
+    })
+}
+
+impl GroupConfig {
+    /// flatten into comparable `setting -> value` pairs, e.g. `tag.team` or
+    /// `metric_filter.errors.pattern`. unset settings are left out
+    pub fn settings(&self) -> BTreeMap<String, String> {
+        let mut settings = BTreeMap::new();
+        if let Some(days) = self.retention_in_days {
+            settings.insert("retention_in_days".to_string(), days.to_string());
+        }
+        if let Some(ref key) = self.kms_key_id {
+            settings.insert("kms_key_id".to_string(), key.clone());
+        }
+        if let Some(ref class) = self.log_group_class {
+            settings.insert("log_group_class".to_string(), class.clone());
+        }
+        for (key, value) in &self.tags {
+            settings.insert(format!("tag.{key}"), value.clone());
+        }
+        for f in &self.subscription_filters {
+            let prefix = format!("subscription_filter.{}", f.name);
+            settings.insert(format!("{prefix}.pattern"), f.pattern.clone());
+            settings.insert(
+                format!("{prefix}.destination_arn"),
+                f.destination_arn.clone(),
+            );
+            if let Some(ref role) = f.role_arn {
+                settings.insert(format!("{prefix}.role_arn"), role.clone());
+            }
+            if let Some(ref distribution) = f.distribution {
+                settings.insert(format!("{prefix}.distribution"), distribution.clone());
+            }
+        }
+        for f in &self.metric_filters {
+            let prefix = format!("metric_filter.{}", f.name);
+            settings.insert(format!("{prefix}.pattern"), f.pattern.clone());
+            for t in &f.transformations {
+                let metric = format!("{prefix}.metric.{}/{}", t.metric_namespace, t.metric_name);
+                settings.insert(format!("{metric}.value"), t.metric_value.clone());
+                if let Some(default) = t.default_value {
+                    settings.insert(format!("{metric}.default_value"), default.to_string());
+                }
+                if let Some(ref unit) = t.unit {
+                    settings.insert(format!("{metric}.unit"), unit.clone());
+                }
+                for (key, value) in &t.dimensions {
+                    settings.insert(format!("{metric}.dimension.{key}"), value.clone());
+                }
+            }
+        }
+        settings
+    }
+}
+
+/// settings that differ between two groups as (setting, left value, right value),
+/// None where a side doesn't have the setting
+pub fn diff(
+    left: &GroupConfig,
+    right: &GroupConfig,
+) -> Vec<(String, Option<String>, Option<String>)> {
+    let mut left = left.settings();
+    let mut right = right.settings();
+    let keys = left
+        .keys()
+        .chain(right.keys())
+        .cloned()
+        .collect::<BTreeSet<String>>();
+    keys.into_iter()
+        .filter_map(|key| {
+            let (l, r) = (left.remove(&key), right.remove(&key));
+            (l != r).then_some((key, l, r))
+        })
+        .collect()
+}
@@ -0,0 +1,255 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use aws_sdk_cloudwatchlogs::types::LogGroupClass;
+use log::{debug, info, warn};
+
+use crate::error::AwsLogsError;
+use crate::format::format_bytes;
+use crate::insights::Row;
+use crate::streams::ListOrder;
+use crate::timestamps::format_display;
+
+/// a log group as listed by DescribeLogGroups
+#[derive(Debug, Clone)]
+pub struct GroupInfo {
+    pub name: String,
+    /// epoch millis
+    pub creation_time: Option<i64>,
+    /// None when events never expire
+    pub retention_days: Option<i32>,
+    pub stored_bytes: Option<i64>,
+    pub kms_key_id: Option<String>,
+    /// STANDARD or INFREQUENT_ACCESS
+    pub class: Option<String>,
+}
+
+/// log groups as listed, and whether the listing was cut short
+#[derive(Debug, Clone)]
+pub struct GroupListing {
+    pub groups: Vec<GroupInfo>,
+    /// the token to resume from when max_pages stopped the listing before its end
+    pub resume_token: Option<String>,
+}
+
+/// every log group (whose name starts with name_prefix when given, a filter done by
+/// CloudWatch), sorted by name. CloudWatch lists them by name, so paging stops once there are
+/// limit of them. stops after max_pages pages too, warning with the token to resume from
+pub async fn sorted_log_groups(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    name_prefix: Option<&str>,
+    max_pages: u32,
+    resume_token: Option<String>,
+    limit: Option<usize>,
+) -> Result<GroupListing, AwsLogsError> {
+    let mut groups: Vec<GroupInfo> = vec![];
+    let mut next_token: Option<String> = resume_token;
+    let mut truncated_at: Option<String> = None;
+    let mut i = 0;
+    loop {
+        debug!("fetch log groups, iter: {i}");
+        let response = client
+            .describe_log_groups()
+            .set_log_group_name_prefix(name_prefix.map(str::to_string))
+            .set_next_token(next_token.take())
+            .send()
+            .await
+            .map_err(|e| AwsLogsError::sdk("failed to list log groups", &e))?;
+        next_token = response.next_token;
+        for group in response.log_groups.unwrap_or_default() {
+            groups.push(GroupInfo {
+                name: group
+                    .log_group_name
+                    .ok_or(AwsLogsError::MissingField("logGroupName"))?,
+                creation_time: group.creation_time,
+                retention_days: group.retention_in_days,
+                stored_bytes: group.stored_bytes,
+                kms_key_id: group.kms_key_id,
+                class: group.log_group_class.map(|c| c.as_str().to_string()),
+            });
+        }
+        if next_token.is_none() || limit.is_some_and(|limit| groups.len() >= limit) {
+            break;
+        }
+        i += 1;
+        // next_token is set, the end of the listing was checked above
+        if i >= max_pages {
+            warn!(
+                "stopped after {i} pages (--max-pages), log group list truncated; continue with --resume-token {}",
+                next_token.as_deref().unwrap_or_default()
+            );
+            truncated_at = next_token;
+            break;
+        }
+    }
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+    if let Some(limit) = limit {
+        groups.truncate(limit);
+    }
+    Ok(GroupListing {
+        groups,
+        resume_token: truncated_at,
+    })
+}
+
+/// a --tag of a new log group, `KEY=VALUE`
+#[derive(Debug, Clone)]
+pub struct Tag {
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for Tag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok(Tag {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+            _ => Err(format!("invalid tag '{s}', expected KEY=VALUE")),
+        }
+    }
+}
+
+/// the --log-group-class of a new log group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupClass {
+    Standard,
+    /// cheaper ingestion and storage, but no live tail, metric filters or subscriptions
+    InfrequentAccess,
+}
+
+impl FromStr for GroupClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(GroupClass::Standard),
+            "infrequent-access" => Ok(GroupClass::InfrequentAccess),
+            other => Err(format!(
+                "unknown log group class '{other}', expected standard or infrequent-access"
+            )),
+        }
+    }
+}
+
+impl From<GroupClass> for LogGroupClass {
+    fn from(class: GroupClass) -> Self {
+        match class {
+            GroupClass::Standard => LogGroupClass::Standard,
+            GroupClass::InfrequentAccess => LogGroupClass::InfrequentAccess,
+        }
+    }
+}
+
+/// what `alog create-log-group` creates
+#[derive(Debug, Clone)]
+pub struct NewGroup {
+    pub name: String,
+    pub tags: Vec<Tag>,
+    pub kms_key_id: Option<String>,
+    pub class: Option<GroupClass>,
+    /// never expire when None
+    pub retention_days: Option<i32>,
+}
+
+/// create a log group with its tags, KMS key and class, then set its retention, which
+/// CreateLogGroup can't
+pub async fn create_log_group(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    group: &NewGroup,
+) -> Result<(), AwsLogsError> {
+    info!("creating log group: {}", group.name);
+    let tags = (!group.tags.is_empty()).then(|| {
+        group
+            .tags
+            .iter()
+            .map(|tag| (tag.key.clone(), tag.value.clone()))
+            .collect::<HashMap<String, String>>()
+    });
+    client
+        .create_log_group()
+        .log_group_name(&group.name)
+        .set_tags(tags)
+        .set_kms_key_id(group.kms_key_id.clone())
+        .set_log_group_class(group.class.map(LogGroupClass::from))
+        .send()
+        .await
+        .map_err(|e| AwsLogsError::sdk("failed to create log group", &e))?;
+    if let Some(days) = group.retention_days {
+        client
+            .put_retention_policy()
+            .log_group_name(&group.name)
+            .retention_in_days(days)
+            .send()
+            .await
+            .map_err(|e| {
+                AwsLogsError::sdk(
+                    &format!("created {}, but failed to set its retention", group.name),
+                    &e,
+                )
+            })?;
+    }
+    Ok(())
+}
+
+/// delete a log group and every event in it
+pub async fn delete_log_group(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    name: &str,
+) -> Result<(), AwsLogsError> {
+    info!("deleting log group: {name}");
+    client
+        .delete_log_group()
+        .log_group_name(name)
+        .send()
+        .await
+        .map_err(|e| AwsLogsError::sdk(&format!("failed to delete log group {name}"), &e))?;
+    Ok(())
+}
+
+/// order groups by --order-by, the other way around with --desc, groups without a creation
+/// time or size first. stable, so ties stay in name order. groups have no last event time,
+/// that order leaves them as they are
+pub fn sort_groups(groups: &mut [GroupInfo], order: ListOrder, descending: bool) {
+    groups.sort_by(|a, b| {
+        let ordering = match order {
+            ListOrder::LastEvent => Ordering::Equal,
+            ListOrder::Creation => a.creation_time.cmp(&b.creation_time),
+            ListOrder::Name => a.name.cmp(&b.name),
+            ListOrder::Size => a.stored_bytes.cmp(&b.stored_bytes),
+        };
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// a row of the --details table of log groups
+pub fn details_row(group: &GroupInfo) -> Row {
+    let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    vec![
+        ("log group".to_string(), group.name.clone()),
+        (
+            "created".to_string(),
+            or_dash(group.creation_time.map(format_display)),
+        ),
+        (
+            "retention".to_string(),
+            group
+                .retention_days
+                .map_or("never expire".to_string(), |days| format!("{days} days")),
+        ),
+        (
+            "stored bytes".to_string(),
+            or_dash(group.stored_bytes.map(|b| format_bytes(b as f64))),
+        ),
+        ("class".to_string(), or_dash(group.class.clone())),
+        ("kms key".to_string(), or_dash(group.kms_key_id.clone())),
+    ]
+}
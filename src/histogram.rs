@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use crate::timestamps::format_display;
+use crate::Event;
+
+/// the longest bar, in characters
+const BAR_WIDTH: usize = 50;
+
+/// the most buckets a chart may have, a smaller bucket over a long time range is rejected
+/// rather than allocating and printing millions of lines
+const MAX_BINS: i64 = 10_000;
+
+/// eighths of a block, for bars that end part way through a character
+const PARTIAL_BLOCKS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// event counts per bucket of event time, from the bucket of the first event to that of the
+/// last, buckets without events included. buckets start at multiples of bucket since the epoch
+pub fn bin_events(events: &[Event], bucket: Duration) -> Result<Vec<(i64, usize)>, String> {
+    let bucket_ms = (bucket.as_millis() as i64).max(1);
+    let bin = |timestamp: i64| timestamp.div_euclid(bucket_ms) * bucket_ms;
+    let (Some(first), Some(last)) = (
+        events.iter().map(|e| e.timestamp).min(),
+        events.iter().map(|e| e.timestamp).max(),
+    ) else {
+        return Ok(vec![]);
+    };
+    let first_bin = bin(first);
+    let bins = (bin(last) - first_bin) / bucket_ms + 1;
+    if bins > MAX_BINS {
+        return Err(format!(
+            "--histogram {} would need {bins} buckets for these events, at most {MAX_BINS} are \
+             drawn: use a larger bucket",
+            humantime::format_duration(bucket)
+        ));
+    }
+    let mut counts = vec![0; bins as usize];
+    for event in events {
+        counts[((bin(event.timestamp) - first_bin) / bucket_ms) as usize] += 1;
+    }
+    Ok(counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| (first_bin + i as i64 * bucket_ms, count))
+        .collect())
+}
+
+fn bar(count: usize, max: usize) -> String {
+    let eighths = (count * BAR_WIDTH * 8).div_ceil(max.max(1));
+    let mut bar = "█".repeat(eighths / 8);
+    if !eighths.is_multiple_of(8) {
+        bar.push(PARTIAL_BLOCKS[eighths % 8]);
+    }
+    bar
+}
+
+/// a bar chart of the events per bucket, one line per bucket with its start time and count
+pub fn render(events: &[Event], bucket: Duration) -> Result<String, String> {
+    let bins = bin_events(events, bucket)?;
+    let max = bins.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let count_width = max.to_string().len();
+    Ok(bins
+        .iter()
+        .map(|(start, count)| {
+            format!(
+                "{}  {count:>count_width$}  {}",
+                format_display(*start),
+                bar(*count, max)
+            )
+            .trim_end()
+            .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bins_events_including_empty_buckets() {
+        let events = [Event::at(61_000, ""), Event::at(119_999, ""), Event::at(240_000, "")];
+        let bins = bin_events(&events, Duration::from_secs(60)).unwrap();
+        assert_eq!(
+            bins,
+            [(60_000, 2), (120_000, 0), (180_000, 0), (240_000, 1)]
+        );
+    }
+
+    #[test]
+    fn bins_nothing_without_events() {
+        assert_eq!(bin_events(&[], Duration::from_secs(60)), Ok(vec![]));
+    }
+
+    #[test]
+    fn rejects_too_many_buckets() {
+        let events = [Event::at(0, ""), Event::at(MAX_BINS * 1000, "")];
+        assert!(bin_events(&events, Duration::from_secs(1)).is_err());
+        assert!(bin_events(&events, Duration::from_secs(2)).is_ok());
+    }
+
+    #[test]
+    fn scales_bars_to_the_largest_count() {
+        assert_eq!(bar(10, 10).chars().count(), BAR_WIDTH);
+        assert_eq!(bar(5, 10), "█".repeat(BAR_WIDTH / 2));
+        assert_eq!(bar(1, 80), "▋");
+        assert_eq!(bar(0, 10), "");
+    }
+}
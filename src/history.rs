@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::config::state_dir;
+use rust_aws_logs::timestamps::{format_rfc3339, now_millis};
+
+/// only the most recent invocations are kept
+const MAX_HISTORY_ENTRIES: usize = 1000;
+
+/// one recorded invocation, args excludes the program name
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    #[serde(rename = "time")]
+    pub time: String,
+
+    #[serde(rename = "args")]
+    pub args: Vec<String>,
+}
+
+/// `history.jsonl` in the state dir
+pub fn history_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("history.jsonl"))
+}
+
+/// all recorded invocations, oldest first
+pub fn load_history() -> Result<Vec<HistoryEntry>, String> {
+    let path = match history_path() {
+        Some(path) if path.exists() => path,
+        _ => return Ok(vec![]),
+    };
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("unable to read history {}: {e}", path.display()))?;
+    let entries = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    Ok(entries)
+}
+
+/// append an invocation to the history file
+pub fn record(args: &[String]) -> Result<(), String> {
+    let path = history_path().ok_or_else(|| "unable to locate history file".to_string())?;
+    let mut entries = load_history()?;
+    entries.push(HistoryEntry {
+        time: format_rfc3339(now_millis()),
+        args: args.to_vec(),
+    });
+    let skip = entries.len().saturating_sub(MAX_HISTORY_ENTRIES);
+    let contents = entries
+        .iter()
+        .skip(skip)
+        .filter_map(|entry| serde_json::to_string(entry).ok())
+        .map(|line| line + "\n")
+        .collect::<String>();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("unable to create {}: {e}", dir.display()))?;
+    }
+    debug!("recording invocation in history: {}", path.display());
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("unable to write history {}: {e}", path.display()))
+}
+
+/// quote an argument for display so the printed command can be pasted back into a shell
+fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c));
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+pub fn format_command(program: &str, args: &[String]) -> String {
+    std::iter::once(program.to_string())
+        .chain(args.iter().map(|arg| shell_quote(arg)))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// remove a flag and its value (`--flag value` or `--flag=value`) from a list of args
+pub fn strip_flag(args: &[String], flag: &str) -> Vec<String> {
+    let mut stripped = vec![];
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == flag {
+            iter.next();
+        } else if !arg.starts_with(&format!("{flag}=")) {
+            stripped.push(arg.clone());
+        }
+    }
+    stripped
+}
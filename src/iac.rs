@@ -0,0 +1,298 @@
+use std::str::FromStr;
+
+use serde_json::{json, Map, Value};
+
+use crate::group_config::GroupConfig;
+
+/// infrastructure-as-code formats a log group's configuration can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IacFormat {
+    Terraform,
+    CloudFormation,
+}
+
+impl FromStr for IacFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "terraform" => Ok(IacFormat::Terraform),
+            "cloudformation" => Ok(IacFormat::CloudFormation),
+            _ => Err(format!(
+                "unknown iac format '{s}', expected one of: terraform, cloudformation"
+            )),
+        }
+    }
+}
+
+pub fn export(config: &GroupConfig, format: IacFormat) -> Result<String, String> {
+    match format {
+        IacFormat::Terraform => Ok(terraform(config)),
+        IacFormat::CloudFormation => cloudformation(config),
+    }
+}
+
+/// terraform resource name for a log group or filter name, e.g. /ecs/my-app -> ecs_my_app
+fn terraform_id(name: &str) -> String {
+    let id = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join("_")
+        .to_lowercase();
+    match id.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => id,
+        _ => format!("_{id}"),
+    }
+}
+
+/// quote a string as an HCL literal, escaping template sequences
+fn hcl_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace("${", "$${")
+        .replace("%{", "%%{");
+    format!("\"{escaped}\"")
+}
+
+fn hcl_map<'a>(entries: impl Iterator<Item = (&'a String, &'a String)>, indent: &str) -> String {
+    let entries = entries.collect::<Vec<_>>();
+    let width = entries
+        .iter()
+        .map(|(k, _)| hcl_string(k).len())
+        .max()
+        .unwrap_or(0);
+    let mut map = "{\n".to_string();
+    for (key, value) in entries {
+        let key = hcl_string(key);
+        map += &format!("{indent}  {key:<width$} = {}\n", hcl_string(value));
+    }
+    map + indent + "}"
+}
+
+/// attributes of a block, with the `=` aligned the way terraform fmt does
+fn hcl_attributes(attributes: &[(&str, String)], indent: &str) -> String {
+    let width = attributes.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+    attributes
+        .iter()
+        .map(|(key, value)| format!("{indent}{key:<width$} = {value}\n"))
+        .collect()
+}
+
+fn hcl_import(address: &str, id: &str) -> String {
+    format!(
+        "import {{\n  to = {address}\n  id = {}\n}}\n\n",
+        hcl_string(id)
+    )
+}
+
+/// resources matching the live configuration, with import blocks (terraform 1.5+) so the
+/// existing group and filters are adopted rather than recreated
+pub fn terraform(config: &GroupConfig) -> String {
+    let id = terraform_id(&config.name);
+    let group_address = format!("aws_cloudwatch_log_group.{id}");
+    let mut out = hcl_import(&group_address, &config.name);
+
+    let mut attributes = vec![("name", hcl_string(&config.name))];
+    if let Some(days) = config.retention_in_days {
+        attributes.push(("retention_in_days", days.to_string()));
+    }
+    if let Some(ref key) = config.kms_key_id {
+        attributes.push(("kms_key_id", hcl_string(key)));
+    }
+    if let Some(ref class) = config.log_group_class {
+        attributes.push(("log_group_class", hcl_string(class)));
+    }
+    out += &format!("resource \"aws_cloudwatch_log_group\" \"{id}\" {{\n");
+    out += &hcl_attributes(&attributes, "  ");
+    if !config.tags.is_empty() {
+        out += &format!("\n  tags = {}\n", hcl_map(config.tags.iter(), "  "));
+    }
+    out += "}\n";
+
+    let group_name_ref = format!("{group_address}.name");
+    for filter in &config.metric_filters {
+        let filter_id = format!(
+            "{id}_{}",
+            terraform_id(&filter.name).trim_start_matches('_')
+        );
+        let address = format!("aws_cloudwatch_log_metric_filter.{filter_id}");
+        out += "\n";
+        out += &hcl_import(&address, &format!("{}:{}", config.name, filter.name));
+        out += &format!("resource \"aws_cloudwatch_log_metric_filter\" \"{filter_id}\" {{\n");
+        out += &hcl_attributes(
+            &[
+                ("name", hcl_string(&filter.name)),
+                ("log_group_name", group_name_ref.clone()),
+                ("pattern", hcl_string(&filter.pattern)),
+            ],
+            "  ",
+        );
+        for t in &filter.transformations {
+            let mut attributes = vec![
+                ("name", hcl_string(&t.metric_name)),
+                ("namespace", hcl_string(&t.metric_namespace)),
+                ("value", hcl_string(&t.metric_value)),
+            ];
+            if let Some(default) = t.default_value {
+                attributes.push(("default_value", hcl_string(&default.to_string())));
+            }
+            if let Some(ref unit) = t.unit {
+                attributes.push(("unit", hcl_string(unit)));
+            }
+            out += "\n  metric_transformation {\n";
+            out += &hcl_attributes(&attributes, "    ");
+            if !t.dimensions.is_empty() {
+                out += &format!(
+                    "    dimensions = {}\n",
+                    hcl_map(t.dimensions.iter(), "    ")
+                );
+            }
+            out += "  }\n";
+        }
+        out += "}\n";
+    }
+
+    for filter in &config.subscription_filters {
+        let filter_id = format!(
+            "{id}_{}",
+            terraform_id(&filter.name).trim_start_matches('_')
+        );
+        let address = format!("aws_cloudwatch_log_subscription_filter.{filter_id}");
+        let mut attributes = vec![
+            ("name", hcl_string(&filter.name)),
+            ("log_group_name", group_name_ref.clone()),
+            ("filter_pattern", hcl_string(&filter.pattern)),
+            ("destination_arn", hcl_string(&filter.destination_arn)),
+        ];
+        if let Some(ref role) = filter.role_arn {
+            attributes.push(("role_arn", hcl_string(role)));
+        }
+        if let Some(ref distribution) = filter.distribution {
+            attributes.push(("distribution", hcl_string(distribution)));
+        }
+        out += "\n";
+        out += &hcl_import(&address, &format!("{}|{}", config.name, filter.name));
+        out += &format!("resource \"aws_cloudwatch_log_subscription_filter\" \"{filter_id}\" {{\n");
+        out += &hcl_attributes(&attributes, "  ");
+        out += "}\n";
+    }
+    out
+}
+
+/// CloudFormation logical id, e.g. metric filter `error-count` -> MetricFilterErrorCount
+fn logical_id(prefix: &str, name: &str) -> String {
+    let suffix = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect::<String>();
+    format!("{prefix}{suffix}")
+}
+
+fn key_value_list<'a>(entries: impl Iterator<Item = (&'a String, &'a String)>) -> Value {
+    entries
+        .map(|(key, value)| json!({"Key": key, "Value": value}))
+        .collect()
+}
+
+/// a template matching the live configuration, every resource is retained on delete so the
+/// template can be used to import the existing group and filters into a stack
+pub fn cloudformation(config: &GroupConfig) -> Result<String, String> {
+    let mut resources = Map::new();
+
+    let mut properties = Map::new();
+    properties.insert("LogGroupName".to_string(), json!(config.name));
+    if let Some(days) = config.retention_in_days {
+        properties.insert("RetentionInDays".to_string(), json!(days));
+    }
+    if let Some(ref key) = config.kms_key_id {
+        properties.insert("KmsKeyId".to_string(), json!(key));
+    }
+    if let Some(ref class) = config.log_group_class {
+        properties.insert("LogGroupClass".to_string(), json!(class));
+    }
+    if !config.tags.is_empty() {
+        properties.insert("Tags".to_string(), key_value_list(config.tags.iter()));
+    }
+    resources.insert(
+        "LogGroup".to_string(),
+        json!({"Type": "AWS::Logs::LogGroup", "DeletionPolicy": "Retain", "Properties": properties}),
+    );
+
+    for filter in &config.metric_filters {
+        let transformations = filter
+            .transformations
+            .iter()
+            .map(|t| {
+                let mut transformation = Map::new();
+                transformation.insert("MetricName".to_string(), json!(t.metric_name));
+                transformation.insert("MetricNamespace".to_string(), json!(t.metric_namespace));
+                transformation.insert("MetricValue".to_string(), json!(t.metric_value));
+                if let Some(default) = t.default_value {
+                    transformation.insert("DefaultValue".to_string(), json!(default));
+                }
+                if let Some(ref unit) = t.unit {
+                    transformation.insert("Unit".to_string(), json!(unit));
+                }
+                if !t.dimensions.is_empty() {
+                    transformation.insert(
+                        "Dimensions".to_string(),
+                        key_value_list(t.dimensions.iter()),
+                    );
+                }
+                Value::Object(transformation)
+            })
+            .collect::<Vec<Value>>();
+        resources.insert(
+            logical_id("MetricFilter", &filter.name),
+            json!({
+                "Type": "AWS::Logs::MetricFilter",
+                "DeletionPolicy": "Retain",
+                "Properties": {
+                    "FilterName": filter.name,
+                    "FilterPattern": filter.pattern,
+                    "LogGroupName": {"Ref": "LogGroup"},
+                    "MetricTransformations": transformations,
+                },
+            }),
+        );
+    }
+
+    for filter in &config.subscription_filters {
+        let mut properties = Map::new();
+        properties.insert("FilterName".to_string(), json!(filter.name));
+        properties.insert("FilterPattern".to_string(), json!(filter.pattern));
+        properties.insert("LogGroupName".to_string(), json!({"Ref": "LogGroup"}));
+        properties.insert("DestinationArn".to_string(), json!(filter.destination_arn));
+        if let Some(ref role) = filter.role_arn {
+            properties.insert("RoleArn".to_string(), json!(role));
+        }
+        if let Some(ref distribution) = filter.distribution {
+            properties.insert("Distribution".to_string(), json!(distribution));
+        }
+        resources.insert(
+            logical_id("SubscriptionFilter", &filter.name),
+            json!({
+                "Type": "AWS::Logs::SubscriptionFilter",
+                "DeletionPolicy": "Retain",
+                "Properties": properties,
+            }),
+        );
+    }
+
+    let template = json!({
+        "AWSTemplateFormatVersion": "2010-09-09",
+        "Description": format!("CloudWatch Logs configuration of {}", config.name),
+        "Resources": resources,
+    });
+    serde_yaml::to_string(&template).map_err(|e| format!("unable to serialize template: {e}"))
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use aws_sdk_cloudwatchlogs::types::QueryStatus;
+use log::{debug, info};
+
+use crate::error::AwsLogsError;
+
+/// how long to wait between get_query_results polls
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// give up on a query that hasn't finished after this many polls
+const MAX_POLLS: u32 = 300;
+
+/// the most log groups StartQuery takes in one query
+pub const MAX_QUERY_GROUPS: usize = 50;
+
+/// a result row as (field name, value) pairs, in the order Insights returned them
+pub type Row = Vec<(String, String)>;
+
+/// look up a field in a result row
+pub fn field<'a>(row: &'a Row, name: &str) -> Option<&'a str> {
+    row.iter()
+        .find(|(field, _)| field == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// run a CloudWatch Logs Insights query and wait for it to finish.
+/// start_time and end_time are epoch seconds, as expected by StartQuery.
+/// the internal @ptr field is dropped from the returned rows
+pub async fn run_query(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    query: &str,
+    start_time: i64,
+    end_time: i64,
+) -> Result<Vec<Row>, AwsLogsError> {
+    run_query_on_groups(
+        client,
+        &[log_group.to_string()],
+        query,
+        start_time,
+        end_time,
+    )
+    .await
+}
+
+/// run_query across several log groups at once (up to 50), rows from all of them together.
+/// more groups than that are refused before a query is started, since splitting them over
+/// several queries would give stats per batch instead of one result
+pub async fn run_query_on_groups(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_groups: &[String],
+    query: &str,
+    start_time: i64,
+    end_time: i64,
+) -> Result<Vec<Row>, AwsLogsError> {
+    if log_groups.len() > MAX_QUERY_GROUPS {
+        return Err(AwsLogsError::InvalidArgument(format!(
+            "an Insights query covers at most {MAX_QUERY_GROUPS} log groups, {} matched; narrow the --log-group pattern",
+            log_groups.len()
+        )));
+    }
+    info!("start insights query - log_groups: {log_groups:?}, start: {start_time}, end: {end_time}, query: {query}");
+    let response = client
+        .start_query()
+        .set_log_group_names(Some(log_groups.to_vec()))
+        .query_string(query)
+        .start_time(start_time)
+        .end_time(end_time)
+        .send()
+        .await
+        .map_err(|e| AwsLogsError::sdk("failed to start insights query", &e))?;
+    let query_id = response
+        .query_id
+        .ok_or(AwsLogsError::MissingField("queryId"))?;
+
+    for i in 0..MAX_POLLS {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let results = client
+            .get_query_results()
+            .query_id(&query_id)
+            .send()
+            .await
+            .map_err(|e| AwsLogsError::sdk("failed to get insights query results", &e))?;
+        let status = results.status.clone().unwrap_or(QueryStatus::UnknownValue);
+        debug!(
+            "[{i}] insights query {query_id} status: {}",
+            status.as_str()
+        );
+        match status {
+            QueryStatus::Complete => {
+                let rows = results
+                    .results
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|row| {
+                        row.into_iter()
+                            .filter_map(|field| Some((field.field?, field.value?)))
+                            .filter(|(name, _)| name != "@ptr")
+                            .collect::<Row>()
+                    })
+                    .collect();
+                return Ok(rows);
+            }
+            QueryStatus::Running | QueryStatus::Scheduled => continue,
+            other => {
+                return Err(AwsLogsError::Sdk(format!(
+                    "insights query {query_id} ended with status: {}",
+                    other.as_str()
+                )))
+            }
+        }
+    }
+    Err(AwsLogsError::Sdk(format!(
+        "insights query {query_id} did not complete after {MAX_POLLS} polls"
+    )))
+}
+
+/// count the events in a single log stream in [start_ms, end_ms) (epoch millis)
+pub async fn count_stream_events(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    log_stream: &str,
+    start_ms: i64,
+    end_ms: i64,
+) -> Result<i64, AwsLogsError> {
+    let query = format!(
+        "filter @logStream = '{}' and @timestamp >= {start_ms} and @timestamp < {end_ms} | stats count(*) as event_count",
+        log_stream.replace('\'', "\\'")
+    );
+    // insights works in whole seconds, widen the window so the edges are included and cut it
+    // back to the millisecond with the filter
+    let start_time = start_ms / 1000;
+    let end_time = end_ms / 1000 + 1;
+    let rows = run_query(client, log_group, &query, start_time, end_time).await?;
+    let count = match rows.first().and_then(|row| field(row, "event_count")) {
+        Some(value) => value.parse::<i64>().map_err(|e| {
+            AwsLogsError::Sdk(format!("unexpected event_count value '{value}': {e}"))
+        })?,
+        // no rows means no matching events
+        None => 0,
+    };
+    Ok(count)
+}
+
+/// approximate event count and message bytes of one log stream
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamSize {
+    pub events: i64,
+    pub bytes: i64,
+}
+
+/// event counts and message sizes per log stream between start_time and end_time (epoch
+/// seconds), from a single `stats ... by @logStream` query. bytes count message characters,
+/// which is close to but not the same as what CloudWatch stores
+pub async fn stream_sizes(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    start_time: i64,
+    end_time: i64,
+) -> Result<HashMap<String, StreamSize>, AwsLogsError> {
+    // insights returns at most 10000 rows, the default limit is 1000
+    let query =
+        "stats count(*) as events, sum(strlen(@message)) as bytes by @logStream | limit 10000";
+    let rows = run_query(client, log_group, query, start_time, end_time).await?;
+    let parse = |row: &Row, name: &str| {
+        field(row, name)
+            .and_then(|value| value.parse::<f64>().ok())
+            .unwrap_or(0.0) as i64
+    };
+    let sizes = rows
+        .iter()
+        .filter_map(|row| {
+            let stream = field(row, "@logStream")?.to_string();
+            let size = StreamSize {
+                events: parse(row, "events"),
+                bytes: parse(row, "bytes"),
+            };
+            Some((stream, size))
+        })
+        .collect();
+    Ok(sizes)
+}
+
+/// build the query for a --stats shortcut, e.g. `count(*) by bin(5m)`
+pub fn stats_query(expression: &str) -> String {
+    format!("stats {expression}")
+}
+
+/// the time units Insights' bin() takes a period in
+const BIN_UNITS: [&str; 5] = ["ms", "s", "m", "h", "d"];
+
+/// check a bin() period, a whole number and a unit like `5m` or `1h`. humantime spellings like
+/// `5min` or `1h30m` aren't understood by Insights
+fn check_bin_period(period: &str) -> Result<(), String> {
+    let unit_start = period
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(period.len());
+    let (amount, unit) = period.split_at(unit_start);
+    let amount = amount.parse::<u64>().ok().filter(|amount| *amount > 0);
+    match (amount, BIN_UNITS.contains(&unit)) {
+        (Some(_), true) => Ok(()),
+        _ => Err(format!(
+            "invalid --count-by '{period}', expected a bin size like 5m (a whole number and one of: {}) or field:<name>",
+            BIN_UNITS.join(", ")
+        )),
+    }
+}
+
+/// build the query for a --count-by shortcut: a bin size like `5m` counts events over time,
+/// `field:<name>` counts events per distinct value of a field
+pub fn count_by_query(spec: &str) -> Result<String, String> {
+    if let Some(name) = spec.strip_prefix("field:") {
+        if name.is_empty() {
+            return Err("--count-by field: needs a field name, e.g. field:level".to_string());
+        }
+        return Ok(format!(
+            "stats count(*) as count by {name} | sort count desc"
+        ));
+    }
+    let bin = spec.trim();
+    check_bin_period(bin)?;
+    Ok(format!(
+        "stats count(*) as count by bin({bin}) as time | sort time asc"
+    ))
+}
+
+/// render result rows as an aligned text table, columns taken from the first row
+pub fn format_table(rows: &[Row]) -> String {
+    let columns = match rows.first() {
+        Some(row) => row
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<&str>>(),
+        None => return "no results".to_string(),
+    };
+    let cells = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| field(row, column).unwrap_or(""))
+                .collect::<Vec<&str>>()
+        })
+        .collect::<Vec<Vec<&str>>>();
+    let widths = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain(std::iter::once(column.chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<usize>>();
+    let format_line = |values: &[&str]| {
+        values
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{value:<width$}"))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    let mut lines = vec![format_line(&columns)];
+    lines.extend(cells.iter().map(|row| format_line(row)));
+    lines.join("\n")
+}
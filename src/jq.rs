@@ -0,0 +1,68 @@
+use jaq_interpret::{Ctx, Filter, FilterT, ParseCtx, RcIter, Val};
+use log::debug;
+
+use crate::Event;
+
+/// a compiled --jq expression, run on every message that parses as json
+pub struct JqFilter {
+    filter: Filter,
+}
+
+impl JqFilter {
+    /// compile expr with the jq standard library (`select`, `map`, `test`, ...) available
+    pub fn compile(expr: &str) -> Result<JqFilter, String> {
+        let mut defs = ParseCtx::new(Vec::new());
+        defs.insert_natives(jaq_core::core());
+        defs.insert_defs(jaq_std::std());
+        let (main, errs) = jaq_parse::parse(expr, jaq_parse::main());
+        if let Some(e) = errs.first() {
+            return Err(format!("unable to parse --jq expression '{expr}': {e}"));
+        }
+        let main = main.ok_or_else(|| format!("unable to parse --jq expression '{expr}'"))?;
+        let filter = defs.compile(main);
+        if !defs.errs.is_empty() {
+            return Err(format!(
+                "unable to compile --jq expression '{expr}': {} undefined filters or variables",
+                defs.errs.len()
+            ));
+        }
+        Ok(JqFilter { filter })
+    }
+
+    /// the outputs of the filter on a json message, one per line, strings without their quotes
+    /// like `jq -r`. None when the message isn't json, the filter has no output (e.g. a
+    /// `select` that didn't match) or it fails
+    fn apply(&self, message: &str) -> Option<String> {
+        let value: serde_json::Value = serde_json::from_str(message.trim()).ok()?;
+        let inputs = RcIter::new(core::iter::empty());
+        let outputs = self
+            .filter
+            .run((Ctx::new([], &inputs), Val::from(value)))
+            .map(|output| {
+                output.map(|value| match value {
+                    Val::Str(s) => s.to_string(),
+                    value => value.to_string(),
+                })
+            })
+            .collect::<Result<Vec<String>, _>>();
+        match outputs {
+            Ok(outputs) if !outputs.is_empty() => Some(outputs.join("\n")),
+            Ok(_) => None,
+            Err(e) => {
+                debug!("--jq failed on {message}: {e}");
+                None
+            }
+        }
+    }
+
+    /// keep the events the filter has output for, with their message replaced by it
+    pub fn filter_events(&self, events: &mut Vec<Event>) {
+        events.retain_mut(|event| match self.apply(&event.message) {
+            Some(message) => {
+                event.message = message;
+                true
+            }
+            None => false,
+        });
+    }
+}
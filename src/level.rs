@@ -0,0 +1,231 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// severity of a log line, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl Level {
+    /// parse a level name, accepting the common spellings used by logging libraries
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "trace" | "trc" | "t" => Some(Level::Trace),
+            "debug" | "dbg" | "d" => Some(Level::Debug),
+            "info" | "inf" | "information" | "notice" | "i" => Some(Level::Info),
+            "warn" | "warning" | "wrn" | "w" => Some(Level::Warn),
+            "error" | "err" | "e" => Some(Level::Error),
+            "fatal" | "critical" | "crit" | "panic" | "emergency" | "alert" | "f" | "c" => {
+                Some(Level::Fatal)
+            }
+            _ => None,
+        }
+    }
+
+    /// numeric levels as used by pino / bunyan
+    fn from_number(n: i64) -> Option<Level> {
+        match n {
+            10 => Some(Level::Trace),
+            20 => Some(Level::Debug),
+            30 => Some(Level::Info),
+            40 => Some(Level::Warn),
+            50 => Some(Level::Error),
+            60 => Some(Level::Fatal),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Level::from_name(s).ok_or_else(|| format!("unknown log level: '{s}'"))
+    }
+}
+
+/// json keys that commonly hold the level of a structured log line
+const JSON_LEVEL_KEYS: [&str; 6] = [
+    "level",
+    "severity",
+    "levelname",
+    "log.level",
+    "lvl",
+    "loglevel",
+];
+
+/// plaintext level tokens are only looked for near the start of the line
+const PLAINTEXT_PREFIX_LEN: usize = 80;
+
+fn logfmt_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?:^|\s)(?:level|lvl|severity)="?([A-Za-z]+)"?(?:\s|$)"#).unwrap()
+    })
+}
+
+fn plaintext_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        // [ERROR], ERROR:, <timestamp> WARN ..., [warning], and tornado style "[I 240313 16:28:29 ...]"
+        Regex::new(
+            r"(?x)
+            ^\s*\[([TDIWEFC])\s\d{6}
+            | \b(TRACE|DEBUG|INFO|NOTICE|WARN|WARNING|ERROR|ERR|FATAL|CRITICAL|PANIC)\b
+            | \[(trace|debug|info|warn|warning|error|fatal|critical)\]
+            ",
+        )
+        .unwrap()
+    })
+}
+
+fn detect_json_level(message: &str) -> Option<Level> {
+    let value: serde_json::Value = serde_json::from_str(message).ok()?;
+    let object = value.as_object()?;
+    JSON_LEVEL_KEYS
+        .iter()
+        .find_map(|key| match object.get(*key)? {
+            serde_json::Value::String(name) => Level::from_name(name),
+            serde_json::Value::Number(n) => Level::from_number(n.as_i64()?),
+            _ => None,
+        })
+}
+
+/// detect the level of a message from a json level field, a logfmt level= pair,
+/// or a common plaintext prefix. None if the message doesn't look like it has one
+pub fn detect_level(message: &str) -> Option<Level> {
+    let trimmed = message.trim_start();
+    if trimmed.starts_with('{') {
+        if let Some(level) = detect_json_level(trimmed) {
+            return Some(level);
+        }
+    }
+    if let Some(caps) = logfmt_regex().captures(message) {
+        if let Some(level) = Level::from_name(&caps[1]) {
+            return Some(level);
+        }
+    }
+    let mut end = message.len().min(PLAINTEXT_PREFIX_LEN);
+    while !message.is_char_boundary(end) {
+        end -= 1;
+    }
+    let caps = plaintext_regex().captures(&message[..end])?;
+    let name = caps.iter().skip(1).flatten().next()?.as_str();
+    Level::from_name(name)
+}
+
+/// which levels to keep: `warn+` means warn and anything more severe,
+/// `warn` means only warn, and `info,error` is a list of exact levels
+#[derive(Debug, Clone)]
+pub enum LevelFilter {
+    AtLeast(Level),
+    AnyOf(Vec<Level>),
+}
+
+impl FromStr for LevelFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(name) = s.strip_suffix('+') {
+            return Ok(LevelFilter::AtLeast(name.parse()?));
+        }
+        let levels = s
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<Level>, String>>()?;
+        Ok(LevelFilter::AnyOf(levels))
+    }
+}
+
+impl LevelFilter {
+    /// messages without a recognizable level never match
+    pub fn matches(&self, message: &str) -> bool {
+        match (self, detect_level(message)) {
+            (LevelFilter::AtLeast(min), Some(level)) => level >= *min,
+            (LevelFilter::AnyOf(levels), Some(level)) => levels.contains(&level),
+            (_, None) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_json_levels() {
+        assert_eq!(
+            detect_level(r#"{"level":"warn","msg":"slow"}"#),
+            Some(Level::Warn)
+        );
+        assert_eq!(detect_level(r#"{"severity":"ERROR"}"#), Some(Level::Error));
+        // pino / bunyan numbers
+        assert_eq!(
+            detect_level(r#"{"level":50,"msg":"boom"}"#),
+            Some(Level::Error)
+        );
+    }
+
+    #[test]
+    fn detects_logfmt_levels() {
+        assert_eq!(
+            detect_level("ts=2024-03-13T16:28:28Z level=info msg=started"),
+            Some(Level::Info)
+        );
+        assert_eq!(
+            detect_level(r#"lvl="debug" msg="cache miss""#),
+            Some(Level::Debug)
+        );
+    }
+
+    #[test]
+    fn detects_plaintext_levels() {
+        assert_eq!(
+            detect_level("[ERROR] connection refused"),
+            Some(Level::Error)
+        );
+        assert_eq!(detect_level("WARN: disk 91% full"), Some(Level::Warn));
+        assert_eq!(
+            detect_level("[I 240313 16:28:29 worker:41] polled 12 jobs"),
+            Some(Level::Info)
+        );
+        assert_eq!(detect_level("request completed in 12ms"), None);
+    }
+
+    #[test]
+    fn plaintext_levels_only_near_the_start() {
+        let message = format!("{} ERROR", "x".repeat(PLAINTEXT_PREFIX_LEN));
+        assert_eq!(detect_level(&message), None);
+    }
+
+    #[test]
+    fn filters_at_least_a_level() {
+        let filter: LevelFilter = "warn+".parse().unwrap();
+        assert!(filter.matches("WARN: retrying"));
+        assert!(filter.matches("[ERROR] failed"));
+        assert!(!filter.matches("INFO started"));
+        assert!(!filter.matches("no level here"));
+    }
+
+    #[test]
+    fn filters_a_list_of_levels() {
+        let filter: LevelFilter = "info,error".parse().unwrap();
+        assert!(filter.matches("INFO started"));
+        assert!(filter.matches("ERROR failed"));
+        assert!(!filter.matches("WARN retrying"));
+    }
+
+    #[test]
+    fn rejects_unknown_levels() {
+        assert!("loud+".parse::<LevelFilter>().is_err());
+        assert!("info,loud".parse::<LevelFilter>().is_err());
+    }
+}
@@ -0,0 +1,571 @@
+//! fetch, filter and render CloudWatch Logs events. the `alog` binary is a command line around
+//! this crate, other programs can use the same fetches, e.g. to collect a whole stream
+//!
+//! ```no_run
+//! # async fn example(client: &aws_sdk_cloudwatchlogs::Client) -> Result<(), rust_aws_logs::error::AwsLogsError> {
+//! let pagination = rust_aws_logs::Pagination::default();
+//! let events = rust_aws_logs::fetch_entire_log(
+//!     client, "/ecs/app", "ecs/app/1234", None, None, None, &pagination, None,
+//! )
+//! .await?;
+//! for event in events {
+//!     println!("{} {}", event.timestamp, event.message);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! or to go through it page by page, stopping whenever enough was seen
+//!
+//! ```no_run
+//! # use futures::TryStreamExt;
+//! # async fn example(client: &aws_sdk_cloudwatchlogs::Client) -> Result<(), rust_aws_logs::error::AwsLogsError> {
+//! let pagination = rust_aws_logs::Pagination::default();
+//! let events = rust_aws_logs::stream_log_events(client, "/ecs/app", "ecs/app/1234", None, None, &pagination);
+//! futures::pin_mut!(events);
+//! while let Some(event) = events.try_next().await? {
+//!     if event.message.contains("panicked") {
+//!         println!("{}", event.message);
+//!         break;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod bucket;
+pub mod checkpoint;
+pub mod chunked;
+pub mod compress;
+pub mod dedup;
+pub mod encrypt;
+pub mod error;
+pub mod export;
+pub mod extract;
+pub mod follow;
+pub mod format;
+pub mod gaps;
+pub mod glob;
+pub mod grafana;
+pub mod group_config;
+pub mod groups;
+pub mod histogram;
+pub mod iac;
+pub mod insights;
+pub mod jq;
+pub mod level;
+pub mod live;
+pub mod manifest;
+pub mod merge;
+pub mod org;
+pub mod preview;
+pub mod ratelimit;
+pub mod recording;
+pub mod records;
+pub mod redact;
+pub mod regions;
+pub mod retention;
+pub mod rollup;
+pub mod rotate;
+pub mod rules;
+pub mod sidecar;
+pub mod sink;
+pub mod stats;
+pub mod stitch;
+pub mod store;
+pub mod streams;
+pub mod timestamps;
+
+use std::sync::OnceLock;
+
+use futures::{Stream, TryStreamExt};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use error::AwsLogsError;
+
+/// GetLogEvents returns at most this many events per call
+pub const MAX_PAGE_LIMIT: u32 = 10_000;
+
+/// CloudWatch can take up to an hour to update the last event time of a log stream
+pub const LAST_EVENT_TIME_LAG_MS: i64 = 60 * 60 * 1000;
+
+/// log group listings stop after this many pages unless told otherwise
+pub const DEFAULT_MAX_GROUP_PAGES: u32 = 100;
+
+/// how a full stream fetch pages through GetLogEvents
+#[derive(Debug, Clone, Default)]
+pub struct Pagination {
+    /// events per page, the API default when None
+    pub page_limit: Option<i32>,
+    /// stop after this many pages
+    pub max_pages: Option<u32>,
+    /// forward token to start from instead of the head of the stream
+    pub resume_token: Option<String>,
+}
+
+/// one page of GetLogEvents results, with the tokens of the pages around it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EventLog {
+    #[serde(rename = "events")]
+    pub events: Vec<Event>,
+
+    #[serde(rename = "nextForwardToken")]
+    pub next_forward_token: String,
+
+    #[serde(rename = "nextBackwardToken")]
+    pub next_backward_token: String,
+}
+
+/// a log event, with its times in epoch millis
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Event {
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+
+    #[serde(rename = "message")]
+    pub message: String,
+
+    #[serde(rename = "ingestionTime")]
+    pub ingestion_time: i64,
+
+    /// the stream the event came from, set when output mixes events of several streams
+    #[serde(rename = "logStream", default, skip_serializing_if = "Option::is_none")]
+    pub log_stream: Option<String>,
+
+    /// the group the event came from, set when output mixes events of several groups
+    #[serde(rename = "logGroup", default, skip_serializing_if = "Option::is_none")]
+    pub log_group: Option<String>,
+}
+
+#[cfg(test)]
+impl Event {
+    /// an event of the tests, ingested when it happened, without a stream or group
+    pub(crate) fn at(timestamp: i64, message: &str) -> Event {
+        Event {
+            timestamp,
+            message: message.to_string(),
+            ingestion_time: timestamp,
+            log_stream: None,
+            log_group: None,
+        }
+    }
+}
+
+/// one page of a stream's events. from_tail reads backwards from the end of the stream, start
+/// and end times (epoch millis) limit the events to a time range
+#[allow(clippy::too_many_arguments)]
+pub async fn fetch_single_log_page(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    log_stream: &str,
+    fwd_token: Option<&str>,
+    limit: Option<i32>,
+    from_tail: Option<bool>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+) -> Result<EventLog, AwsLogsError> {
+    let token_disp = fwd_token.unwrap_or("None");
+    let limit_disp = limit.unwrap_or(-1);
+    debug!(
+        "fetch single log page for: {log_stream}, token: {}, limit: {}",
+        token_disp, limit_disp
+    );
+    ratelimit::get_log_events_turn().await;
+    let mut bld = client
+        .get_log_events()
+        .log_stream_name(log_stream)
+        .log_group_name(log_group)
+        .start_from_head(true);
+    // determine which page to get
+    if let Some(token) = fwd_token {
+        bld = bld.next_token(token);
+    }
+    if let Some(lmt) = limit {
+        bld = bld.limit(lmt);
+    }
+    if let Some(tail) = from_tail {
+        bld = bld.start_from_head(!tail);
+    }
+    if let Some(start) = start_time {
+        bld = bld.start_time(start);
+    }
+    if let Some(end) = end_time {
+        bld = bld.end_time(end);
+    }
+    let response = bld
+        .send()
+        .await
+        .map_err(|e| AwsLogsError::sdk("failed to fetch log events", &e))?;
+    let events = response.events.unwrap_or_default();
+    let my_events = events
+        .into_iter()
+        .map(|event| {
+            let timestamp = event
+                .timestamp
+                .ok_or(AwsLogsError::MissingField("event timestamp"))?;
+            let message = event
+                .message
+                .ok_or(AwsLogsError::MissingField("event message"))?;
+            let ingestion_time = event
+                .ingestion_time
+                .ok_or(AwsLogsError::MissingField("event ingestion time"))?;
+            Ok(Event {
+                timestamp,
+                message,
+                ingestion_time,
+                log_stream: None,
+                log_group: None,
+            })
+        })
+        .collect::<Result<Vec<Event>, AwsLogsError>>()?;
+    let eventlog: EventLog = EventLog {
+        events: my_events,
+        next_forward_token: response
+            .next_forward_token
+            .ok_or(AwsLogsError::MissingField("nextForwardToken"))?,
+        next_backward_token: response
+            .next_backward_token
+            .ok_or(AwsLogsError::MissingField("nextBackwardToken"))?,
+    };
+    Ok(eventlog)
+}
+
+/// the first limit events of a stream, sorted by timestamp
+pub async fn fetch_first_n_events(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    log_stream: &str,
+    limit: i32,
+) -> Result<Vec<Event>, AwsLogsError> {
+    check_log_stream_name(log_stream)?;
+    info!("fetch first N events from log stream - log_group: {log_group}, log_stream: {log_stream}, limit: {limit}");
+    let fwd_token: Option<&str> = None;
+    let event_log: EventLog = fetch_single_log_page(
+        client,
+        log_group,
+        log_stream,
+        fwd_token,
+        Some(limit),
+        None,
+        None,
+        None,
+    )
+    .await?;
+    // append all the events to all_events
+    let page_size = event_log.events.len();
+    info!("fetched single page, size: {page_size}, limit was: {limit}");
+    let mut all_events = event_log.events;
+    all_events.sort_by_key(|e| e.timestamp);
+    Ok(all_events)
+}
+
+/// stream names never start with /, one that does is almost certainly a log group name
+fn check_log_stream_name(log_stream: &str) -> Result<(), AwsLogsError> {
+    if log_stream.starts_with('/') {
+        return Err(AwsLogsError::InvalidArgument(format!(
+            "log stream '{log_stream}' starts with /, is it a log group name?"
+        )));
+    }
+    Ok(())
+}
+
+/// whether event is kept by a --grep regex, everything is without one
+pub fn grep_matches(grep: Option<&regex::Regex>, event: &Event) -> bool {
+    grep.is_none_or(|re| re.is_match(&event.message))
+}
+
+/// sees each page fetched from one log stream
+pub type PageWatcher = Box<dyn FnMut(&[Event]) + Send>;
+
+/// starts watching the pages fetched from a log stream, see set_page_observer
+pub type PageObserver = fn(log_stream: &str) -> PageWatcher;
+
+static PAGE_OBSERVER: OnceLock<PageObserver> = OnceLock::new();
+
+/// have observer see the pages of every stream stream_log_pages fetches from now on, e.g. to
+/// show download progress. it's called as a stream starts, the closure it returns with each
+/// page, and that closure is dropped when the stream ends. only the first call has an effect
+pub fn set_page_observer(observer: PageObserver) {
+    let _ = PAGE_OBSERVER.set(observer);
+}
+
+/// where stream_log_pages is in a stream
+struct PageState {
+    /// forward token of the next page, the head of the stream when None
+    token: Option<String>,
+    pages: u32,
+    empty_pages_in_a_row: u32,
+    done: bool,
+    observer: Option<PageWatcher>,
+}
+
+/// the pages of a stream from its head (or the pagination's resume token), fetched as the
+/// stream is polled. each page's next_forward_token is where to resume after it
+pub fn stream_log_pages<'a>(
+    client: &'a aws_sdk_cloudwatchlogs::Client,
+    log_group: &'a str,
+    log_stream: &'a str,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    pagination: &'a Pagination,
+) -> impl Stream<Item = Result<EventLog, AwsLogsError>> + 'a {
+    let first = PageState {
+        token: pagination.resume_token.clone(),
+        pages: 0,
+        empty_pages_in_a_row: 0,
+        done: false,
+        observer: PAGE_OBSERVER.get().map(|start| start(log_stream)),
+    };
+    futures::stream::try_unfold(first, move |mut state| async move {
+        if state.done {
+            return Ok(None);
+        }
+        check_log_stream_name(log_stream)?;
+        let event_log = fetch_single_log_page(
+            client,
+            log_group,
+            log_stream,
+            state.token.as_deref(),
+            pagination.page_limit,
+            None,
+            start_time,
+            end_time,
+        )
+        .await?;
+        let pages = state.pages + 1;
+        let page_size = event_log.events.len();
+        if let Some(ref mut observer) = state.observer {
+            observer(&event_log.events);
+        }
+        info!("fetched page {pages}, size: {page_size}");
+        debug!(
+            "[{pages}] forward_token: {}, backward_token: {}",
+            event_log.next_forward_token, event_log.next_backward_token
+        );
+        let empty_pages_in_a_row = match page_size {
+            0 => state.empty_pages_in_a_row + 1,
+            _ => 0,
+        };
+        // the forward token stays the same once the end of the stream is reached
+        let at_end = state.token.as_deref() == Some(event_log.next_forward_token.as_str());
+        if empty_pages_in_a_row >= 3 {
+            debug!("page size is 0 multiple times in a row, stop paginating");
+        }
+        // the forward token never runs out, so whether --max-pages cut anything off is only
+        // known by looking for an event past it
+        let truncated = !at_end
+            && pagination.max_pages.is_some_and(|max| pages >= max)
+            && !fetch_single_log_page(
+                client,
+                log_group,
+                log_stream,
+                Some(&event_log.next_forward_token),
+                Some(1),
+                None,
+                start_time,
+                end_time,
+            )
+            .await?
+            .events
+            .is_empty();
+        if truncated {
+            warn!(
+                "stopped after {pages} pages (--max-pages), output truncated; continue with --resume-token {}",
+                event_log.next_forward_token
+            );
+        }
+        let next = PageState {
+            done: at_end || truncated || empty_pages_in_a_row >= 3,
+            token: Some(event_log.next_forward_token.clone()),
+            pages,
+            empty_pages_in_a_row,
+            observer: state.observer,
+        };
+        Ok(Some((event_log, next)))
+    })
+}
+
+/// the events of a stream from its head (or the pagination's resume token), fetched one page
+/// at a time as the stream is polled. memory use doesn't grow with the log stream, and
+/// dropping the stream stops fetching
+pub fn stream_log_events<'a>(
+    client: &'a aws_sdk_cloudwatchlogs::Client,
+    log_group: &'a str,
+    log_stream: &'a str,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    pagination: &'a Pagination,
+) -> impl Stream<Item = Result<Event, AwsLogsError>> + 'a {
+    stream_log_pages(
+        client, log_group, log_stream, start_time, end_time, pagination,
+    )
+    .map_ok(|page| futures::stream::iter(page.events.into_iter().map(Ok)))
+    .try_flatten()
+}
+
+/// events of a stream, sorted by timestamp. with grep, only the events whose message matches
+/// are kept as each page is fetched
+#[allow(clippy::too_many_arguments)]
+pub async fn fetch_entire_log(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    log_stream: &str,
+    tail: Option<u32>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    pagination: &Pagination,
+    grep: Option<&regex::Regex>,
+) -> Result<Vec<Event>, AwsLogsError> {
+    check_log_stream_name(log_stream)?;
+
+    info!("fetch entire log - log_group: {log_group}, log_stream: {log_stream}");
+    let mut all_events = Vec::new();
+
+    if let Some(tail_num) = tail {
+        // tail arg... just fetch single page, and from tail (not head)
+        // still apply event number limit, but take from tail arg
+        let limit = tail_num as i32;
+        let event_log: EventLog = fetch_single_log_page(
+            client,
+            log_group,
+            log_stream,
+            None,
+            Some(limit),
+            Some(true),
+            start_time,
+            end_time,
+        )
+        .await?;
+        all_events.extend(
+            event_log
+                .events
+                .into_iter()
+                .filter(|e| grep_matches(grep, e)),
+        );
+        info!("fetched single page TAIL, limit was: {limit}");
+    } else {
+        // no tail... just regular full log fetch
+        all_events = stream_log_events(
+            client, log_group, log_stream, start_time, end_time, pagination,
+        )
+        .try_filter(|e| std::future::ready(grep_matches(grep, e)))
+        .try_collect()
+        .await?;
+    }
+    // sort all the events based on timestamp, just in case they are out of order
+    all_events.sort_by_key(|e| e.timestamp);
+    Ok(all_events)
+}
+
+/// fetch only the events of a stream matching a CloudWatch Logs filter pattern, using
+/// FilterLogEvents so the filtering happens server side. with tail, the last N matches are kept
+#[allow(clippy::too_many_arguments)]
+pub async fn fetch_filtered_log(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    log_stream: &str,
+    filter_pattern: &str,
+    tail: Option<u32>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    pagination: &Pagination,
+    grep: Option<&regex::Regex>,
+) -> Result<Vec<Event>, AwsLogsError> {
+    info!("fetch filtered log - log_group: {log_group}, log_stream: {log_stream}, filter_pattern: {filter_pattern}");
+    let mut i = 0;
+    let mut next_token: Option<String> = pagination.resume_token.clone();
+    let mut all_events = Vec::new();
+    loop {
+        let response = client
+            .filter_log_events()
+            .log_group_name(log_group)
+            .log_stream_names(log_stream)
+            .filter_pattern(filter_pattern)
+            .set_start_time(start_time)
+            .set_end_time(end_time)
+            .set_limit(pagination.page_limit)
+            .set_next_token(next_token.take())
+            .send()
+            .await
+            .map_err(|e| AwsLogsError::sdk("failed to filter log events", &e))?;
+        let events = response.events.unwrap_or_default();
+        let n = i + 1;
+        info!("fetched filtered page {n}, size: {}", events.len());
+        let events = events
+            .into_iter()
+            .map(|event| {
+                let timestamp = event
+                    .timestamp
+                    .ok_or(AwsLogsError::MissingField("event timestamp"))?;
+                let message = event
+                    .message
+                    .ok_or(AwsLogsError::MissingField("event message"))?;
+                let ingestion_time = event
+                    .ingestion_time
+                    .ok_or(AwsLogsError::MissingField("event ingestion time"))?;
+                Ok(Event {
+                    timestamp,
+                    message,
+                    ingestion_time,
+                    log_stream: None,
+                    log_group: None,
+                })
+            })
+            .collect::<Result<Vec<Event>, AwsLogsError>>()?;
+        all_events.extend(events.into_iter().filter(|e| grep_matches(grep, e)));
+        next_token = response.next_token;
+        let Some(ref token) = next_token else {
+            break;
+        };
+        i += 1;
+        if pagination.max_pages.is_some_and(|max| i >= max) {
+            warn!(
+                "stopped after {i} pages (--max-pages), output truncated; continue with --resume-token {token}"
+            );
+            break;
+        }
+    }
+    all_events.sort_by_key(|e| e.timestamp);
+    if let Some(tail_num) = tail {
+        let skip = all_events.len().saturating_sub(tail_num as usize);
+        all_events.drain(..skip);
+    }
+    Ok(all_events)
+}
+
+/// the streams of a log group matching filter, oldest first
+pub async fn get_sorted_log_streams(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    filter: &streams::StreamFilter,
+) -> Result<Vec<streams::StreamInfo>, AwsLogsError> {
+    streams::filtered_log_streams(client, log_group, filter).await
+}
+
+/// the names of the streams of a log group matching filter, oldest first
+pub async fn get_sorted_log_stream_names(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    filter: &streams::StreamFilter,
+) -> Result<Vec<String>, AwsLogsError> {
+    let names = get_sorted_log_streams(client, log_group, filter)
+        .await?
+        .into_iter()
+        .map(|stream| stream.name)
+        .collect::<Vec<String>>();
+    Ok(names)
+}
+
+/// the names of every log group (whose name starts with name_prefix when given, a filter done by
+/// CloudWatch), sorted. stops after max_pages pages, warning with the token to resume from
+pub async fn get_sorted_log_group_names(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    name_prefix: Option<&str>,
+    max_pages: u32,
+    resume_token: Option<String>,
+) -> Result<Vec<String>, AwsLogsError> {
+    let listing =
+        groups::sorted_log_groups(client, name_prefix, max_pages, resume_token, None).await?;
+    Ok(listing.groups.into_iter().map(|group| group.name).collect())
+}
@@ -0,0 +1,210 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use aws_sdk_cloudwatchlogs::operation::start_live_tail::StartLiveTailOutput;
+use aws_sdk_cloudwatchlogs::types::StartLiveTailResponseStream;
+use aws_smithy_types::error::display::DisplayErrorContext;
+use log::{debug, info, warn};
+
+use crate::error::AwsLogsError;
+use crate::Event;
+
+/// give up after this many failed sessions in a row
+const MAX_RECONNECTS: u32 = 5;
+
+/// wait this long before the first reconnect, doubling with each failure in a row
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// StartLiveTail only takes log group ARNs, without the trailing `:*`
+pub async fn log_group_arn(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+) -> Result<String, AwsLogsError> {
+    let response = client
+        .describe_log_groups()
+        .log_group_name_prefix(log_group)
+        .send()
+        .await
+        .map_err(|e| AwsLogsError::sdk(&format!("failed to describe log group {log_group}"), &e))?;
+    response
+        .log_groups
+        .unwrap_or_default()
+        .into_iter()
+        .find(|g| g.log_group_name.as_deref() == Some(log_group))
+        .and_then(|g| g.arn)
+        .map(|arn| arn.trim_end_matches(":*").to_string())
+        .ok_or_else(|| AwsLogsError::InvalidArgument(format!("no log group named {log_group}")))
+}
+
+/// the name of the log group Live Tail labels an event with, given as one of the ARNs the
+/// session was started with
+fn group_name(identifier: &str) -> String {
+    let identifier = identifier.trim_end_matches(":*");
+    identifier
+        .split_once(":log-group:")
+        .map_or(identifier, |(_, name)| name)
+        .to_string()
+}
+
+async fn start_session(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    group_arns: &[String],
+    stream_prefixes: &[String],
+    filter_pattern: Option<&str>,
+) -> Result<StartLiveTailOutput, AwsLogsError> {
+    client
+        .start_live_tail()
+        .set_log_group_identifiers(Some(group_arns.to_vec()))
+        .set_log_stream_name_prefixes(
+            (!stream_prefixes.is_empty()).then(|| stream_prefixes.to_vec()),
+        )
+        .set_log_event_filter_pattern(filter_pattern.map(|p| p.to_string()))
+        .send()
+        .await
+        .map_err(|e| AwsLogsError::sdk("failed to start live tail", &e))
+}
+
+/// streams events from a CloudWatch Logs Live Tail session as they are ingested, starting a new
+/// session when one ends (sessions last at most 3 hours) or is interrupted
+pub struct LiveTail<'a> {
+    client: &'a aws_sdk_cloudwatchlogs::Client,
+    group_arns: Vec<String>,
+    stream_prefixes: &'a [String],
+    filter_pattern: Option<&'a str>,
+    session: Option<StartLiveTailOutput>,
+    /// whether the current session sent an update, one that ends without any failed
+    updated: bool,
+    /// sessions that failed to start, broke off or ended without an update, in a row
+    failures: u32,
+    interrupted: Pin<Box<dyn Future<Output = std::io::Result<()>> + Send>>,
+}
+
+impl<'a> LiveTail<'a> {
+    /// stream_prefixes limits the session to streams starting with any of them, all streams
+    /// when empty. filter_pattern is a CloudWatch Logs filter pattern events must match
+    pub fn new(
+        client: &'a aws_sdk_cloudwatchlogs::Client,
+        group_arns: Vec<String>,
+        stream_prefixes: &'a [String],
+        filter_pattern: Option<&'a str>,
+    ) -> LiveTail<'a> {
+        LiveTail {
+            client,
+            group_arns,
+            stream_prefixes,
+            filter_pattern,
+            session: None,
+            updated: false,
+            failures: 0,
+            interrupted: Box::pin(tokio::signal::ctrl_c()),
+        }
+    }
+
+    /// count a failed session and wait before the next one, longer after each failure in a
+    /// row. Err once there were too many, false when Ctrl-C is pressed while waiting
+    async fn back_off(&mut self, reason: String) -> Result<bool, AwsLogsError> {
+        self.session = None;
+        self.failures += 1;
+        if self.failures > MAX_RECONNECTS {
+            return Err(AwsLogsError::Sdk(format!(
+                "live tail failed {MAX_RECONNECTS} times in a row: {reason}"
+            )));
+        }
+        let delay = RECONNECT_DELAY * 2u32.pow(self.failures - 1);
+        warn!(
+            "live tail interrupted, reconnecting in {}: {reason}",
+            humantime::format_duration(delay)
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => Ok(true),
+            _ = &mut self.interrupted => Ok(false),
+        }
+    }
+
+    /// the next non-empty batch of events, labeled with their log stream (and log group, when
+    /// there are several), waiting for them as long as it takes. None once Ctrl-C is pressed
+    pub async fn next_batch(&mut self) -> Result<Option<Vec<Event>>, AwsLogsError> {
+        loop {
+            let session = match self.session {
+                Some(ref mut session) => session,
+                None => {
+                    let start = start_session(
+                        self.client,
+                        &self.group_arns,
+                        self.stream_prefixes,
+                        self.filter_pattern,
+                    );
+                    let started = tokio::select! {
+                        started = start => started,
+                        _ = &mut self.interrupted => return Ok(None),
+                    };
+                    match started {
+                        Ok(started) => {
+                            self.updated = false;
+                            self.session.insert(started)
+                        }
+                        // reconnecting won't help until the user logs in again
+                        Err(e @ AwsLogsError::SsoSessionExpired(_)) => return Err(e),
+                        Err(e) => {
+                            if !self.back_off(e.to_string()).await? {
+                                return Ok(None);
+                            }
+                            continue;
+                        }
+                    }
+                }
+            };
+            let received = tokio::select! {
+                received = session.response_stream.recv() => received,
+                _ = &mut self.interrupted => return Ok(None),
+            };
+            match received {
+                Ok(Some(StartLiveTailResponseStream::SessionStart(start))) => {
+                    debug!("live tail session started: {:?}", start.session_id());
+                }
+                Ok(Some(StartLiveTailResponseStream::SessionUpdate(update))) => {
+                    self.updated = true;
+                    self.failures = 0;
+                    // like fetched events, only labeled with their group when there are several
+                    let several_groups = self.group_arns.len() > 1;
+                    let events = update
+                        .session_results
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|e| Event {
+                            timestamp: e.timestamp.unwrap_or_default(),
+                            message: e.message.unwrap_or_default(),
+                            ingestion_time: e.ingestion_time.unwrap_or_default(),
+                            log_stream: e.log_stream_name,
+                            log_group: e
+                                .log_group_identifier
+                                .as_deref()
+                                .filter(|_| several_groups)
+                                .map(group_name),
+                        })
+                        .collect::<Vec<Event>>();
+                    if !events.is_empty() {
+                        return Ok(Some(events));
+                    }
+                }
+                Ok(Some(other)) => debug!("ignoring live tail message: {other:?}"),
+                Ok(None) if self.updated => {
+                    info!("live tail session ended, starting a new one");
+                    self.session = None;
+                }
+                Ok(None) => {
+                    let reason = "the session ended before sending anything".to_string();
+                    if !self.back_off(reason).await? {
+                        return Ok(None);
+                    }
+                }
+                Err(e) => {
+                    if !self.back_off(DisplayErrorContext(&e).to_string()).await? {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+    }
+}
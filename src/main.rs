@@ -1,35 +1,447 @@
-use clap::Parser;
-use std::collections::HashMap;
+// the terminal side of alog: prompts, the picker, the event view and the like, not part of the library
+mod bookmarks;
+mod cache;
+mod clipboard;
+mod color;
+mod completions;
+mod config;
+mod console;
+mod history;
+mod panes;
+mod picker;
+mod progress;
+mod repl;
+mod sso;
+mod subscribe;
 
+use clap::parser::ValueSource;
+use clap::{FromArgMatches, Parser, Subcommand};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Seek, Write};
+
+use rust_aws_logs::error::AwsLogsError;
+use rust_aws_logs::{
+    bucket, checkpoint, chunked, compress, dedup, encrypt, export, extract, follow, format, gaps,
+    glob, grafana, group_config, groups, histogram, iac, insights, jq, level, live, manifest,
+    merge, org, preview, ratelimit, recording, records, redact, regions, retention, rollup, rotate,
+    rules, sidecar, sink, stats, stitch, store, streams, timestamps,
+};
+use rust_aws_logs::{
+    fetch_entire_log, fetch_filtered_log, fetch_first_n_events, get_sorted_log_group_names,
+    get_sorted_log_stream_names, grep_matches, stream_log_pages, Event, Pagination,
+    DEFAULT_MAX_GROUP_PAGES, LAST_EVENT_TIME_LAG_MS, MAX_PAGE_LIMIT,
+};
+
+use aws_config::retry::RetryConfig;
 use aws_config::BehaviorVersion;
-use serde::{Deserialize, Serialize};
 use std::str;
 
+use futures::{StreamExt, TryStreamExt};
 use log::{debug, info};
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_override_self = true)]
 struct Args {
     /// list log groups in this AWS account
     #[arg(long, action = clap::ArgAction::SetTrue)]
     describe_log_groups: bool,
 
+    /// only list log groups whose name starts with PREFIX, filtered by CloudWatch so the rest
+    /// aren't paged through
+    #[arg(long, value_name = "PREFIX")]
+    log_group_prefix: Option<String>,
+
     /// list log streams in this log group
     #[arg(long, action = clap::ArgAction::SetTrue)]
     describe_log_streams: bool,
 
-    /// log stream to fetch contents of
+    #[command(flatten)]
+    log_groups: LogGroupArgs,
+
+    /// the first --log-group, the one everything that takes a single group uses
+    #[arg(skip)]
+    log_group: Option<String>,
+
+    /// the --log-group flags after the first
+    #[arg(skip)]
+    extra_log_groups: Vec<String>,
+
+    #[command(flatten)]
+    streams: StreamArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+
+    /// read the events of FILE, as written with --format ndjson (decompressed when it ends in
+    /// .gz, .zst or .xz), instead of fetching them. they're filtered by the time window, --tail,
+    /// --grep, --level, --jq and --redact-rules and printed like fetched ones, without calling AWS
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["offline", "output_file", "sink", "follow", "live", "cache", "verify_count", "filter_pattern", "rules", "dedup", "rollup", "histogram", "gap_threshold", "stitch_split_events", "pager", "copy"])]
+    input_file: Option<String>,
+
+    #[command(flatten)]
+    store: StoreArgs,
+
+    #[command(flatten)]
+    listing: ListingArgs,
+
+    #[command(flatten)]
+    stream_listing: StreamListingArgs,
+
+    #[command(flatten)]
+    start: WindowStartArgs,
+
+    #[command(flatten)]
+    end: WindowEndArgs,
+
+    /// keep polling the stream and print new events as they arrive (or append them to
+    /// --output-file), until Ctrl-C. starts at --start-time / --since, or now
+    #[arg(short = 'f', long, action = clap::ArgAction::SetTrue, conflicts_with_all = ["tail", "sink", "export_chunked", "end_time", "until", "pager", "copy", "log_stream_prefix", "all_streams", "stream_regex", "filter_pattern", "grep", "resume", "cache", "histogram", "offline"])]
+    follow: bool,
+
+    /// stream new events of --log-group with CloudWatch Logs Live Tail until Ctrl-C, prefixed
+    /// with their log stream. events arrive within about a second, unlike polling with --follow
+    #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with_all = ["follow", "log_stream", "tail", "output_file", "sink", "export_chunked", "pager", "copy", "log_stream_prefix", "all_streams", "stream_regex", "grep", "cache", "rollup", "histogram", "offline"])]
+    live: bool,
+
+    /// with --live, only stream log streams whose names start with PREFIX (repeatable)
+    #[arg(long, value_name = "PREFIX", requires = "live")]
+    live_stream_prefix: Vec<String>,
+
+    /// how often --follow polls for new events
+    #[arg(long, value_name = "DURATION", default_value = "2s", value_parser = humantime::parse_duration)]
+    follow_interval: std::time::Duration,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    single_stream: SingleStreamArgs,
+
+    #[command(flatten)]
+    filters: FilterArgs,
+
+    #[command(flatten)]
+    analysis: AnalysisArgs,
+
+    #[command(flatten)]
+    render: RenderArgs,
+
+    /// color lines by the level found in their message (ERROR, WARN, INFO, DEBUG, ...): auto (only
+    /// on a terminal, unless NO_COLOR is set), always, or never
+    #[arg(long, value_name = "WHEN", default_value = "auto", global = true)]
+    color: color::ColorChoice,
+
+    /// show rendered times (event timestamps, stream creation times, bookmarks) in this time
+    /// zone: UTC (the default), local, or an IANA name like Europe/Berlin
+    #[arg(long, value_name = "ZONE", value_parser = timestamps::parse_time_zone, global = true)]
+    timezone: Option<jiff::tz::TimeZone>,
+
+    /// run a CloudWatch Logs Insights query against --log-group and print the results as a table
+    #[arg(long, conflicts_with_all = ["stats", "count_by"])]
+    query: Option<String>,
+
+    /// interactive Insights query prompt with history and multi-line editing, querying --log-group
+    /// over --since
+    #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with_all = ["query", "stats", "count_by"])]
+    interactive: bool,
+
+    /// Insights query shortcut that runs `stats <EXPR>`, e.g. --stats 'count(*) by bin(5m)'
+    #[arg(long, value_name = "EXPR", conflicts_with = "count_by")]
+    stats: Option<String>,
+
+    /// Insights query shortcut that counts events per time bin (e.g. 5m, in ms, s, m, h or d) or per field value (e.g. field:level)
+    #[arg(long, value_name = "BIN|field:NAME")]
+    count_by: Option<String>,
+
+    /// open --log-group (and --log-stream, starting --since ago) in the CloudWatch console
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    open: bool,
+
+    #[command(flatten)]
+    view: ViewArgs,
+
+    /// list previously run commands, numbered for use with --rerun
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    history: bool,
+
+    /// re-run command N from --history, any other flags given override the recorded ones
+    #[arg(long, value_name = "N")]
+    rerun: Option<usize>,
+
+    /// save the --log-group / --log-stream position at --bookmark-time (default now) as NAME
+    #[arg(long, value_name = "NAME")]
+    bookmark_add: Option<String>,
+
+    /// time to bookmark with --bookmark-add, RFC3339 or epoch millis
+    #[arg(long, value_name = "TIME")]
+    bookmark_time: Option<String>,
+
+    /// list saved bookmarks
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    bookmarks: bool,
+
+    /// fetch the bookmarked stream starting from the bookmarked time
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["start_time", "log_stream_prefix", "all_streams", "stream_regex"])]
+    goto: Option<String>,
+
+    /// AWS profile to use, optionally in a specific region as PROFILE@REGION
+    /// (give two with --diff-config)
+    #[arg(long, value_name = "PROFILE[@REGION]", global = true)]
+    profile: Vec<String>,
+
+    /// config file context to take the default region, profile, log group, format and preview
+    /// settings from, instead of the one named by `context` in the config file
+    #[arg(long, value_name = "NAME", global = true)]
+    context: Option<String>,
+
+    /// AWS region to use instead of the one in the AWS config or environment, e.g. eu-west-1
+    #[arg(long, global = true)]
+    region: Option<String>,
+
+    /// IAM role to assume with STS before calling CloudWatch Logs, for roles in other accounts
+    #[arg(long, value_name = "ARN", global = true)]
+    role_arn: Option<String>,
+
+    /// external ID the --role-arn trust policy requires
+    #[arg(long, value_name = "ID", requires = "role_arn", global = true)]
+    external_id: Option<String>,
+
+    /// session name for --role-arn, shown in CloudTrail (default: alog)
+    #[arg(long, value_name = "NAME", requires = "role_arn", global = true)]
+    role_session_name: Option<String>,
+
+    /// retry throttled (ThrottlingException) and failed AWS calls up to this many times, waiting
+    /// with jittered exponential backoff in between
+    #[arg(long, value_name = "N", default_value_t = 5, global = true)]
+    max_retries: u32,
+
+    /// compare the configuration of --log-group (retention, KMS key, tags, subscription and
+    /// metric filters) between two --profile environments and print the differences
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    diff_config: bool,
+
+    /// print the --log-group configuration (group, retention, tags, metric and subscription
+    /// filters) as terraform or cloudformation, ready to import
+    #[arg(long, value_name = "FORMAT")]
+    export_iac: Option<iac::IacFormat>,
+
+    /// recommend a retention per log group (starting with --log-group, if given) from its stored
+    /// bytes, estimated ingestion and recent Insights queries, with projected monthly savings
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    retention_report: bool,
+
+    /// save every AWS API response into DIR, to reproduce this run later with --replay
+    #[arg(long, value_name = "DIR", conflicts_with = "replay", global = true)]
+    record: Option<String>,
+
+    /// serve AWS API responses from a --record directory instead of calling AWS
+    #[arg(long, value_name = "DIR", global = true)]
+    replay: Option<String>,
+
+    #[command(flatten)]
+    fan_out: FanOutArgs,
+
+    #[command(flatten)]
+    sink: SinkArgs,
+
+    /// interactively subscribe --log-group (or a group picked at the prompt) to a Kinesis or
+    /// Firehose destination, checking each step. the IAM role it needs is printed and, when asked
+    /// to, created with the aws cli
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    subscribe_wizard: bool,
+
+    /// print a completion script for bash, zsh or fish that also completes log group and log
+    /// stream names, e.g. `alog --completions bash > /etc/bash_completion.d/alog`
+    #[arg(long, value_name = "SHELL")]
+    completions: Option<completions::Shell>,
+
+    /// list log group or log stream names for the completion scripts
+    #[arg(long, hide = true)]
+    complete: Option<completions::CompletionKind>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// the preset `alog run` names, applied once the config file is loaded
+    #[arg(skip)]
+    preset: Option<String>,
+
+    /// the full-text query `alog search` runs against the event cache
+    #[arg(skip)]
+    search: Option<String>,
+
+    /// `alog stats` was run
+    #[arg(skip)]
+    group_stats: bool,
+
+    /// the streams `alog tail` follows side by side, when it was given several
+    #[arg(skip)]
+    panes: Vec<String>,
+
+    /// where `alog export` sends the log group
+    #[arg(skip)]
+    export_to: Option<export::S3Destination>,
+
+    /// the export task `alog import` loads
+    #[arg(skip)]
+    import_from: Option<export::ExportLocation>,
+
+    /// the log group `alog create-log-group` creates
+    #[arg(skip)]
+    create_log_group: Option<groups::NewGroup>,
+
+    /// `alog delete-log-group` was run
+    #[arg(skip)]
+    delete_log_group: bool,
+
+    /// the log groups (or globs) `alog delete-log-group` was given, as the argument or with
+    /// --log-group, never a default from the config file
+    #[arg(skip)]
+    delete_log_groups: Vec<String>,
+
+    /// `alog delete-log-group --yes` was given
+    #[arg(skip)]
+    yes: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct LogGroupArgs {
+    /// log group, picked from a list when left out in a terminal. a glob like
+    /// '/aws/lambda/orders-*' stands for every group it matches, for --describe-log-groups,
+    /// Insights queries, --live and fetching with --all-streams, --stream-regex or
+    /// --log-stream-prefix. repeat it to fetch the events of several groups (e.g. an app, its
+    /// sidecar and its load balancer), merged in time order and labeled with group and stream
+    #[arg(short = 'g', long = "log-group", value_name = "LOG_GROUP", action = clap::ArgAction::Append)]
+    log_group_args: Vec<String>,
+}
+
+/// which streams of the log group to fetch
+#[derive(clap::Args, Debug, Clone)]
+struct StreamArgs {
+    /// log stream to fetch contents of, picked from a list when left out in a terminal
     #[arg(short = 's', long)]
     log_stream: Option<String>,
 
-    /// log group
-    #[arg(short = 'g', long)]
-    log_group: Option<String>,
+    /// fetch every stream in --log-group whose name starts with PREFIX, output one after the
+    /// other (oldest first), labeling each line with its stream. with --describe-log-streams,
+    /// only list those streams (e.g. a date or an instance id), a filter done by CloudWatch
+    #[arg(long, value_name = "PREFIX", conflicts_with_all = ["log_stream", "export_chunked", "verify_count", "resume_token"])]
+    log_stream_prefix: Option<String>,
 
-    /// output file to write to
+    /// fetch every stream in --log-group (with events in the --start-time / --since window) and
+    /// merge them into one timeline, labeling each line with its stream
+    #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with_all = ["log_stream", "log_stream_prefix", "export_chunked", "verify_count", "resume_token"])]
+    all_streams: bool,
+
+    /// fetch every stream in --log-group (with events in the --start-time / --since window)
+    /// whose name matches this regex and merge them into one timeline, labeling each line with
+    /// its stream. with --describe-log-streams, only list those streams, paging stopping once
+    /// --limit of them are found
+    #[arg(long, value_name = "REGEX", conflicts_with_all = ["log_stream", "log_stream_prefix", "all_streams", "export_chunked", "verify_count", "resume_token"])]
+    stream_regex: Option<String>,
+}
+
+/// where the fetched events are written
+#[derive(clap::Args, Debug, Clone)]
+struct OutputArgs {
+    /// output file to write to (compressed when it ends in .gz, .zst or .xz, or with --compress),
+    /// `-` for stdout (without the header or colors) or a file descriptor like /dev/fd/3
     #[arg(short, long)]
     output_file: Option<String>,
 
+    /// compress --output-file on the fly whatever its name: gzip, zstd or xz
+    #[arg(long, value_name = "FORMAT", requires = "output_file")]
+    compress: Option<compress::Compression>,
+
+    /// roll --output-file over to FILE.1, FILE.2, ... once it reaches SIZE (e.g. 100MB), also when
+    /// following a stream into it
+    #[arg(long, value_name = "SIZE", value_parser = format::parse_bytes, requires = "output_file")]
+    rotate_size: Option<u64>,
+
+    /// with --output-file, also write SHA-256 checksums and a json manifest describing the output
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    manifest: bool,
+
+    /// next to --output-file, write a `.meta.jsonl` sidecar with each event's timestamp, ingestion
+    /// time, stream and byte offset into the text
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    meta_sidecar: bool,
+
+    /// encrypt --output-file to this age recipient or gpg key as it is written (repeatable)
+    #[arg(long, value_name = "RECIPIENT")]
+    encrypt_to: Vec<String>,
+
+    /// treat --output-file as a directory and append events to one file per UTC hour or day,
+    /// e.g. out/2024-05-01T13.log
+    #[arg(long)]
+    bucket_by: Option<bucket::BucketBy>,
+}
+
+/// the event store read instead of CloudWatch
+#[derive(clap::Args, Debug, Clone)]
+struct StoreArgs {
+    /// read events from the event store (of --cache and `alog import`) instead of CloudWatch,
+    /// without credentials or a network: `alog get`, `alog grep` (or --all-streams, --stream-regex
+    /// and --log-stream-prefix) and `alog stats`, with the same flags and output formats. a
+    /// stream is only as complete as what was stored of it
+    #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with_all = ["cache", "output_file", "sink", "filter_pattern", "rules", "dedup", "rollup", "histogram", "gap_threshold", "stitch_split_events", "pager", "copy"])]
+    offline: bool,
+
+    /// keep the event store of --cache, `alog import`, `alog search` and --offline in DIR instead
+    /// of the cache dir, e.g. to hand the events of an incident over
+    #[arg(long, value_name = "DIR")]
+    archive_dir: Option<std::path::PathBuf>,
+}
+
+/// how log groups and streams are listed
+#[derive(clap::Args, Debug, Clone)]
+struct ListingArgs {
+    /// show a table of each log stream's creation time and first and last event times (and its
+    /// size with --with-sizes), or of each log group's creation time, retention, stored bytes,
+    /// class and KMS key
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    details: bool,
+
+    /// list log groups or streams by name, creation (oldest first), size (smallest first: stored
+    /// bytes of groups, --with-sizes bytes of streams) or, for streams, last-event (most recent
+    /// first, as CloudWatch orders them). without it groups are listed by name and streams
+    /// oldest first, --limit taking the first N CloudWatch returns
+    #[arg(long, value_name = "ORDER")]
+    order_by: Option<streams::ListOrder>,
+
+    /// with --order-by, the other way around: last name, newest, largest or least recent first
+    #[arg(long, action = clap::ArgAction::SetTrue, requires = "order_by")]
+    desc: bool,
+
+    /// list at most N log groups or streams, after ordering. paging stops after N when
+    /// CloudWatch lists them in that order already or no order is asked for (groups by name,
+    /// streams without --order-by, by name or --order-by last-event, without --desc)
+    #[arg(long, value_name = "N")]
+    limit: Option<usize>,
+}
+
+/// which log streams are listed, and what's shown of them
+#[derive(clap::Args, Debug, Clone)]
+struct StreamListingArgs {
+    /// only list streams created at or after TIME (RFC3339 or epoch millis)
+    #[arg(long, value_name = "TIME")]
+    created_after: Option<String>,
+
+    /// only list streams created before TIME (RFC3339 or epoch millis)
+    #[arg(long, value_name = "TIME")]
+    created_before: Option<String>,
+
+    /// only list streams with events at or after TIME (RFC3339 or epoch millis). CloudWatch
+    /// updates a stream's last event time within about an hour
+    #[arg(long, value_name = "TIME")]
+    last_event_after: Option<String>,
+
+    /// show each stream's approximate event count and size over --since (default 1h), from one
+    /// Insights query
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    with_sizes: bool,
+
     /// get previews of the log streams when listing log groups, up to N events
     #[arg(long, default_value_t = 0)]
     preview_lines: u32,
@@ -37,351 +449,3373 @@ struct Args {
     /// get previews of the log streams when listing log groups, up to N most recent streams
     #[arg(long, default_value_t = 0)]
     preview_streams: u32,
+}
+
+/// where the time window starts
+#[derive(clap::Args, Debug, Clone)]
+struct WindowStartArgs {
+    /// only fetch events at or after TIME (RFC3339 or epoch millis)
+    #[arg(long, value_name = "TIME")]
+    start_time: Option<String>,
+
+    /// how far back fetches, Insights queries and --open look, e.g. 30m, 6h, 2days
+    /// [default: 1h for queries, the whole stream for fetches]
+    #[arg(long, value_parser = humantime::parse_duration, conflicts_with = "start_time")]
+    since: Option<std::time::Duration>,
+}
+
+/// where the time window ends
+#[derive(clap::Args, Debug, Clone)]
+struct WindowEndArgs {
+    /// only fetch events before TIME (RFC3339 or epoch millis)
+    #[arg(long, value_name = "TIME")]
+    end_time: Option<String>,
+
+    /// only fetch events from more than this long ago, e.g. 10m
+    #[arg(long, value_parser = humantime::parse_duration, conflicts_with = "end_time")]
+    until: Option<std::time::Duration>,
+}
+
+/// which events are fetched and how fast
+#[derive(clap::Args, Debug, Clone)]
+struct FetchArgs {
+    /// with --log-stream-prefix, --stream-regex or --all-streams, how many streams to download at
+    /// once
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    concurrency: usize,
+
+    /// only fetch events matching this CloudWatch Logs filter pattern, e.g. '"timed out"' or
+    /// '{ $.status >= 500 }'. the filtering is done by CloudWatch (FilterLogEvents), so only
+    /// matches are downloaded
+    #[arg(long, value_name = "PATTERN")]
+    filter_pattern: Option<String>,
+
+    /// only keep events whose message matches this regex, checked as each page is fetched so
+    /// nothing else is held in memory. applies after --filter-pattern
+    #[arg(long, value_name = "REGEX")]
+    grep: Option<String>,
 
     /// view just the last N lines
     #[arg(short, long)]
     tail: Option<u32>,
+
+    /// events per GetLogEvents page, up to 10000 (pages are also capped at 1MB) [default: as
+    /// many as fit in 1MB]. smaller pages help on slow links
+    #[arg(long, value_name = "N")]
+    page_limit: Option<u32>,
+
+    /// stop paginating after N pages and report the token to resume from
+    /// [default: 100 for log group listings, unlimited for events]
+    #[arg(long, value_name = "N")]
+    max_pages: Option<u32>,
+
+    /// make at most this many GetLogEvents calls per second, across every stream and preview
+    /// fetched, to stay clear of account level API limits (e.g. 5 or 0.5)
+    #[arg(long, value_name = "N")]
+    max_rps: Option<f64>,
+
+    /// keep fetched events in a local SQLite database (events.sqlite in the cache dir or
+    /// --archive-dir), apart per account, region and profile, so fetching a stream again only requests what was written
+    /// since. not used with --tail or --filter-pattern
+    #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "max_pages")]
+    cache: bool,
+}
+
+/// what only works on a fetch of one log stream
+#[derive(clap::Args, Debug, Clone)]
+struct SingleStreamArgs {
+    /// continue a fetch or log group listing cut off by --max-pages from the reported token
+    #[arg(long, value_name = "TOKEN", conflicts_with = "cache")]
+    resume_token: Option<String>,
+
+    /// continue a download into --output-file that was interrupted, from the checkpoint saved
+    /// next to it (FILE.checkpoint.json) instead of the head of the stream
+    #[arg(long, action = clap::ArgAction::SetTrue, requires = "output_file", conflicts_with_all = ["resume_token", "cache"])]
+    resume: bool,
+
+    /// after fetching, cross-check the event count against a CloudWatch Logs Insights count(*),
+    /// before --level, --jq and the other filters. skipped with --tail or --grep
+    #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with_all = ["filter_pattern", "offline"])]
+    verify_count: bool,
+
+    /// treat --output-file as a directory and export the stream as one file per WINDOW of its
+    /// time range (e.g. 1h), filtered by --level, --jq and --redact-rules, resuming an
+    /// interrupted export by skipping finished chunks
+    #[arg(long, value_name = "WINDOW", value_parser = humantime::parse_duration, conflicts_with_all = ["bucket_by", "tail", "start_time", "end_time", "since", "until", "grep", "filter_pattern"])]
+    export_chunked: Option<std::time::Duration>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct EventLog {
-    #[serde(rename = "events")]
-    events: Vec<Event>,
+/// which of the fetched events are kept, and how their messages are changed
+#[derive(clap::Args, Debug, Clone)]
+struct FilterArgs {
+    /// yaml file of named regex -> replacement rules applied to every message on output
+    #[arg(long, value_name = "FILE")]
+    redact_rules: Option<String>,
+
+    /// only show lines at these log levels, e.g. `warn+` (warn and above), `error`, or `info,error`
+    #[arg(long)]
+    level: Option<level::LevelFilter>,
+
+    /// only show lines at this log level or more severe, e.g. `warn` for warnings and errors
+    /// (the same as `--level warn+`)
+    #[arg(long, value_name = "LEVEL", conflicts_with = "level")]
+    min_level: Option<level::Level>,
+
+    /// run this jq expression on every JSON message and output what it outputs instead, e.g.
+    /// `select(.status >= 500) | .path`. events it outputs nothing for, or that aren't JSON,
+    /// are dropped
+    #[arg(long, value_name = "EXPR")]
+    jq: Option<String>,
 
-    #[serde(rename = "nextForwardToken")]
-    next_forward_token: String,
+    /// collapse consecutive identical messages into one line with a repeat count, like
+    /// `uniq -c`, e.g. `retrying request ×38`
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    dedup: bool,
 
-    #[serde(rename = "nextBackwardToken")]
-    next_backward_token: String,
+    /// yaml file of alert rules (pattern, severity, and highlight/bell/webhook/exit actions)
+    /// evaluated against every tailed event. only events ingested after alog started alert, the
+    /// ones caught up on from --since are just highlighted, as are the events of a fetch
+    #[arg(long, value_name = "FILE")]
+    rules: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct Event {
-    #[serde(rename = "timestamp")]
-    timestamp: i64,
+/// what's made of the fetched events besides printing them
+#[derive(clap::Args, Debug, Clone)]
+struct AnalysisArgs {
+    /// collapse repeats of the same error within each window of this length into one summary
+    /// line, e.g. `ERROR connection refused ×214 in last 1m`. with --follow, a window's summary
+    /// is printed once it's over, new events or not
+    #[arg(long, value_name = "WINDOW", value_parser = humantime::parse_duration)]
+    rollup: Option<std::time::Duration>,
+
+    /// bin events by this bucket of event time (e.g. 1m) and print a bar chart of how many
+    /// there were instead of the events, which still go to --output-file when given. at most
+    /// 10000 buckets are drawn
+    #[arg(long, value_name = "BUCKET", value_parser = humantime::parse_duration, conflicts_with_all = ["sink", "pager"])]
+    histogram: Option<std::time::Duration>,
 
-    #[serde(rename = "message")]
-    message: String,
+    /// mark stretches longer than this with no events (e.g. 5m) inline, and list them at the end.
+    /// a silence at the start or end of the time window counts too
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    gap_threshold: Option<std::time::Duration>,
 
-    #[serde(rename = "ingestionTime")]
-    ingestion_time: i64,
+    /// rejoin messages the producer split across consecutive events at the 256KB event size
+    /// limit, so large JSON payloads come out whole
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    stitch_split_events: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LogGroupsResponse {
-    #[serde(rename = "logGroups")]
-    log_groups: Vec<LogGroup>,
+/// how each event is rendered
+#[derive(clap::Args, Debug, Clone)]
+struct RenderArgs {
+    /// prefix each line with its timestamp: rfc3339, relative (to now, e.g. -3m12s), since-start
+    /// (e.g. +3m12s), or a strftime pattern like '%Y-%m-%d %H:%M:%S.%L'
+    #[arg(long)]
+    time_format: Option<timestamps::TimeFormat>,
+
+    /// output format: text, grafana (JSON datasource table, for Insights query results), json
+    /// (for --describe-log-streams and its previews), ndjson (a JSON object per fetched event) or
+    /// csv (a row per fetched event, see --csv-columns)
+    #[arg(long, default_value = "text")]
+    format: format::OutputFormat,
+
+    /// columns of --format csv, from timestamp, ingestion_time, log_group, log_stream, level and
+    /// message
+    #[arg(long, value_name = "COLUMNS", default_value = "timestamp,message")]
+    csv_columns: records::Columns,
+
+    /// output only these fields of JSON messages, e.g. `level,msg,req.user.id` (array elements
+    /// by index, e.g. `items.0`). lines that aren't a JSON object are output as they are
+    #[arg(long, value_name = "FIELDS")]
+    extract: Option<extract::Fields>,
+
+    /// what goes between --extract fields: tab or comma (quoted like csv where needed)
+    #[arg(long, value_name = "SEP", default_value = "tab")]
+    extract_separator: extract::Separator,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LogGroup {
-    #[serde(rename = "logGroupName")]
-    log_group_name: String,
+/// where the fetched text is shown besides the terminal
+#[derive(clap::Args, Debug, Clone)]
+struct ViewArgs {
+    /// also copy the fetched text to the system clipboard (the last 1MB at most)
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    copy: bool,
+
+    /// page through the fetched events on the terminal, searching them as you type (/pattern,
+    /// then n and N for the next and previous match) and filtering them (&pattern)
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pager: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LogStreamsResponse {
-    #[serde(rename = "logStreams")]
-    log_streams: Vec<LogStream>,
+/// the accounts and regions to run in besides the one of --profile
+#[derive(clap::Args, Debug, Clone)]
+struct FanOutArgs {
+    /// run --describe-log-groups, --describe-log-streams, an Insights query or a --log-stream
+    /// fetch in every active account of the AWS Organization, labeling output by account
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    org_accounts: bool,
+
+    /// role to assume in each account with --org-accounts
+    #[arg(long, value_name = "ROLE", default_value = org::DEFAULT_ROLE_NAME)]
+    org_role: String,
+
+    /// like --org-accounts, but in the accounts of FILE, a TOML file of `[[account]]` tables
+    /// with an alias, a role_arn to assume and optionally an external_id
+    #[arg(long, value_name = "FILE", conflicts_with = "org_accounts")]
+    accounts: Option<String>,
+
+    /// with --org-accounts, --accounts or --regions, merge the events of a --log-stream fetch
+    /// into one timeline instead of printing them account (or region) by account
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    merge_accounts: bool,
+
+    /// run --describe-log-groups, --describe-log-streams, an Insights query or a --log-stream
+    /// fetch in each of these comma separated regions, labeling output by region. with
+    /// --org-accounts or --accounts, in each region of each account
+    #[arg(
+        long,
+        value_name = "REGIONS",
+        value_delimiter = ',',
+        conflicts_with = "all_regions"
+    )]
+    regions: Vec<String>,
+
+    /// like --regions, in every region the account has enabled (needs account:ListRegions)
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    all_regions: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LogStream {
-    #[serde(rename = "logStreamName")]
-    log_stream_name: String,
+/// where the fetched events are sent instead of printing them
+#[derive(clap::Args, Debug, Clone)]
+struct SinkArgs {
+    /// send fetched events somewhere instead of printing them: sqs (to --queue-url)
+    #[arg(long, value_name = "SINK", conflicts_with = "output_file")]
+    sink: Option<sink::SinkKind>,
 
-    #[serde(rename = "creationTime")]
-    creation_time: i64,
+    /// SQS queue to send events to with --sink sqs, one message per event with logGroup,
+    /// logStream and timestamp attributes
+    #[arg(long, value_name = "URL")]
+    queue_url: Option<String>,
 }
 
-async fn fetch_single_log_page(
-    client: &aws_sdk_cloudwatchlogs::Client,
-    log_group: &str,
-    log_stream: &str,
-    fwd_token: Option<&str>,
-    limit: Option<i32>,
-    from_tail: Option<bool>,
-) -> Result<EventLog, String> {
-    let token_disp = fwd_token.unwrap_or("None");
-    let limit_disp = limit.unwrap_or(-1);
-    debug!(
-        "fetch single log page for: {log_stream}, token: {}, limit: {}",
-        token_disp, limit_disp
-    );
-    let mut bld = client
-        .get_log_events()
-        .log_stream_name(log_stream)
-        .log_group_name(log_group)
-        .start_from_head(true);
-    // determine which page to get
-    if let Some(token) = fwd_token {
-        bld = bld.next_token(token);
-    }
-    if let Some(lmt) = limit {
-        bld = bld.limit(lmt);
-    }
-    if let Some(tail) = from_tail {
-        bld = bld.start_from_head(!tail);
-    }
-    let response = bld.send().await.unwrap();
-    let events = response.events.unwrap();
-    let my_events = events
-        .into_iter()
-        .map(|event| {
-            let timestamp = event.timestamp.unwrap();
-            let message = event.message.unwrap();
-            let ingestion_time = event.ingestion_time.unwrap();
-            Event {
-                timestamp,
-                message,
-                ingestion_time,
-            }
-        })
-        .collect::<Vec<Event>>();
-    let eventlog: EventLog = EventLog {
-        events: my_events,
-        next_forward_token: response.next_forward_token.unwrap(),
-        next_backward_token: response.next_backward_token.unwrap(),
-    };
-    Ok(eventlog)
+/// fetch, follow, query and list CloudWatch Logs, each subcommand taking the flags that apply to
+/// it (see `alog help <COMMAND>`), e.g. `alog get /ecs/app ecs/app/1234 --tail 50`. without one,
+/// every flag is taken
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// list log groups (like --describe-log-groups)
+    Groups {
+        #[command(flatten)]
+        log_groups: LogGroupArgs,
+        /// only list log groups whose name starts with PREFIX, filtered by CloudWatch so the
+        /// rest aren't paged through
+        #[arg(long, value_name = "PREFIX")]
+        log_group_prefix: Option<String>,
+        #[command(flatten)]
+        listing: ListingArgs,
+        /// stop listing after N pages (default 100) and report the token to resume from
+        #[arg(long, value_name = "N")]
+        max_pages: Option<u32>,
+        /// continue a listing cut off by --max-pages from the reported token
+        #[arg(long, value_name = "TOKEN")]
+        resume_token: Option<String>,
+        #[command(flatten)]
+        fan_out: FanOutArgs,
+    },
+    /// list the log streams of a log group (like --describe-log-streams)
+    Streams {
+        #[arg(value_name = "LOG_GROUP")]
+        group: String,
+        /// only list the streams whose name starts with PREFIX (e.g. a date or an instance id),
+        /// a filter done by CloudWatch
+        #[arg(long, value_name = "PREFIX")]
+        log_stream_prefix: Option<String>,
+        /// only list the streams whose name matches this regex, paging stopping once --limit
+        /// of them are found
+        #[arg(long, value_name = "REGEX")]
+        stream_regex: Option<String>,
+        #[command(flatten)]
+        listing: ListingArgs,
+        #[command(flatten)]
+        stream_listing: StreamListingArgs,
+        /// how far back --with-sizes counts each stream's events and bytes, e.g. 1d [default: 1h]
+        #[arg(long, value_parser = humantime::parse_duration)]
+        since: Option<std::time::Duration>,
+        #[command(flatten)]
+        filters: FilterArgs,
+        #[command(flatten)]
+        render: RenderArgs,
+        #[command(flatten)]
+        fan_out: FanOutArgs,
+    },
+    /// fetch the events of a log stream
+    Get {
+        #[arg(value_name = "LOG_GROUP")]
+        group: String,
+        #[arg(value_name = "LOG_STREAM")]
+        stream: String,
+        #[command(flatten)]
+        start: WindowStartArgs,
+        #[command(flatten)]
+        end: WindowEndArgs,
+        #[command(flatten)]
+        fetch: FetchArgs,
+        #[command(flatten)]
+        single_stream: SingleStreamArgs,
+        #[command(flatten)]
+        filters: FilterArgs,
+        #[command(flatten)]
+        analysis: AnalysisArgs,
+        #[command(flatten)]
+        render: RenderArgs,
+        #[command(flatten)]
+        view: ViewArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+        #[command(flatten)]
+        sink: SinkArgs,
+        #[command(flatten)]
+        store: StoreArgs,
+        #[command(flatten)]
+        fan_out: FanOutArgs,
+    },
+    /// print new events of a log stream as they are written (like --follow), or of every stream
+    /// in the log group when no stream is given (like --live). given two to four streams, they're
+    /// tailed side by side in panes that scroll back on their own (tab switches between them),
+    /// e.g. to watch a deployment roll across instances
+    Tail {
+        #[arg(value_name = "LOG_GROUP")]
+        group: String,
+        #[arg(value_name = "LOG_STREAM")]
+        streams: Vec<String>,
+        #[command(flatten)]
+        start: WindowStartArgs,
+        /// with no stream given, only stream events matching this CloudWatch Logs filter
+        /// pattern, e.g. '"timed out"', filtered by Live Tail
+        #[arg(long, value_name = "PATTERN", conflicts_with = "streams")]
+        filter_pattern: Option<String>,
+        /// with no stream given, only stream the log streams whose names start with PREFIX
+        /// (repeatable)
+        #[arg(long, value_name = "PREFIX", conflicts_with = "streams")]
+        live_stream_prefix: Vec<String>,
+        /// with one stream, collapse repeats of the same error within each window of this
+        /// length into one summary line, printed once the window is over
+        #[arg(long, value_name = "WINDOW", value_parser = humantime::parse_duration)]
+        rollup: Option<std::time::Duration>,
+        /// how often the streams are polled for new events
+        #[arg(long, value_name = "DURATION", default_value = "2s", value_parser = humantime::parse_duration)]
+        follow_interval: std::time::Duration,
+        #[command(flatten)]
+        filters: FilterArgs,
+        #[command(flatten)]
+        render: RenderArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// run a CloudWatch Logs Insights query against a log group (like --query)
+    Query {
+        #[arg(value_name = "LOG_GROUP")]
+        group: String,
+        #[arg(value_name = "QUERY")]
+        insights_query: String,
+        /// how far back to query, e.g. 30m, 6h, 2days [default: 1h]
+        #[arg(long, value_parser = humantime::parse_duration)]
+        since: Option<std::time::Duration>,
+        /// output format: text (a table) or grafana (JSON datasource table)
+        #[arg(long, default_value = "text")]
+        format: format::OutputFormat,
+        #[command(flatten)]
+        fan_out: FanOutArgs,
+    },
+    /// fetch or query with a preset from the config file, flags override its settings
+    Run {
+        #[arg(value_name = "PRESET")]
+        name: String,
+        #[command(flatten)]
+        log_groups: LogGroupArgs,
+        #[command(flatten)]
+        streams: StreamArgs,
+        #[command(flatten)]
+        start: WindowStartArgs,
+        #[command(flatten)]
+        end: WindowEndArgs,
+        #[command(flatten)]
+        fetch: FetchArgs,
+        #[command(flatten)]
+        single_stream: SingleStreamArgs,
+        #[command(flatten)]
+        filters: FilterArgs,
+        #[command(flatten)]
+        analysis: AnalysisArgs,
+        #[command(flatten)]
+        render: RenderArgs,
+        #[command(flatten)]
+        view: ViewArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+        #[command(flatten)]
+        sink: SinkArgs,
+        #[command(flatten)]
+        store: StoreArgs,
+        #[command(flatten)]
+        fan_out: FanOutArgs,
+    },
+    /// search the events kept with --cache or loaded with `alog import` without calling AWS, e.g.
+    /// `"connection reset"` or `timeout AND NOT healthcheck`, narrowed by --log-group,
+    /// --log-stream, the time window and --tail (the last N matches). messages are redacted like
+    /// fetched ones
+    Search {
+        #[arg(value_name = "QUERY")]
+        query: String,
+        #[command(flatten)]
+        log_groups: LogGroupArgs,
+        /// only search the events of this log stream
+        #[arg(short = 's', long)]
+        log_stream: Option<String>,
+        #[command(flatten)]
+        start: WindowStartArgs,
+        #[command(flatten)]
+        end: WindowEndArgs,
+        /// only the last N matches
+        #[arg(short, long)]
+        tail: Option<u32>,
+        /// yaml file of named regex -> replacement rules applied to the matches, instead of the
+        /// ones the config file has for their log group
+        #[arg(long, value_name = "FILE")]
+        redact_rules: Option<String>,
+        /// search the event store in DIR instead of the cache dir, e.g. one handed over with an
+        /// incident
+        #[arg(long, value_name = "DIR")]
+        archive_dir: Option<std::path::PathBuf>,
+    },
+    /// summarize a log group: stream count, stored bytes, first and last event times per stream,
+    /// and with --with-sizes each stream's events and bytes over --since (default 1h)
+    Stats {
+        #[arg(value_name = "GROUP")]
+        group: Option<String>,
+        #[command(flatten)]
+        log_groups: LogGroupArgs,
+        /// also count each stream's events and bytes over --since, from one Insights query
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        with_sizes: bool,
+        /// how far back --with-sizes counts, e.g. 1d [default: 1h]
+        #[arg(long, value_parser = humantime::parse_duration)]
+        since: Option<std::time::Duration>,
+        /// output format: text or json
+        #[arg(long, default_value = "text")]
+        format: format::OutputFormat,
+        /// summarize what the event store (of --cache and `alog import`) holds of the group
+        /// instead, without calling AWS
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        offline: bool,
+        /// with --offline, read the event store in DIR instead of the cache dir
+        #[arg(long, value_name = "DIR")]
+        archive_dir: Option<std::path::PathBuf>,
+    },
+    /// search every stream of --log-group with events in the time window (e.g. --since 6h, the
+    /// last hour by default) for lines matching a regex, printed with their stream and timestamp
+    /// (like --all-streams --grep)
+    Grep {
+        #[arg(value_name = "REGEX", conflicts_with = "grep")]
+        pattern: String,
+        #[command(flatten)]
+        log_groups: LogGroupArgs,
+        #[command(flatten)]
+        start: WindowStartArgs,
+        #[command(flatten)]
+        end: WindowEndArgs,
+        #[command(flatten)]
+        fetch: FetchArgs,
+        #[command(flatten)]
+        filters: FilterArgs,
+        #[command(flatten)]
+        analysis: AnalysisArgs,
+        #[command(flatten)]
+        render: RenderArgs,
+        #[command(flatten)]
+        view: ViewArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+        #[command(flatten)]
+        sink: SinkArgs,
+        #[command(flatten)]
+        store: StoreArgs,
+    },
+    /// export --log-group over the time window (e.g. --since 30d) to S3 with an export task,
+    /// only the streams starting with --log-stream-prefix when given, and wait for it to finish.
+    /// far cheaper than paging through months of events. with --output-file, the exported
+    /// objects are then downloaded and written to it one at a time, stream after stream, labeled
+    /// with their streams and filtered by --grep, --level, --jq and --redact-rules
+    Export {
+        #[arg(value_name = "S3_URL")]
+        destination: export::S3Destination,
+        #[command(flatten)]
+        log_groups: LogGroupArgs,
+        /// only export the streams whose name starts with PREFIX
+        #[arg(long, value_name = "PREFIX")]
+        log_stream_prefix: Option<String>,
+        #[command(flatten)]
+        start: WindowStartArgs,
+        #[command(flatten)]
+        end: WindowEndArgs,
+        /// only write the exported events whose message matches this regex to --output-file
+        #[arg(long, value_name = "REGEX")]
+        grep: Option<String>,
+        #[command(flatten)]
+        filters: FilterArgs,
+        #[command(flatten)]
+        render: RenderArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// load an export task into the event store `alog search` reads, like the events kept with
+    /// --cache, as the events of --log-group (exports don't name their group): its s3:// url as
+    /// `alog export` prints it, or a directory its objects were copied to. importing the same
+    /// export again replaces what it stored
+    Import {
+        #[arg(value_name = "DIR_OR_S3_URL")]
+        from: export::ExportLocation,
+        #[command(flatten)]
+        log_groups: LogGroupArgs,
+        /// load into the event store in DIR instead of the cache dir, e.g. to hand the events of
+        /// an incident over
+        #[arg(long, value_name = "DIR")]
+        archive_dir: Option<std::path::PathBuf>,
+    },
+    /// create a log group, with --tag, --kms-key-id, --log-group-class and --retention-days
+    /// applied in one go
+    CreateLogGroup {
+        #[arg(value_name = "LOG_GROUP")]
+        group: String,
+        /// a tag of the new group as KEY=VALUE (repeatable)
+        #[arg(long, value_name = "KEY=VALUE")]
+        tag: Vec<groups::Tag>,
+        /// the ARN of the KMS key to encrypt the new group with
+        #[arg(long, value_name = "ARN")]
+        kms_key_id: Option<String>,
+        /// standard or infrequent-access (cheaper, but without live tail, metric filters or
+        /// subscriptions)
+        #[arg(long, value_name = "CLASS")]
+        log_group_class: Option<groups::GroupClass>,
+        /// how long to keep events, one of the retentions CloudWatch Logs accepts (1, 3, 5, 7,
+        /// 14, 30, 60, 90, ... 3653). never expire when left out
+        #[arg(long, value_name = "DAYS")]
+        retention_days: Option<retention::RetentionDays>,
+    },
+    /// delete a log group and all of its events, or every group a glob matches, given here or
+    /// with --log-group (repeatable), or every group starting with --log-group-prefix, after
+    /// asking (or not, with --yes)
+    DeleteLogGroup {
+        #[arg(value_name = "LOG_GROUP")]
+        group: Option<String>,
+        #[command(flatten)]
+        log_groups: LogGroupArgs,
+        /// delete every log group whose name starts with PREFIX
+        #[arg(long, value_name = "PREFIX")]
+        log_group_prefix: Option<String>,
+        /// delete without asking first
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        yes: bool,
+    },
 }
 
-async fn fetch_first_n_events(
-    client: &aws_sdk_cloudwatchlogs::Client,
-    log_group: &str,
-    log_stream: &str,
-    limit: i32,
-) -> Vec<Event> {
-    if log_stream.starts_with("/") {
-        panic!("log_stream should probably not begin with / -> {log_stream}");
-    }
-    info!("fetch first N events from log stream - log_group: {log_group}, log_stream: {log_stream}, limit: {limit}");
-    let fwd_token: Option<&str> = None;
-    let event_log: EventLog =
-        fetch_single_log_page(client, &log_group, &log_stream, fwd_token, Some(limit), None)
-            .await
-            .unwrap_or_else(|e| panic!("failed to fetch single log page: {}", e));
-    // append all the events to all_events
-    let page_size = event_log.events.len();
-    info!("fetched single page, size: {page_size}, limit was: {limit}");
-    let mut all_events = event_log.events;
-    all_events.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-    all_events
+impl Args {
+    /// turn the subcommand into the flags it stands for, so the rest of main only looks at flags.
+    /// each subcommand only takes the flags that apply to it, clap rejects the rest
+    fn apply_command(&mut self) -> Result<(), String> {
+        // the group named by a subcommand's argument comes before any given with --log-group
+        let mut named_group = None;
+        if let Some(command) = self.command.take() {
+            match command {
+                Command::Groups {
+                    log_groups,
+                    log_group_prefix,
+                    listing,
+                    max_pages,
+                    resume_token,
+                    fan_out,
+                } => {
+                    self.log_groups = log_groups;
+                    self.log_group_prefix = log_group_prefix;
+                    self.listing = listing;
+                    self.fetch.max_pages = max_pages;
+                    self.single_stream.resume_token = resume_token;
+                    self.fan_out = fan_out;
+                    self.describe_log_groups = true;
+                }
+                Command::Streams {
+                    group,
+                    log_stream_prefix,
+                    stream_regex,
+                    listing,
+                    stream_listing,
+                    since,
+                    filters,
+                    render,
+                    fan_out,
+                } => {
+                    named_group = Some(group);
+                    self.streams.log_stream_prefix = log_stream_prefix;
+                    self.streams.stream_regex = stream_regex;
+                    self.listing = listing;
+                    self.stream_listing = stream_listing;
+                    self.start.since = since;
+                    self.filters = filters;
+                    self.render = render;
+                    self.fan_out = fan_out;
+                    self.describe_log_streams = true;
+                }
+                Command::Get {
+                    group,
+                    stream,
+                    start,
+                    end,
+                    fetch,
+                    single_stream,
+                    filters,
+                    analysis,
+                    render,
+                    view,
+                    output,
+                    sink,
+                    store,
+                    fan_out,
+                } => {
+                    named_group = Some(group);
+                    self.streams.log_stream = Some(stream);
+                    self.start = start;
+                    self.end = end;
+                    self.fetch = fetch;
+                    self.single_stream = single_stream;
+                    self.filters = filters;
+                    self.analysis = analysis;
+                    self.render = render;
+                    self.view = view;
+                    self.output = output;
+                    self.sink = sink;
+                    self.store = store;
+                    self.fan_out = fan_out;
+                }
+                Command::Tail {
+                    group,
+                    streams,
+                    start,
+                    filter_pattern,
+                    live_stream_prefix,
+                    rollup,
+                    follow_interval,
+                    filters,
+                    render,
+                    output,
+                } => {
+                    if streams.len() > panes::MAX_PANES {
+                        return Err(format!(
+                            "`alog tail` follows at most {} streams side by side, not {}",
+                            panes::MAX_PANES,
+                            streams.len()
+                        ));
+                    }
+                    if streams.len() != 1 && (output.output_file.is_some() || rollup.is_some()) {
+                        return Err(
+                            "--output-file and --rollup only work when `alog tail` follows one stream"
+                                .to_string(),
+                        );
+                    }
+                    named_group = Some(group);
+                    self.start = start;
+                    self.fetch.filter_pattern = filter_pattern;
+                    self.live_stream_prefix = live_stream_prefix;
+                    self.analysis.rollup = rollup;
+                    self.follow_interval = follow_interval;
+                    self.filters = filters;
+                    self.render = render;
+                    self.output = output;
+                    match <[String; 1]>::try_from(streams) {
+                        Ok([stream]) => {
+                            self.streams.log_stream = Some(stream);
+                            self.follow = true;
+                        }
+                        Err(streams) if streams.is_empty() => self.live = true,
+                        Err(streams) => self.panes = streams,
+                    }
+                }
+                Command::Query {
+                    group,
+                    insights_query,
+                    since,
+                    format,
+                    fan_out,
+                } => {
+                    named_group = Some(group);
+                    self.query = Some(insights_query);
+                    self.start.since = since;
+                    self.render.format = format;
+                    self.fan_out = fan_out;
+                }
+                Command::Run {
+                    name,
+                    log_groups,
+                    streams,
+                    start,
+                    end,
+                    fetch,
+                    single_stream,
+                    filters,
+                    analysis,
+                    render,
+                    view,
+                    output,
+                    sink,
+                    store,
+                    fan_out,
+                } => {
+                    self.preset = Some(name);
+                    self.log_groups = log_groups;
+                    self.streams = streams;
+                    self.start = start;
+                    self.end = end;
+                    self.fetch = fetch;
+                    self.single_stream = single_stream;
+                    self.filters = filters;
+                    self.analysis = analysis;
+                    self.render = render;
+                    self.view = view;
+                    self.output = output;
+                    self.sink = sink;
+                    self.store = store;
+                    self.fan_out = fan_out;
+                }
+                Command::Search {
+                    query,
+                    log_groups,
+                    log_stream,
+                    start,
+                    end,
+                    tail,
+                    redact_rules,
+                    archive_dir,
+                } => {
+                    self.search = Some(query);
+                    self.log_groups = log_groups;
+                    self.streams.log_stream = log_stream;
+                    self.start = start;
+                    self.end = end;
+                    self.fetch.tail = tail;
+                    self.filters.redact_rules = redact_rules;
+                    self.store.archive_dir = archive_dir;
+                }
+                Command::Stats {
+                    group,
+                    log_groups,
+                    with_sizes,
+                    since,
+                    format,
+                    offline,
+                    archive_dir,
+                } => {
+                    named_group = group;
+                    self.log_groups = log_groups;
+                    self.stream_listing.with_sizes = with_sizes;
+                    self.start.since = since;
+                    self.render.format = format;
+                    self.store.offline = offline;
+                    self.store.archive_dir = archive_dir;
+                    self.group_stats = true;
+                }
+                Command::Grep {
+                    pattern,
+                    log_groups,
+                    start,
+                    end,
+                    fetch,
+                    filters,
+                    analysis,
+                    render,
+                    view,
+                    output,
+                    sink,
+                    store,
+                } => {
+                    self.log_groups = log_groups;
+                    self.start = start;
+                    self.end = end;
+                    self.fetch = fetch;
+                    self.filters = filters;
+                    self.analysis = analysis;
+                    self.render = render;
+                    self.view = view;
+                    self.output = output;
+                    self.sink = sink;
+                    self.store = store;
+                    // every stream with events in the window is read, so keep the window bounded
+                    if self.start.since.is_none() && self.start.start_time.is_none() {
+                        self.start.since = Some(DEFAULT_QUERY_WINDOW);
+                    }
+                    self.streams.all_streams = true;
+                    self.fetch.grep = Some(pattern);
+                    if self.render.time_format.is_none() {
+                        self.render.time_format = Some(timestamps::TimeFormat::Rfc3339);
+                    }
+                }
+                Command::Export {
+                    destination,
+                    log_groups,
+                    log_stream_prefix,
+                    start,
+                    end,
+                    grep,
+                    filters,
+                    render,
+                    output,
+                } => {
+                    self.export_to = Some(destination);
+                    self.log_groups = log_groups;
+                    self.streams.log_stream_prefix = log_stream_prefix;
+                    self.start = start;
+                    self.end = end;
+                    self.fetch.grep = grep;
+                    self.filters = filters;
+                    self.render = render;
+                    self.output = output;
+                }
+                Command::Import {
+                    from,
+                    log_groups,
+                    archive_dir,
+                } => {
+                    self.import_from = Some(from);
+                    self.log_groups = log_groups;
+                    self.store.archive_dir = archive_dir;
+                }
+                Command::CreateLogGroup {
+                    group,
+                    tag,
+                    kms_key_id,
+                    log_group_class,
+                    retention_days,
+                } => {
+                    self.create_log_group = Some(groups::NewGroup {
+                        name: group,
+                        tags: tag,
+                        kms_key_id,
+                        class: log_group_class,
+                        retention_days: retention_days.map(|days| days.0),
+                    });
+                }
+                Command::DeleteLogGroup {
+                    group,
+                    log_groups,
+                    log_group_prefix,
+                    yes,
+                } => {
+                    // kept apart from --log-group, so no config file default can stand in for a
+                    // group
+                    self.delete_log_groups =
+                        group.into_iter().chain(log_groups.log_group_args).collect();
+                    self.log_group_prefix = log_group_prefix;
+                    self.yes = yes;
+                    self.delete_log_group = true;
+                }
+            }
+        }
+        let mut log_groups = std::mem::take(&mut self.log_groups.log_group_args).into_iter();
+        self.log_group = log_groups.next();
+        self.extra_log_groups = log_groups.collect();
+        if named_group.is_some() {
+            self.log_group = named_group;
+        }
+        if let Some(min_level) = self.filters.min_level.take() {
+            self.filters.level = Some(level::LevelFilter::AtLeast(min_level));
+        }
+        Ok(())
+    }
 }
 
-async fn fetch_entire_log(
-    client: &aws_sdk_cloudwatchlogs::Client,
-    log_group: &str,
-    log_stream: &str,
-    tail: Option<u32>,
-) -> Vec<Event> {
-    if log_stream.starts_with("/") {
-        panic!("log_stream should probably not begin with / -> {log_stream}");
+/// longest wait between retries of a throttled or failed AWS call
+const MAX_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_secs(20);
+
+/// time window for Insights queries when --since isn't given
+const DEFAULT_QUERY_WINDOW: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// apply redaction rules to each event's message in place
+fn redact_events(events: &mut [Event], redactor: &redact::Redactor) {
+    for event in events.iter_mut() {
+        if let Cow::Owned(message) = redactor.redact(&event.message) {
+            event.message = message;
+        }
     }
+}
 
-    info!("fetch entire log - log_group: {log_group}, log_stream: {log_stream}");
-    let mut i = 0;
-    let mut current_token: Option<String> = None;
-    let mut all_events = Vec::new();
+/// how each event is rendered as a line of output text
+#[derive(Debug, Default, Clone, Copy)]
+struct LineFormat<'a> {
+    /// style lines by their log level
+    theme: Option<&'a color::Theme>,
 
-    if let Some(tail_num) = tail {
-        // tail arg... just fetch single page, and from tail (not head)
-        // still apply event number limit, but take from tail arg
-        let limit = tail_num as i32;
-        let event_log: EventLog = fetch_single_log_page(
-            client,
-            &log_group,
-            &log_stream,
-            None,
-            Some(limit),
-            Some(true),
-        )
-        .await
-        .unwrap_or_else(|e| panic!("failed to fetch single log page: {}", e));
-        all_events.extend(event_log.events);
-        info!("fetched single page TAIL, limit was: {limit}");
-    } else {
-        // no tail... just regular full log fetch
-        let mut size_zero_pages_in_a_row = 0;
-        loop {
-            let limit: Option<i32> = None;
-            let event_log: EventLog = fetch_single_log_page(
-                client,
-                &log_group,
-                &log_stream,
-                current_token.as_deref(),
-                limit,
-                None,
-            )
-            .await
-            .unwrap_or_else(|e| panic!("failed to fetch single log page: {}", e));
-            // append all the events to all_events
-            let page_size = event_log.events.len();
-            if page_size == 0 {
-                size_zero_pages_in_a_row += 1;
-            } else {
-                size_zero_pages_in_a_row = 0;
-            }
-            if size_zero_pages_in_a_row >= 3 {
-                debug!("page size is 0 multiple times in a row, break loop");
-                break;
-            }
-            all_events.extend(event_log.events);
-            let forward_token: &str = &event_log.next_forward_token;
-            // check if current token is the same as this new forward token
-            let backward_token = event_log.next_backward_token;
+    /// prefix lines with the event timestamp
+    time_format: Option<&'a timestamps::TimeFormat>,
 
-            debug!("[{i}] forward_token: {forward_token}, backward_token: {backward_token}");
-            let n = i + 1;
-            info!("fetched page {n}, size: {page_size}");
+    /// highlight lines matching these alert rules, needs a theme
+    rules: Option<&'a rules::RuleSet>,
 
-            if let Some(ref ct) = current_token {
-                if ct == &forward_token {
-                    break;
-                }
+    /// output only these fields of json messages
+    extract: Option<&'a extract::Extraction>,
+
+    /// render each event as a record (json object, csv row) instead, ignoring the other settings
+    records: Option<records::Records<'a>>,
+}
+
+/// one rendered line per event, a message spanning several lines stays one entry
+fn render_lines(events: &[Event], line_format: &LineFormat) -> Vec<String> {
+    render_lines_from(events, line_format, events.first().map(|e| e.timestamp))
+}
+
+/// render_lines for events that continue earlier ones, relative times count from first_event_ms
+fn render_lines_from(
+    events: &[Event],
+    line_format: &LineFormat,
+    first_event_ms: Option<i64>,
+) -> Vec<String> {
+    let renderer = line_format
+        .time_format
+        .map(|format| timestamps::TimestampRenderer::new(format, first_event_ms));
+    if let Some(ref records) = line_format.records {
+        return events.iter().map(|e| records.render(e)).collect();
+    }
+    events
+        .iter()
+        .map(|e| {
+            let message = e.message.trim();
+            let text = match line_format.extract {
+                Some(extraction) => extraction.extract(message),
+                None => message.to_string(),
+            };
+            let line = match renderer {
+                Some(ref renderer) => format!("{} {text}", renderer.render(e.timestamp)),
+                None => text,
+            };
+            let line = match (&e.log_group, &e.log_stream) {
+                (Some(log_group), Some(log_stream)) => format!("[{log_group} {log_stream}] {line}"),
+                (None, Some(log_stream)) => format!("[{log_stream}] {line}"),
+                (_, None) => line,
+            };
+            let highlight = line_format
+                .rules
+                .and_then(|rules| rules.highlight_severity(message));
+            match (line_format.theme, highlight) {
+                (Some(theme), Some(severity)) => theme.highlight(&line, severity),
+                (Some(theme), None) => theme.paint(&line, message),
+                (None, _) => line,
             }
-            current_token = Some(forward_token.to_string());
-            i += 1;
-        }
+        })
+        .collect::<Vec<String>>()
+}
+
+/// text with a header line (e.g. csv column names) in front, if there is one
+fn with_header(header: Option<String>, text: String) -> String {
+    match header {
+        Some(header) => format!("{header}\n{text}"),
+        None => text,
     }
-    // sort all the events based on timestamp, just in case they are out of order
-    all_events.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-    all_events
 }
 
-fn get_text_from_events(events: &[Event]) -> String {
-    let text: String = events
-        .iter()
-        .map(|e| e.message.trim())
-        .collect::<Vec<&str>>()
-        .join("\n");
-    text
+fn get_text_from_events(events: &[Event], line_format: &LineFormat) -> String {
+    render_lines(events, line_format).join("\n")
 }
 
-async fn get_sorted_log_stream_names(
-    client: &aws_sdk_cloudwatchlogs::Client,
-    log_group: &str,
-) -> Result<Vec<String>, String> {
-    let mut all_log_streams = vec![];
-    let mut next_token: Option<String> = None;
-    loop {
-        let mut request = client.describe_log_streams();
-        request = request.log_group_name(log_group);
-        if let Some(ref token) = next_token {
-            request = request.next_token(token);
-        }
-        let response = request.send().await.expect("failed to fetch log streams");
-        let log_streams_option = response.log_streams;
-        // TODO could this end up abandoning a partially built result we actually would like to return?
-        if log_streams_option.is_none() {
-            return Err("log_streams_option is None".to_string());
+/// where OutputFile's lines go
+enum OutputTarget {
+    Stdout(std::io::BufWriter<std::io::Stdout>),
+    File(std::io::BufWriter<std::fs::File>),
+    Compressed(std::io::BufWriter<compress::Compressor>),
+    Rotating(rotate::RotatingWriter),
+}
+
+/// --output-file written a line at a time as events come in, separated like
+/// get_text_from_events so the result is the same as writing it all at once
+struct OutputFile {
+    target: OutputTarget,
+    separator: &'static str,
+    /// end every line, the last one too, so a stream read line by line (stdout, a pipe) gets
+    /// each line as soon as it's flushed rather than when the next one comes
+    terminate_lines: bool,
+    /// bytes written so far, including any kept from an earlier run
+    bytes: u64,
+    error_msg: String,
+}
+
+impl OutputFile {
+    /// `-` is stdout, with rotate_size the file rolls over to `fpath.1`, `fpath.2`, ...
+    fn create(
+        fpath: &str,
+        compression: Option<compress::Compression>,
+        rotate_size: Option<u64>,
+    ) -> Result<OutputFile, String> {
+        let path = std::path::Path::new(fpath);
+        let target = if fpath == "-" {
+            OutputTarget::Stdout(std::io::BufWriter::new(std::io::stdout()))
+        } else if let Some(compression) = compression {
+            let compressor = compress::Compressor::create(path, compression)?;
+            OutputTarget::Compressed(std::io::BufWriter::new(compressor))
+        } else if let Some(max_bytes) = rotate_size {
+            OutputTarget::Rotating(rotate::RotatingWriter::create(path, max_bytes)?)
         } else {
-            let log_streams = log_streams_option.unwrap();
-            all_log_streams.extend(log_streams);
+            let file = std::fs::File::create(path)
+                .map_err(|e| format!("unable to create {fpath}: {e}"))?;
+            OutputTarget::File(std::io::BufWriter::new(file))
+        };
+        Ok(OutputFile {
+            target,
+            separator: "",
+            terminate_lines: is_stream_target(fpath),
+            bytes: 0,
+            error_msg: format!("Unable to write file: {fpath}"),
+        })
+    }
+
+    /// carry on writing a plain file where checkpoint left it, dropping whatever was written to
+    /// it after the checkpoint
+    fn resume(fpath: &str, checkpoint: &checkpoint::Checkpoint) -> Result<OutputFile, String> {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(fpath)
+            .map_err(|e| format!("unable to open {fpath} to resume: {e}"))?;
+        file.set_len(checkpoint.bytes)
+            .and_then(|_| file.seek(std::io::SeekFrom::End(0)))
+            .map_err(|e| format!("unable to resume {fpath}: {e}"))?;
+        Ok(OutputFile {
+            target: OutputTarget::File(std::io::BufWriter::new(file)),
+            separator: if checkpoint.events > 0 { "\n" } else { "" },
+            terminate_lines: false,
+            bytes: checkpoint.bytes,
+            error_msg: format!("Unable to write file: {fpath}"),
+        })
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self.target {
+            OutputTarget::Stdout(ref mut w) => w,
+            OutputTarget::File(ref mut w) => w,
+            OutputTarget::Compressed(ref mut w) => w,
+            OutputTarget::Rotating(ref mut w) => w,
         }
-        next_token = response.next_token;
-        if next_token.is_none() {
-            break;
+    }
+
+    /// a records header, on its own line before the first event
+    fn write_header(&mut self, header: &str) {
+        let error_msg = self.error_msg.clone();
+        let writer = self.writer();
+        writer.write_all(header.as_bytes()).expect(&error_msg);
+        writer.write_all(b"\n").expect(&error_msg);
+        self.bytes += header.len() as u64 + 1;
+    }
+
+    fn write_line(&mut self, line: &str) {
+        let (error_msg, separator) = (self.error_msg.clone(), self.separator);
+        let terminator = if self.terminate_lines { "\n" } else { "" };
+        let writer = self.writer();
+        writer.write_all(separator.as_bytes()).expect(&error_msg);
+        writer.write_all(line.as_bytes()).expect(&error_msg);
+        writer.write_all(terminator.as_bytes()).expect(&error_msg);
+        self.bytes += (separator.len() + line.len() + terminator.len()) as u64;
+        if !self.terminate_lines {
+            self.separator = "\n";
         }
     }
-    // sort all_log_streams by creation time
-    all_log_streams.sort_by(|a, b| a.creation_time.cmp(&b.creation_time));
-    let names = all_log_streams
-        .into_iter()
-        .map(|stream| stream.log_stream_name.unwrap())
-        .collect::<Vec<String>>();
-    Ok(names)
-}
 
-async fn get_cloudwatch_client() -> aws_sdk_cloudwatchlogs::Client {
-    let config = aws_config::load_defaults(BehaviorVersion::v2024_03_28()).await;
-    let client = aws_sdk_cloudwatchlogs::Client::new(&config);
-    client
+    fn flush(&mut self) {
+        let error_msg = self.error_msg.clone();
+        self.writer().flush().expect(&error_msg);
+    }
+
+    /// flush everything written, finishing the compressed stream
+    fn finish(mut self) -> Result<(), String> {
+        self.flush();
+        match self.target {
+            OutputTarget::Compressed(writer) => writer
+                .into_inner()
+                .map_err(|e| format!("{}: {}", self.error_msg, e.error()))?
+                .finish(),
+            _ => Ok(()),
+        }
+    }
 }
 
-async fn get_sorted_log_group_names(
+/// fetch log_stream into output page by page, so memory stays flat however long the stream is
+/// and the pages written before a failure are kept. the output is the same as writing all the
+/// events at the end, returns how many were written.
+///
+/// with checkpoint_output, where the download got to is saved next to that file every
+/// checkpoint::SAVE_INTERVAL and when a page fails, and removed once the stream is done.
+/// resumed is the checkpoint output picks up from, pagination starting at its token
+#[allow(clippy::too_many_arguments)]
+async fn write_events_incrementally(
     client: &aws_sdk_cloudwatchlogs::Client,
-) -> Result<Vec<String>, String> {
-    let mut all_group_names: Vec<String> = vec![];
-    let mut next_token: Option<String> = None;
-    let max_iters = 100;
-    let mut i = 0;
-    loop {
-        debug!("fetch log groups, iter: {i}");
-        //let log_groups_output = client.describe_log_groups().send().await.unwrap();
-        let mut bld = client.describe_log_groups();
-        if next_token.is_some() {
-            bld = bld.next_token(next_token.unwrap());
-        }
-        let log_groups_output = bld.send().await.unwrap();
-        next_token = log_groups_output.next_token;
-        // get all log group names sorted by alphabetical
-        let mut log_group_names: Vec<String> = log_groups_output
-            .log_groups
-            .unwrap()
-            .into_iter()
-            .map(|group| group.log_group_name.unwrap())
-            .collect();
-        all_group_names.append(&mut log_group_names);
-        if next_token.is_none() {
-            break;
+    log_group: &str,
+    log_stream: &str,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    pagination: &Pagination,
+    grep: Option<&regex::Regex>,
+    level_filter: Option<&level::LevelFilter>,
+    jq_filter: Option<&jq::JqFilter>,
+    redactor: Option<&redact::Redactor>,
+    line_format: &LineFormat<'_>,
+    mut output: OutputFile,
+    checkpoint_output: Option<&std::path::Path>,
+    resumed: Option<&checkpoint::Checkpoint>,
+) -> Result<usize, AwsLogsError> {
+    if resumed.is_none() {
+        if let Some(header) = line_format.records.and_then(|records| records.header()) {
+            output.write_header(&header);
+        }
+    }
+    let mut written = resumed.map_or(0, |c| c.events);
+    let mut first_event_ms = resumed.and_then(|c| c.first_event_ms);
+    let mut forward_token = resumed.map(|c| c.forward_token.clone());
+    let mut split_events = false;
+    let mut saved_at = std::time::Instant::now();
+    let save_checkpoint = |output: &mut OutputFile,
+                           forward_token: &Option<String>,
+                           written: usize,
+                           first_event_ms: Option<i64>| {
+        let (Some(path), Some(forward_token)) = (checkpoint_output, forward_token) else {
+            return;
+        };
+        // the file has to hold everything before the token first
+        output.flush();
+        let checkpoint = checkpoint::Checkpoint {
+            log_group: log_group.to_string(),
+            log_stream: log_stream.to_string(),
+            forward_token: forward_token.clone(),
+            bytes: output.bytes,
+            events: written,
+            first_event_ms,
+        };
+        if let Err(e) = checkpoint.save(path) {
+            eprintln!("WARNING: {e}");
+        }
+    };
+    let pages = stream_log_pages(
+        client, log_group, log_stream, start_time, end_time, pagination,
+    );
+    let mut pages = std::pin::pin!(pages);
+    let fetched = loop {
+        let page = match pages.try_next().await {
+            Ok(Some(page)) => page,
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e),
+        };
+        let mut events = page.events;
+        events.retain(|e| grep_matches(grep, e));
+        split_events |= stitch::has_split_events(&events);
+        if let Some(level_filter) = level_filter {
+            events.retain(|e| level_filter.matches(&e.message));
+        }
+        if let Some(jq_filter) = jq_filter {
+            jq_filter.filter_events(&mut events);
         }
-        i += 1;
-        if i > max_iters {
-            return Err("max iterations exceeded".to_string());
+        if let Some(redactor) = redactor {
+            redact_events(&mut events, redactor);
         }
+        first_event_ms = first_event_ms.or(events.first().map(|e| e.timestamp));
+        for line in render_lines_from(&events, line_format, first_event_ms) {
+            output.write_line(&line);
+        }
+        written += events.len();
+        forward_token = Some(page.next_forward_token);
+        if saved_at.elapsed() >= checkpoint::SAVE_INTERVAL {
+            save_checkpoint(&mut output, &forward_token, written, first_event_ms);
+            saved_at = std::time::Instant::now();
+        }
+    };
+    // a run cut off by --max-pages can be picked up with --resume as well
+    if fetched.is_err() || pagination.max_pages.is_some() {
+        save_checkpoint(&mut output, &forward_token, written, first_event_ms);
+    } else if let Some(path) = checkpoint_output {
+        checkpoint::Checkpoint::remove(path).unwrap_or_else(|e| eprintln!("WARNING: {e}"));
+    }
+    // whatever was fetched is kept, even when a later page failed
+    output.finish().unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(1);
+    });
+    if split_events {
+        eprintln!("WARNING: some messages look split at the event size limit, use --stitch-split-events to rejoin them");
     }
-    all_group_names.sort();
-    Ok(all_group_names)
+    fetched.map(|_| written)
 }
 
-#[tokio::main]
-async fn main() {
-    env_logger::init();
-    let args = Args::parse();
-    let cwl_client = get_cloudwatch_client().await;
-    let client = &cwl_client;
-
-    if args.describe_log_groups {
-        let log_group_names = get_sorted_log_group_names(client).await.unwrap();
-        println!("Log Groups:");
-        for name in log_group_names {
-            println!("{}", name);
+/// append events to one file per time bucket inside dir, returning the files written to and
+/// whether any of them already had events from an earlier run.
+/// with sidecar_stream, each bucket file also gets a `.meta.jsonl` sidecar
+fn write_bucketed_events(
+    dir: &std::path::Path,
+    bucket_by: bucket::BucketBy,
+    events: &[Event],
+    line_format: &LineFormat,
+    sidecar_stream: Option<&str>,
+) -> Result<(Vec<std::path::PathBuf>, bool), String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("unable to create directory {}: {e}", dir.display()))?;
+    let mut paths = vec![];
+    let mut appended = false;
+    for (name, bucket_events) in bucket_by.split(events) {
+        let path = dir.join(format!("{name}.log"));
+        info!(
+            "writing {} events to: {}",
+            bucket_events.len(),
+            path.display()
+        );
+        let lines = render_lines(bucket_events, line_format);
+        let start_offset = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        appended |= start_offset > 0 && !paths.contains(&path);
+        // a header only goes at the top of a new file
+        let header = line_format
+            .records
+            .filter(|_| start_offset == 0)
+            .and_then(|records| records.header());
+        let text = with_header(header, lines.join("\n")) + "\n";
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .map_err(|e| format!("unable to write file {}: {e}", path.display()))?;
+        if let Some(log_stream) = sidecar_stream {
+            let sidecar_path =
+                sidecar::write_sidecar(&path, log_stream, bucket_events, &lines, start_offset)?;
+            if !paths.contains(&sidecar_path) {
+                paths.push(sidecar_path);
+            }
+        }
+        if !paths.contains(&path) {
+            paths.push(path);
         }
-        return;
     }
-    let log_group = args.log_group.unwrap_or(String::from(""));
-    if args.describe_log_streams {
-        if log_group.is_empty() {
-            println!("--log-group is required when using --describe-log-streams");
+    Ok((paths, appended))
+}
+
+/// compare the number of fetched events to what Insights counts for the same stream and the
+/// requested time range [start_time, end_time) (the whole stream up to now when not given),
+/// warning on stderr if they differ (e.g. pagination stopped early)
+async fn verify_event_count(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    log_stream: &str,
+    tail: Option<u32>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    events: &[Event],
+) {
+    if tail.is_some() {
+        eprintln!(
+            "--verify-count is skipped when using --tail, only part of the stream is fetched"
+        );
+        return;
+    }
+    let start_ms = start_time.unwrap_or(0);
+    let end_ms = end_time.unwrap_or_else(timestamps::now_millis);
+    match insights::count_stream_events(client, log_group, log_stream, start_ms, end_ms).await {
+        Ok(expected) => {
+            let fetched = events.len() as i64;
+            if expected == fetched {
+                info!("verify count ok: {fetched} events");
+            } else {
+                eprintln!(
+                    "WARNING: fetched {fetched} events but Insights counts {expected} events for this stream and time range, output may be incomplete"
+                );
+            }
+        }
+        Err(e) => eprintln!("WARNING: unable to verify event count: {e}"),
+    }
+}
+
+/// profile is `PROFILE[@REGION]`, without one the default credential chain and region are used.
+/// --region overrides the configured region, a region in the profile overrides both. with
+/// --role-arn, the returned config uses the role's temporary credentials. throttled and failed
+/// calls are retried with jittered exponential backoff, up to --max-retries times
+async fn load_aws_config(
+    args: &Args,
+    profile: Option<&str>,
+    recording: Option<&recording::Recording>,
+) -> aws_config::SdkConfig {
+    let mut loader = aws_config::defaults(BehaviorVersion::v2024_03_28()).retry_config(
+        RetryConfig::standard()
+            .with_max_attempts(args.max_retries + 1)
+            .with_max_backoff(MAX_RETRY_BACKOFF),
+    );
+    if let Some(ref region) = args.region {
+        loader = loader.region(aws_config::Region::new(region.clone()));
+    }
+    if let Some(recording) = recording {
+        let http_client = recording.http_client().unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        });
+        loader = loader.http_client(http_client);
+        if let recording::Recording::Replay(_) = recording {
+            // requests are still signed, but nothing checks the signature or needs a real region
+            loader = loader
+                .credentials_provider(aws_sdk_cloudwatchlogs::config::Credentials::new(
+                    "replay", "replay", None, None, "replay",
+                ))
+                .region(aws_config::Region::new("us-east-1"));
+        }
+    }
+    if let Some(profile) = profile {
+        let (name, region) = match profile.split_once('@') {
+            Some((name, region)) => (name, Some(region)),
+            None => (profile, None),
+        };
+        loader = loader.profile_name(name);
+        if let Some(region) = region {
+            loader = loader.region(aws_config::Region::new(region.to_string()));
+        }
+    }
+    let config = loader.load().await;
+    match args.role_arn {
+        // replayed requests aren't checked, so there's nothing to assume
+        Some(_) if matches!(recording, Some(recording::Recording::Replay(_))) => config,
+        Some(ref role_arn) => {
+            let session_name = args.role_session_name.as_deref().unwrap_or("alog");
+            org::assume_role(config, role_arn, args.external_id.as_deref(), session_name).await
+        }
+        None => config,
+    }
+}
+
+async fn get_cloudwatch_client(
+    args: &Args,
+    profile: Option<&str>,
+    recording: Option<&recording::Recording>,
+) -> aws_sdk_cloudwatchlogs::Client {
+    let config = load_aws_config(args, profile, recording).await;
+    aws_sdk_cloudwatchlogs::Client::new(&config)
+}
+
+/// remember a listing so shell completion can answer without calling AWS
+fn cache_listing(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    profile: Option<&str>,
+    listing: &str,
+    log_group: Option<&str>,
+    names: &[String],
+) {
+    let region = client.config().region().map(|r| r.to_string());
+    let key = cache::listing_key(profile, region.as_deref(), listing, log_group);
+    if let Err(e) = cache::store_listing(&key, names) {
+        debug!("unable to cache {listing}: {e}");
+    }
+}
+
+/// print log group or log stream names for shell completion, from the cached listing when
+/// it's fresh enough
+async fn print_completions(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    profile: Option<&str>,
+    kind: completions::CompletionKind,
+    log_group: Option<&str>,
+) {
+    let listing = match kind {
+        completions::CompletionKind::LogGroup => "log-groups",
+        completions::CompletionKind::LogStream => "log-streams",
+    };
+    let log_group = match kind {
+        completions::CompletionKind::LogGroup => None,
+        completions::CompletionKind::LogStream => match log_group {
+            Some(group) => Some(group),
+            None => return,
+        },
+    };
+    let region = client.config().region().map(|r| r.to_string());
+    let key = cache::listing_key(profile, region.as_deref(), listing, log_group);
+    let names = match cache::load_listing(&key) {
+        Some(cached) if cached.is_fresh() => cached.names,
+        _ => {
+            // (names, whether they're all of them)
+            let fetched = match log_group {
+                Some(group) => get_sorted_log_stream_names(client, group, &Default::default())
+                    .await
+                    .map(|names| (names, true)),
+                None => {
+                    groups::sorted_log_groups(client, None, DEFAULT_MAX_GROUP_PAGES, None, None)
+                        .await
+                        .map(|groups| {
+                            let complete = groups.resume_token.is_none();
+                            let names = groups.groups.into_iter().map(|g| g.name).collect();
+                            (names, complete)
+                        })
+                }
+            };
+            let (names, complete) = fetched.unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            });
+            // a partial listing would leave groups out of completion until it expires
+            if complete {
+                cache_listing(client, profile, listing, log_group, &names);
+            }
+            names
+        }
+    };
+    for name in names {
+        println!("{name}");
+    }
+}
+
+/// compare the configuration of --log-group between the two --profile environments,
+/// exiting with 1 when they differ
+async fn diff_group_config(args: &Args, recording: Option<&recording::Recording>) {
+    let Some(ref log_group) = args.log_group else {
+        println!("--log-group is required when using --diff-config");
+        return;
+    };
+    let [left, right] = args.profile.as_slice() else {
+        println!("exactly two --profile values are required when using --diff-config");
+        return;
+    };
+    let (left_client, right_client) = tokio::join!(
+        get_cloudwatch_client(args, Some(left), recording),
+        get_cloudwatch_client(args, Some(right), recording)
+    );
+    let (left_config, right_config) = tokio::join!(
+        group_config::fetch_group_config(&left_client, log_group),
+        group_config::fetch_group_config(&right_client, log_group)
+    );
+    let (left_config, right_config) = match (left_config, right_config) {
+        (Ok(l), Ok(r)) => (l, r),
+        (Err(e), _) | (_, Err(e)) => {
+            println!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        }
+    };
+    let differences = group_config::diff(&left_config, &right_config);
+    if differences.is_empty() {
+        println!("no configuration differences for {log_group} between {left} and {right}");
+        return;
+    }
+    let unset = "(unset)".to_string();
+    let rows = std::iter::once((String::from("setting"), left.clone(), right.clone()))
+        .chain(differences.into_iter().map(|(setting, l, r)| {
+            (
+                setting,
+                l.unwrap_or_else(|| unset.clone()),
+                r.unwrap_or_else(|| unset.clone()),
+            )
+        }))
+        .collect::<Vec<(String, String, String)>>();
+    let setting_width = rows.iter().map(|r| r.0.chars().count()).max().unwrap_or(0);
+    let left_width = rows.iter().map(|r| r.1.chars().count()).max().unwrap_or(0);
+    for (setting, l, r) in &rows {
+        println!("{setting:<setting_width$}  {l:<left_width$}  {r}");
+    }
+    std::process::exit(1);
+}
+
+/// what --org-accounts runs in each account
+#[derive(Debug, Clone)]
+enum OrgOperation {
+    LogGroups(Option<String>),
+    LogStreams(String),
+    Query {
+        log_group: String,
+        query: String,
+        start_time: i64,
+        end_time: i64,
+    },
+    Fetch {
+        log_group: String,
+        log_stream: String,
+        filter_pattern: Option<String>,
+        tail: Option<u32>,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        grep: Option<regex::Regex>,
+    },
+}
+
+/// accounts are fanned out to this many at a time, to stay clear of STS and API throttling
+const ORG_CONCURRENCY: usize = 8;
+
+/// what one account produced for an OrgOperation
+#[derive(Debug)]
+enum OrgOutput {
+    Lines(Vec<String>),
+    /// Insights query results
+    Rows(Vec<insights::Row>),
+    Events(Vec<Event>),
+}
+
+async fn org_account_output(
+    client: aws_sdk_cloudwatchlogs::Client,
+    operation: OrgOperation,
+) -> Result<OrgOutput, AwsLogsError> {
+    match operation {
+        OrgOperation::LogGroups(name_prefix) => get_sorted_log_group_names(
+            &client,
+            name_prefix.as_deref(),
+            DEFAULT_MAX_GROUP_PAGES,
+            None,
+        )
+        .await
+        .map(OrgOutput::Lines),
+        OrgOperation::LogStreams(log_group) => {
+            get_sorted_log_stream_names(&client, &log_group, &Default::default())
+                .await
+                .map(OrgOutput::Lines)
+        }
+        OrgOperation::Query {
+            log_group,
+            query,
+            start_time,
+            end_time,
+        } => insights::run_query(&client, &log_group, &query, start_time, end_time)
+            .await
+            .map(OrgOutput::Rows),
+        OrgOperation::Fetch {
+            log_group,
+            log_stream,
+            filter_pattern,
+            tail,
+            start_time,
+            end_time,
+            grep,
+        } => {
+            let pagination = Pagination {
+                page_limit: None,
+                max_pages: None,
+                resume_token: None,
+            };
+            let events = match filter_pattern {
+                Some(filter_pattern) => {
+                    fetch_filtered_log(
+                        &client,
+                        &log_group,
+                        &log_stream,
+                        &filter_pattern,
+                        tail,
+                        start_time,
+                        end_time,
+                        &pagination,
+                        grep.as_ref(),
+                    )
+                    .await?
+                }
+                None => {
+                    fetch_entire_log(
+                        &client,
+                        &log_group,
+                        &log_stream,
+                        tail,
+                        start_time,
+                        end_time,
+                        &pagination,
+                        grep.as_ref(),
+                    )
+                    .await?
+                }
+            };
+            Ok(OrgOutput::Events(events))
+        }
+    }
+}
+
+/// what is done to the events of a fetch in every account (or region) once they're fetched,
+/// the same as for a fetch in one
+struct FetchPipeline<'a> {
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    grep: Option<&'a regex::Regex>,
+    jq_filter: Option<&'a jq::JqFilter>,
+    redactor: Option<&'a redact::Redactor>,
+    /// how lines are rendered (colors, --extract, --time-format), as in one account
+    line_format: LineFormat<'a>,
+}
+
+/// run a listing, Insights query or fetch in every active account of the organization,
+/// assuming --org-role in each, or in every account of --accounts, and in each of --regions.
+/// accounts (and regions) that fail are reported and skipped
+async fn org_fan_out(
+    args: &Args,
+    profile: Option<&str>,
+    recording: Option<&recording::Recording>,
+    pipeline: &FetchPipeline<'_>,
+) {
+    let flag = match args.fan_out.accounts {
+        Some(_) => "--accounts",
+        None if args.fan_out.org_accounts => "--org-accounts",
+        None if args.fan_out.all_regions => "--all-regions",
+        None => "--regions",
+    };
+    // what a fetch in one account can do, but isn't done across several
+    let given = [
+        ("--output-file", args.output.output_file.is_some()),
+        ("--sink", args.sink.sink.is_some()),
+        ("--follow", args.follow),
+        ("--live", args.live),
+        ("--all-streams", args.streams.all_streams),
+        ("--stream-regex", args.streams.stream_regex.is_some()),
+        (
+            "--log-stream-prefix",
+            args.streams.log_stream_prefix.is_some(),
+        ),
+        ("--cache", args.fetch.cache),
+        ("--verify-count", args.single_stream.verify_count),
+        ("--rules", args.filters.rules.is_some()),
+        ("--dedup", args.filters.dedup),
+        ("--rollup", args.analysis.rollup.is_some()),
+        ("--histogram", args.analysis.histogram.is_some()),
+        ("--gap-threshold", args.analysis.gap_threshold.is_some()),
+        ("--stitch-split-events", args.analysis.stitch_split_events),
+        ("--pager", args.view.pager),
+        ("--copy", args.view.copy),
+        (
+            "--format ndjson or csv",
+            matches!(
+                args.render.format,
+                format::OutputFormat::Ndjson | format::OutputFormat::Csv
+            ),
+        ),
+    ];
+    if let Some((given_flag, _)) = given.iter().find(|(_, set)| *set) {
+        println!("{given_flag} cannot be used with {flag}");
+        return;
+    }
+    let log_group = args.log_group.clone();
+    let insights_query = match (&args.query, &args.stats, &args.count_by) {
+        (Some(query), _, _) => Some(query.clone()),
+        (_, Some(expression), _) => Some(insights::stats_query(expression)),
+        (_, _, Some(spec)) => Some(insights::count_by_query(spec).unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        })),
+        _ => None,
+    };
+    let operation = match (log_group, insights_query) {
+        _ if args.describe_log_groups => OrgOperation::LogGroups(args.log_group_prefix.clone()),
+        (Some(log_group), _) if args.describe_log_streams => OrgOperation::LogStreams(log_group),
+        (Some(log_group), Some(query)) => {
+            let since = args.start.since.unwrap_or(DEFAULT_QUERY_WINDOW);
+            let end_time = timestamps::now_millis() / 1000;
+            OrgOperation::Query {
+                log_group,
+                query,
+                start_time: end_time - since.as_secs() as i64,
+                end_time,
+            }
+        }
+        (Some(log_group), None) if args.streams.log_stream.is_some() => OrgOperation::Fetch {
+            log_group,
+            log_stream: args.streams.log_stream.clone().unwrap(),
+            filter_pattern: args.fetch.filter_pattern.clone(),
+            tail: args.fetch.tail,
+            start_time: pipeline.start_time,
+            end_time: pipeline.end_time,
+            grep: pipeline.grep.cloned(),
+        },
+        _ => {
+            println!("{flag} needs --describe-log-groups, or --log-group with --describe-log-streams, an Insights query or --log-stream");
+            return;
+        }
+    };
+    let config = load_aws_config(args, profile, recording).await;
+    let accounts: Vec<Option<org::Target>> = match args.fan_out.accounts {
+        Some(ref path) => org::load_targets(std::path::Path::new(path))
+            .map(|targets| targets.into_iter().map(Some).collect())
+            .map_err(AwsLogsError::InvalidArgument),
+        // member account roles are in the partition of the account listing them
+        None if args.fan_out.org_accounts => match org::caller_identity(&config).await {
+            Ok(caller) => org::list_accounts(&config).await.map(|accounts| {
+                accounts
+                    .iter()
+                    .map(|account| Some(account.target(&caller.partition, &args.fan_out.org_role)))
+                    .collect()
+            }),
+            Err(e) => Err(e),
+        },
+        // the account of the credentials alog was run with
+        None => Ok(vec![None]),
+    }
+    .unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(e.exit_code());
+    });
+    let target_regions = if args.fan_out.all_regions {
+        regions::enabled_regions(&config)
+            .await
+            .map(|regions| regions.into_iter().map(Some).collect())
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            })
+    } else if args.fan_out.regions.is_empty() {
+        vec![None]
+    } else {
+        args.fan_out.regions.iter().cloned().map(Some).collect()
+    };
+    // (label, account, region) of every place to run in
+    let mut targets: Vec<(String, Option<org::Target>, Option<String>)> = vec![];
+    for account in &accounts {
+        for region in &target_regions {
+            let label = match (account, region) {
+                (Some(account), Some(region)) => format!("{} {region}", account.label),
+                (Some(account), None) => account.label.clone(),
+                (None, Some(region)) => region.clone(),
+                (None, None) => String::new(),
+            };
+            targets.push((label, account.clone(), region.clone()));
+        }
+    }
+    // each account runs in its own task so a panic in one doesn't take down the others
+    let results = futures::stream::iter(targets)
+        .map(|(label, account, region)| {
+            let config = config.clone();
+            let operation = operation.clone();
+            async move {
+                let task = tokio::spawn(async move {
+                    let client = match account {
+                        Some(ref account) => {
+                            org::account_client(&config, account, region.as_deref()).await
+                        }
+                        None => match region {
+                            Some(ref region) => regions::region_client(&config, region),
+                            None => aws_sdk_cloudwatchlogs::Client::new(&config),
+                        },
+                    };
+                    org_account_output(client, operation).await
+                });
+                let output = task.await.unwrap_or_else(|e| {
+                    Err(AwsLogsError::Sdk(format!(
+                        "fetching from {label} failed: {e}"
+                    )))
+                });
+                (label, output)
+            }
+        })
+        .buffered(ORG_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+    let line_format = pipeline.line_format;
+    let label_column = match accounts.as_slice() {
+        [None] => "region",
+        _ => "account",
+    };
+    let mut rows: Vec<insights::Row> = vec![];
+    // (timestamp, line) of every event with --merge-accounts
+    let mut merged_lines: Vec<(i64, String)> = vec![];
+    for (label, output) in results {
+        match output {
+            Ok(OrgOutput::Lines(lines)) => {
+                for line in lines {
+                    println!("[{label}] {line}");
+                }
+            }
+            Ok(OrgOutput::Events(mut events)) => {
+                if let Some(ref level_filter) = args.filters.level {
+                    events.retain(|e| level_filter.matches(&e.message));
+                }
+                if let Some(jq_filter) = pipeline.jq_filter {
+                    jq_filter.filter_events(&mut events);
+                }
+                if let Some(redactor) = pipeline.redactor {
+                    redact_events(&mut events, redactor);
+                }
+                let lines = render_lines(&events, &line_format);
+                let labeled = events
+                    .iter()
+                    .zip(lines)
+                    .map(|(event, line)| (event.timestamp, format!("[{label}] {line}")));
+                if args.fan_out.merge_accounts {
+                    merged_lines.extend(labeled);
+                } else {
+                    for (_, line) in labeled {
+                        println!("{line}");
+                    }
+                }
+            }
+            Ok(OrgOutput::Rows(account_rows)) => {
+                rows.extend(account_rows.into_iter().map(|row| {
+                    std::iter::once((label_column.to_string(), label.clone()))
+                        .chain(row)
+                        .collect()
+                }));
+            }
+            Err(e) => eprintln!("WARNING: skipping {label}: {e}"),
+        }
+    }
+    // a stable sort, so events with the same timestamp stay in account order
+    merged_lines.sort_by_key(|(timestamp, _)| *timestamp);
+    for (_, line) in merged_lines {
+        println!("{line}");
+    }
+    if matches!(operation, OrgOperation::Query { .. }) {
+        match args.render.format {
+            format::OutputFormat::Grafana => println!("{}", grafana::to_json(&rows)),
+            // json is rejected for queries up front
+            format::OutputFormat::Text
+            | format::OutputFormat::Json
+            | format::OutputFormat::Ndjson
+            | format::OutputFormat::Csv => {
+                println!("{}", insights::format_table(&rows))
+            }
+        }
+    }
+}
+
+/// --output-file targets that are a stream rather than a regular file: `-` (stdout) and
+/// devices like /dev/fd/3 or /dev/stderr. they can't be re-read, appended to per bucket or
+/// have sidecar files written next to them
+fn is_stream_target(path: &str) -> bool {
+    path == "-" || path.starts_with("/dev/")
+}
+
+/// follow log_streams at once, each in a pane of its own, from the start of the pipeline's
+/// window (now when it has none). when stdin or stdout isn't a terminal, the lines are printed
+/// as they come instead, labeled with their stream. events are filtered and checked against
+/// the alert rules like those of a followed stream
+async fn tail_in_panes(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    log_streams: &[String],
+    args: &Args,
+    pipeline: &FetchPipeline<'_>,
+    rule_set: Option<&rules::RuleSet>,
+) {
+    let followers = log_streams.iter().enumerate().map(|(pane, log_stream)| {
+        let follower = follow::Follower::new(
+            client,
+            log_group,
+            log_stream,
+            pipeline.start_time,
+            args.follow_interval,
+        );
+        // each batch comes with the pane it goes to
+        Box::pin(futures::stream::unfold(
+            follower,
+            move |mut follower| async move {
+                let batch = follower.next_batch().await;
+                Some(((pane, batch), follower))
+            },
+        ))
+    });
+    let mut batches = futures::stream::select_all(followers);
+    let mut panes =
+        (std::io::stdin().is_terminal() && std::io::stdout().is_terminal()).then(|| {
+            panes::Panes::open(log_streams).unwrap_or_else(|e| {
+                println!("Error: unable to show panes: {}", e);
+                std::process::exit(1);
+            })
+        });
+    // events already in the streams when tailing started don't ring, call webhooks or exit
+    let rules_start = timestamps::now_millis();
+    let mut exit_status = None;
+    let ended = loop {
+        let next = match panes {
+            Some(ref mut panes) => {
+                if let Err(e) = panes.draw() {
+                    break Err(AwsLogsError::Io(format!("unable to show panes: {e}")));
+                }
+                tokio::select! {
+                    action = panes.next_key() => match action {
+                        panes::KeyAction::Quit => break Ok(exit_status),
+                        panes::KeyAction::Redraw => continue,
+                    },
+                    // once a rule exits, the panes stay as they are until a key is pressed
+                    next = batches.next(), if exit_status.is_none() => next,
+                }
+            }
+            None => batches.next().await,
+        };
+        let Some((pane, batch)) = next else {
+            break Ok(exit_status);
+        };
+        let mut events = match batch {
+            Ok(Some(events)) => events,
+            // Ctrl-C
+            Ok(None) => break Ok(exit_status),
+            Err(e) => break Err(e),
+        };
+        let log_stream = &log_streams[pane];
+        if panes.is_none() {
+            for event in events.iter_mut() {
+                event.log_stream = Some(log_stream.clone());
+            }
+        }
+        if let Some(ref level_filter) = args.filters.level {
+            events.retain(|e| level_filter.matches(&e.message));
+        }
+        if let Some(jq_filter) = pipeline.jq_filter {
+            jq_filter.filter_events(&mut events);
+        }
+        if let Some(redactor) = pipeline.redactor {
+            redact_events(&mut events, redactor);
+        }
+        if args.filters.dedup {
+            events = dedup::dedup_events(events);
+        }
+        if let Some(rule_set) = rule_set {
+            for i in 0..events.len() {
+                if events[i].ingestion_time < rules_start {
+                    continue;
+                }
+                let triggered = rule_set.apply(&events[i], log_group, log_stream).await;
+                if triggered.bell {
+                    match panes {
+                        Some(ref panes) => {
+                            let _ = panes.bell();
+                        }
+                        None => eprint!("\x07"),
+                    }
+                }
+                if let Some(status) = triggered.exit_status {
+                    events.truncate(i + 1);
+                    exit_status = Some(status);
+                    break;
+                }
+            }
+        }
+        let lines = render_lines(&events, &pipeline.line_format);
+        match panes {
+            Some(ref mut panes) => {
+                panes.push(pane, lines);
+                if let Some(status) = exit_status {
+                    panes.finish(format!(
+                        "an alert rule matched in {log_stream}, press any key to exit with status {status}"
+                    ));
+                }
+            }
+            None => {
+                for line in lines {
+                    println!("{line}");
+                }
+                if exit_status.is_some() {
+                    break Ok(exit_status);
+                }
+            }
+        }
+    };
+    if let Some(panes) = panes {
+        if let Err(e) = panes.close() {
+            eprintln!("WARNING: unable to restore the terminal: {e}");
+        }
+    }
+    match ended {
+        Ok(Some(status)) => std::process::exit(status),
+        Ok(None) => {}
+        Err(e) => {
+            println!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        }
+    }
+}
+
+/// print the events of an --input-file the way a fetch would, without calling AWS
+fn read_input_file(path: &str, args: &Args, pipeline: &FetchPipeline<'_>) {
+    let path = std::path::Path::new(path);
+    let mut events = compress::open(path)
+        .and_then(|reader| records::read_ndjson(std::io::BufReader::new(reader)))
+        .map_err(|e| format!("invalid --input-file {}: {e}", path.display()))
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        });
+    // like fetched events, they're only labeled with their group when there are several
+    let group_count = events
+        .iter()
+        .filter_map(|e| e.log_group.as_deref())
+        .collect::<HashSet<&str>>()
+        .len();
+    if group_count <= 1 {
+        for event in events.iter_mut() {
+            event.log_group = None;
+        }
+    }
+    print_read_events(events, args, pipeline);
+}
+
+/// print the events of --log-group the event store holds the way a fetch would, or with
+/// `alog stats`, what it holds of the group, without calling AWS. a stream stored from several
+/// accounts, regions, profiles or imports is read from the one holding the most of it
+fn read_offline(args: &Args, pipeline: &FetchPipeline<'_>) {
+    let Some(ref log_group) = args.log_group else {
+        println!("a log group is required, e.g. alog get /ecs/app ecs/app/1234 --offline");
+        return;
+    };
+    let event_store = event_store_path(args)
+        .filter(|path| path.exists())
+        .ok_or_else(|| {
+            "nothing stored yet, fetch with --cache or load an export with `alog import` first"
+                .to_string()
+        })
+        .and_then(|path| store::EventStore::open(&path))
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        });
+    let mut stored = event_store.streams(log_group).unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(1);
+    });
+    // sorted by name, the source holding the most of a stream first
+    stored.dedup_by(|other, kept| {
+        let duplicate = other.log_stream == kept.log_stream;
+        if duplicate {
+            eprintln!(
+                "WARNING: {} is stored from several accounts, regions, profiles or imports, only {} of its events are read",
+                kept.log_stream, kept.events
+            );
+        }
+        duplicate
+    });
+    if args.group_stats {
+        let stats = stats::stored_stats(log_group, &stored);
+        if args.render.format == format::OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&stats).unwrap());
+        } else {
+            println!("{}", stats.report());
+        }
+        return;
+    }
+    let multi_stream_filter = multi_stream_filter(args, pipeline.start_time);
+    let streams = match (&multi_stream_filter, &args.streams.log_stream) {
+        (Some(filter), _) => stored
+            .iter()
+            .filter(|stream| filter.matches(&stream.info()))
+            .collect::<Vec<&store::StoredStream>>(),
+        (None, Some(log_stream)) => stored
+            .iter()
+            .filter(|stream| stream.log_stream == *log_stream)
+            .collect(),
+        (None, None) => {
+            println!("a log stream is required, e.g. alog get /ecs/app ecs/app/1234 --offline, or --all-streams");
             return;
         }
-        let log_stream_names = get_sorted_log_stream_names(client, &log_group)
+    };
+    if streams.is_empty() {
+        println!("no stored streams of {log_group} match, see alog stats {log_group} --offline");
+        return;
+    }
+    let stream_events_list = streams
+        .iter()
+        .map(|stream| {
+            let mut events = event_store
+                .events(
+                    &stream.source,
+                    log_group,
+                    &stream.log_stream,
+                    pipeline.start_time,
+                    pipeline.end_time,
+                )
+                .unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(1);
+                });
+            if multi_stream_filter.is_some() {
+                for event in events.iter_mut() {
+                    event.log_stream = Some(stream.log_stream.clone());
+                }
+            }
+            events
+        })
+        .collect::<Vec<Vec<Event>>>();
+    print_read_events(
+        merge::merge_by_timestamp(stream_events_list),
+        args,
+        pipeline,
+    );
+}
+
+/// the streams --log-stream-prefix, --stream-regex or --all-streams read, None for a single
+/// stream
+fn multi_stream_filter(args: &Args, start_time: Option<i64>) -> Option<streams::StreamFilter> {
+    match args.streams.log_stream_prefix {
+        Some(ref prefix) => Some(streams::StreamFilter {
+            name_prefix: Some(prefix.clone()),
+            ..Default::default()
+        }),
+        // streams last written to before the window can be skipped, allowing for the lag in
+        // CloudWatch updating a stream's last event time
+        None if args.streams.all_streams => Some(streams::StreamFilter {
+            last_event_after: start_time.map(|t| t - LAST_EVENT_TIME_LAG_MS),
+            ..Default::default()
+        }),
+        None => args
+            .streams
+            .stream_regex
+            .as_ref()
+            .map(|pattern| streams::StreamFilter {
+                name_pattern: Some(regex::Regex::new(pattern).unwrap_or_else(|e| {
+                    println!("Error: invalid --stream-regex: {}", e);
+                    std::process::exit(1);
+                })),
+                last_event_after: start_time.map(|t| t - LAST_EVENT_TIME_LAG_MS),
+                ..Default::default()
+            }),
+    }
+}
+
+/// print events read without calling AWS the way a fetch would print them
+fn print_read_events(mut events: Vec<Event>, args: &Args, pipeline: &FetchPipeline<'_>) {
+    // the same window a fetch asks CloudWatch for, the end excluded
+    events.retain(|e| {
+        pipeline.start_time.is_none_or(|start| e.timestamp >= start)
+            && pipeline.end_time.is_none_or(|end| e.timestamp < end)
+            && grep_matches(pipeline.grep, e)
+    });
+    if let Some(tail) = args.fetch.tail {
+        let skip = events.len().saturating_sub(tail as usize);
+        events.drain(..skip);
+    }
+    if let Some(ref level_filter) = args.filters.level {
+        events.retain(|e| level_filter.matches(&e.message));
+    }
+    if let Some(jq_filter) = pipeline.jq_filter {
+        jq_filter.filter_events(&mut events);
+    }
+    if let Some(redactor) = pipeline.redactor {
+        redact_events(&mut events, redactor);
+    }
+    for line in render_lines(&events, &pipeline.line_format) {
+        println!("{line}");
+    }
+}
+
+/// where the event store of --cache, `alog import`, `alog search` and --offline is: in
+/// --archive-dir, or the cache dir
+fn event_store_path(args: &Args) -> Option<std::path::PathBuf> {
+    match args.store.archive_dir {
+        Some(ref dir) => Some(dir.join("events.sqlite")),
+        None => config::event_store_path(),
+    }
+}
+
+/// load the export task at from into the event store as the events of --log-group, only
+/// calling AWS to download it from S3
+async fn import_export(
+    from: &export::ExportLocation,
+    args: &Args,
+    profile: Option<&str>,
+    recording: Option<&recording::Recording>,
+) {
+    let Some(ref log_group) = args.log_group else {
+        println!("a log group is required, e.g. alog import -g /ecs/app s3://bucket/exportedlogs/<task id>");
+        return;
+    };
+    let event_store = event_store_path(args)
+        .ok_or_else(|| "unable to find a cache directory, set HOME or XDG_CACHE_HOME".to_string())
+        .and_then(|path| store::EventStore::open(&path))
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        });
+    let mut import = store::Import::new(&event_store, log_group);
+    let write = |events: Vec<Event>| import.add(&events);
+    let imported = match from {
+        export::ExportLocation::S3(task) => {
+            let aws_config = load_aws_config(args, profile, recording).await;
+            let s3_client = aws_sdk_s3::Client::new(&aws_config);
+            export::download_export_task(&s3_client, task, write, |number, total, key| {
+                eprintln!("[{number}/{total}] downloading {key}")
+            })
+            .await
+        }
+        export::ExportLocation::Dir(dir) => {
+            export::read_export_dir(dir, write, |number, total, path| {
+                eprintln!("[{number}/{total}] reading {path}")
+            })
+        }
+    };
+    let imported = imported.unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(e.exit_code());
+    });
+    eprintln!("imported {imported} events of {log_group}");
+}
+
+/// print the events in the cache matching query, oldest first, without calling AWS. the cache
+/// keeps messages as fetched, so they're redacted here with --redact-rules or, without it, the
+/// redact rules the config file has for each hit's log group
+fn search_cache(
+    query: &str,
+    args: &Args,
+    config: &config::Config,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+) {
+    let hits = event_store_path(args)
+        .filter(|path| path.exists())
+        .ok_or_else(|| "no events cached yet, fetch with --cache first".to_string())
+        .and_then(|path| store::EventStore::open(&path))
+        .and_then(|event_store| {
+            event_store.search(
+                query,
+                args.log_group.as_deref(),
+                args.streams.log_stream.as_deref(),
+                start_time,
+                end_time,
+                args.fetch.tail,
+            )
+        })
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        });
+    let load_redactor = |path: &str| {
+        redact::Redactor::from_file(std::path::Path::new(path)).unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        })
+    };
+    let redactor = args.filters.redact_rules.as_deref().map(load_redactor);
+    // the redact rules of each log group in the hits, loaded once
+    let mut group_redactors: HashMap<&str, Option<redact::Redactor>> = HashMap::new();
+    for hit in &hits {
+        let redactor = match redactor {
+            Some(ref redactor) => Some(redactor),
+            None => group_redactors
+                .entry(hit.log_group.as_str())
+                .or_insert_with(|| {
+                    config
+                        .groups
+                        .get(&hit.log_group)
+                        .and_then(|defaults| defaults.redact_rules.as_deref())
+                        .map(load_redactor)
+                })
+                .as_ref(),
+        };
+        let message = match redactor {
+            Some(redactor) => redactor.redact(&hit.event.message),
+            None => Cow::Borrowed(hit.event.message.as_str()),
+        };
+        println!(
+            "{} [{} {}] {}",
+            timestamps::format_display(hit.event.timestamp),
+            hit.log_group,
+            hit.log_stream,
+            message.trim()
+        );
+    }
+    eprintln!("{} matching events", hits.len());
+}
+
+/// print the recorded invocations, most recent last
+fn print_history(program: &str) {
+    let entries = history::load_history().unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(1);
+    });
+    for (i, entry) in entries.iter().enumerate() {
+        let command = history::format_command(program, &entry.args);
+        println!("{:>5}  {}  {}", i + 1, entry.time, command);
+    }
+}
+
+fn print_bookmarks() {
+    let all = bookmarks::load_bookmarks().unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(1);
+    });
+    for (name, bookmark) in all {
+        println!(
+            "{name}\t{}\t{}\t{}",
+            timestamps::format_display(bookmark.timestamp),
+            bookmark.log_group,
+            bookmark.log_stream
+        );
+    }
+}
+
+fn add_bookmark(name: &str, args: &Args) {
+    let (Some(log_group), Some(log_stream)) = (&args.log_group, &args.streams.log_stream) else {
+        println!("--log-group and --log-stream are required when using --bookmark-add");
+        return;
+    };
+    let timestamp = match args.bookmark_time {
+        Some(ref time) => timestamps::parse_time(time).unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        }),
+        None => timestamps::now_millis(),
+    };
+    let bookmark = bookmarks::Bookmark {
+        log_group: log_group.clone(),
+        log_stream: log_stream.clone(),
+        timestamp,
+    };
+    bookmarks::save_bookmark(name, bookmark).unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(1);
+    });
+    println!(
+        "saved bookmark '{name}' at {}",
+        timestamps::format_display(timestamp)
+    );
+}
+
+/// flags that don't apply everywhere go after the subcommand, which only takes the ones that
+/// apply to it. given before it, they'd be parsed without a word and then dropped
+fn check_flag_order(matches: &clap::ArgMatches) -> Result<(), String> {
+    let Some((command, _)) = matches.subcommand() else {
+        return Ok(());
+    };
+    let cmd = <Args as clap::CommandFactory>::command();
+    let misplaced = cmd.get_arguments().find(|arg| {
+        !arg.is_global_set()
+            && matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+    });
+    match misplaced.and_then(|arg| arg.get_long()) {
+        Some(flag) => Err(format!("--{flag} goes after `alog {command}`")),
+        None => Ok(()),
+    }
+}
+
+/// whether the flag with this id was typed, for the subcommand or without one. flags with a
+/// default always have a value, so check where it came from
+fn given(matches: &clap::ArgMatches, id: &str) -> bool {
+    std::iter::once(matches)
+        .chain(matches.subcommand().map(|(_, sub)| sub))
+        .any(|m| m.ids().any(|i| i == id) && m.value_source(id) == Some(ValueSource::CommandLine))
+}
+
+/// fill in the settings of the preset `alog run` names that weren't given as flags
+fn apply_preset(
+    args: &mut Args,
+    matches: &clap::ArgMatches,
+    config: &config::Config,
+) -> Result<(), String> {
+    let Some(ref name) = args.preset else {
+        return Ok(());
+    };
+    let preset = config
+        .presets
+        .get(name)
+        .ok_or_else(|| format!("no preset named '{name}' in the config file"))?
+        .clone();
+    debug!("applying preset {name}: {preset:?}");
+    let invalid = |e: String| format!("invalid preset '{name}': {e}");
+    if args.log_group.is_none() {
+        args.log_group = preset.log_group;
+    }
+    // streams given as a flag replace the preset's way of picking them
+    if args.streams.log_stream.is_none()
+        && args.streams.stream_regex.is_none()
+        && args.streams.log_stream_prefix.is_none()
+        && !args.streams.all_streams
+    {
+        args.streams.log_stream = preset.log_stream;
+        args.streams.stream_regex = preset.stream_regex;
+    }
+    if args.fetch.filter_pattern.is_none() {
+        args.fetch.filter_pattern = preset.filter_pattern;
+    }
+    if args.fetch.grep.is_none() {
+        args.fetch.grep = preset.grep;
+    }
+    if args.filters.level.is_none() {
+        args.filters.level = preset
+            .level
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(invalid)?;
+    }
+    if args.start.since.is_none() && args.start.start_time.is_none() {
+        args.start.since = preset
+            .since
+            .as_deref()
+            .map(|since| humantime::parse_duration(since).map_err(|e| e.to_string()))
+            .transpose()
+            .map_err(invalid)?;
+    }
+    if args.query.is_none() && args.stats.is_none() && args.count_by.is_none() {
+        args.query = preset.query;
+    }
+    if let Some(format) = preset.format.filter(|_| !given(matches, "format")) {
+        args.render.format = format.parse().map_err(invalid)?;
+    }
+    Ok(())
+}
+
+/// fill in the region, profile, log group, format and preview settings of the config file's
+/// context that weren't given as flags
+fn apply_context_defaults(
+    args: &mut Args,
+    matches: &clap::ArgMatches,
+    config: &config::Config,
+) -> Result<(), String> {
+    let defaults = config.context_defaults(args.context.as_deref())?;
+    debug!("applying config context defaults: {defaults:?}");
+    if args.region.is_none() {
+        args.region = defaults.region;
+    }
+    if args.profile.is_empty() {
+        args.profile.extend(defaults.profile);
+    }
+    if args.log_group.is_none() {
+        args.log_group = defaults.log_group;
+    }
+    if let Some(format) = defaults.format.filter(|_| !given(matches, "format")) {
+        args.render.format = format
+            .parse()
+            .map_err(|e| format!("invalid config for this context: {e}"))?;
+    }
+    if let Some(n) = defaults
+        .preview_lines
+        .filter(|_| !given(matches, "preview_lines"))
+    {
+        args.stream_listing.preview_lines = n;
+    }
+    if let Some(n) = defaults
+        .preview_streams
+        .filter(|_| !given(matches, "preview_streams"))
+    {
+        args.stream_listing.preview_streams = n;
+    }
+    Ok(())
+}
+
+/// fill in the settings the config file has for --log-group that weren't given as flags
+fn apply_group_defaults(
+    args: &mut Args,
+    matches: &clap::ArgMatches,
+    config: &config::Config,
+) -> Result<(), String> {
+    let Some(defaults) = args.log_group.as_ref().and_then(|g| config.groups.get(g)) else {
+        return Ok(());
+    };
+    debug!("applying config defaults for log group: {defaults:?}");
+    let invalid = |e: String| format!("invalid config for this log group: {e}");
+    // a typed --format or the preset's wins, the group's replaces the context's
+    let preset_format = args
+        .preset
+        .as_ref()
+        .and_then(|name| config.presets.get(name))
+        .and_then(|preset| preset.format.as_ref());
+    if let Some(format) = defaults
+        .format
+        .as_ref()
+        .filter(|_| !given(matches, "format") && preset_format.is_none())
+    {
+        args.render.format = format.parse().map_err(invalid)?;
+    }
+    if args.fetch.filter_pattern.is_none() {
+        args.fetch.filter_pattern = defaults.filter_pattern.clone();
+    }
+    if args.fetch.grep.is_none() {
+        args.fetch.grep = defaults.grep.clone();
+    }
+    if args.filters.jq.is_none() {
+        args.filters.jq = defaults.jq.clone();
+    }
+    if args.render.extract.is_none() {
+        args.render.extract = defaults
+            .extract
+            .as_deref()
+            .map(str::parse::<extract::Fields>)
+            .transpose()
+            .map_err(invalid)?;
+    }
+    if args.render.time_format.is_none() {
+        args.render.time_format = defaults
+            .time_format
+            .as_deref()
+            .map(str::parse::<timestamps::TimeFormat>)
+            .transpose()
+            .map_err(invalid)?;
+    }
+    if args.timezone.is_none() {
+        args.timezone = defaults
+            .timezone
+            .as_deref()
+            .map(timestamps::parse_time_zone)
+            .transpose()
+            .map_err(invalid)?;
+    }
+    if args.filters.level.is_none() {
+        args.filters.level = defaults
+            .level
+            .as_deref()
+            .map(str::parse::<level::LevelFilter>)
+            .transpose()
+            .map_err(invalid)?;
+    }
+    if args.filters.redact_rules.is_none() {
+        args.filters.redact_rules = defaults.redact_rules.clone();
+    }
+    if args.filters.rules.is_none() {
+        args.filters.rules = defaults.rules.clone();
+    }
+    args.analysis.stitch_split_events |= defaults.stitch_split_events.unwrap_or(false);
+    Ok(())
+}
+
+/// RUST_LOG as usual when set. without it, only the warnings of the library are shown, the way
+/// alog prints its own
+fn init_logger() {
+    if std::env::var_os("RUST_LOG").is_some() {
+        env_logger::init();
+        return;
+    }
+    env_logger::Builder::new()
+        .filter_module("rust_aws_logs", log::LevelFilter::Warn)
+        .format(|buf, record| match record.level() {
+            log::Level::Error => writeln!(buf, "ERROR: {}", record.args()),
+            _ => writeln!(buf, "WARNING: {}", record.args()),
+        })
+        .init();
+}
+
+#[tokio::main]
+async fn main() {
+    init_logger();
+    let raw_args = std::env::args().collect::<Vec<String>>();
+    let program = raw_args
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "alog".to_string());
+    let mut matches = <Args as clap::CommandFactory>::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    check_flag_order(&matches)
+        .and_then(|()| args.apply_command())
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(2);
+        });
+    if let Some(zone) = args.timezone.clone() {
+        timestamps::set_display_zone(zone);
+    }
+    if let Some(rps) = args.fetch.max_rps {
+        if rps.is_nan() || rps <= 0.0 {
+            println!("--max-rps must be greater than 0");
+            std::process::exit(2);
+        }
+        ratelimit::limit_get_log_events(rps);
+    }
+    // only on a terminal, where it doesn't end up in redirected or piped output
+    if std::io::stdout().is_terminal() {
+        progress::enable();
+    }
+    if let Some(shell) = args.completions {
+        let mut cmd = <Args as clap::CommandFactory>::command();
+        print!("{}", completions::script(shell, "alog", &mut cmd));
+        return;
+    }
+    if let Some(kind) = args.complete {
+        let profile = args.profile.first().map(|p| p.as_str());
+        let client = get_cloudwatch_client(&args, profile, None).await;
+        print_completions(&client, profile, kind, args.log_group.as_deref()).await;
+        return;
+    }
+    if args.history {
+        print_history(&program);
+        return;
+    }
+    let mut invocation = raw_args.iter().skip(1).cloned().collect::<Vec<String>>();
+    if let Some(n) = args.rerun {
+        let entries = history::load_history().unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        });
+        let entry = n
+            .checked_sub(1)
+            .and_then(|i| entries.get(i))
+            .unwrap_or_else(|| {
+                println!("Error: no command {n} in history, see --history");
+                std::process::exit(1);
+            });
+        invocation = entry
+            .args
+            .iter()
+            .cloned()
+            .chain(history::strip_flag(&invocation, "--rerun"))
+            .collect();
+        eprintln!(
+            "re-running: {}",
+            history::format_command(&program, &invocation)
+        );
+        matches = <Args as clap::CommandFactory>::command()
+            .get_matches_from(std::iter::once(&program).chain(&invocation));
+        args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        check_flag_order(&matches)
+            .and_then(|()| args.apply_command())
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(2);
+            });
+    }
+    if let Err(e) = history::record(&invocation) {
+        debug!("unable to record command history: {e}");
+    }
+    if args.bookmarks {
+        print_bookmarks();
+        return;
+    }
+    if let Some(ref name) = args.bookmark_add {
+        add_bookmark(name, &args);
+        return;
+    }
+    let parse_time_arg = |time: &Option<String>| {
+        time.as_deref().map(|t| {
+            timestamps::parse_time(t).unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            })
+        })
+    };
+    let ago =
+        |duration: std::time::Duration| timestamps::now_millis() - duration.as_millis() as i64;
+    let mut start_time = parse_time_arg(&args.start.start_time).or(args.start.since.map(ago));
+    let end_time = parse_time_arg(&args.end.end_time).or(args.end.until.map(ago));
+    if let (Some(start), Some(end)) = (start_time, end_time) {
+        if start >= end {
+            println!("the start of the time window (--start-time, --since) must be before its end (--end-time, --until)");
+            return;
+        }
+    }
+    if let Some(ref name) = args.goto {
+        let bookmark = bookmarks::load_bookmarks()
+            .and_then(|mut all| {
+                all.remove(name)
+                    .ok_or_else(|| format!("no bookmark named '{name}', see --bookmarks"))
+            })
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            });
+        args.log_group = Some(bookmark.log_group);
+        args.streams.log_stream = Some(bookmark.log_stream);
+        start_time = Some(bookmark.timestamp);
+    }
+    let config = config::load_config().unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(1);
+    });
+    if let Some(ref query) = args.search {
+        search_cache(query, &args, &config, start_time, end_time);
+        return;
+    }
+    apply_preset(&mut args, &matches, &config).unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(1);
+    });
+    apply_context_defaults(&mut args, &matches, &config).unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(1);
+    });
+    apply_group_defaults(&mut args, &matches, &config).unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(1);
+    });
+    // a --timezone flag was already applied above and takes precedence
+    if let Some(zone) = args.timezone.clone() {
+        timestamps::set_display_zone(zone);
+    }
+    let theme = color::Theme::from_config(&config.theme).unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(1);
+    });
+    let rule_set = args.filters.rules.as_ref().map(|path| {
+        rules::RuleSet::from_file(std::path::Path::new(path)).unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        })
+    });
+    let extraction = args
+        .render
+        .extract
+        .clone()
+        .map(|fields| extract::Extraction {
+            fields,
+            separator: args.render.extract_separator,
+        });
+    let file_format = LineFormat {
+        theme: None,
+        time_format: args.render.time_format.as_ref(),
+        rules: rule_set.as_ref(),
+        extract: extraction.as_ref(),
+        records: None,
+    };
+    let stdout_format = LineFormat {
+        theme: args.color.enabled().then_some(&theme),
+        ..file_format
+    };
+    let is_query = args.query.is_some() || args.stats.is_some() || args.count_by.is_some();
+    if args.render.format == format::OutputFormat::Grafana && !is_query {
+        println!("--format grafana is only supported for Insights query results");
+        return;
+    }
+    if args.render.format == format::OutputFormat::Json && !args.describe_log_streams {
+        println!("--format json is only supported with --describe-log-streams");
+        return;
+    }
+    let is_record_format = matches!(
+        args.render.format,
+        format::OutputFormat::Ndjson | format::OutputFormat::Csv
+    );
+    if is_record_format && (is_query || args.describe_log_groups || args.describe_log_streams) {
+        println!("--format ndjson and csv are only supported when fetching events");
+        return;
+    }
+    if is_record_format && args.render.extract.is_some() {
+        println!("--extract cannot be used with --format ndjson or csv");
+        return;
+    }
+    if args.render.format == format::OutputFormat::Csv && args.output.meta_sidecar {
+        println!("--meta-sidecar cannot be used with --format csv");
+        return;
+    }
+    if args.fetch.max_pages == Some(0) {
+        println!("--max-pages must be greater than 0");
+        return;
+    }
+    let recording = match (&args.record, &args.replay) {
+        (Some(dir), _) => Some(recording::Recording::Record(dir.into())),
+        (None, Some(dir)) => Some(recording::Recording::Replay(dir.into())),
+        (None, None) => None,
+    };
+    if args.diff_config {
+        diff_group_config(&args, recording.as_ref()).await;
+        return;
+    }
+    if args.profile.len() > 1 {
+        println!("only one --profile can be given unless using --diff-config");
+        return;
+    }
+    let profile = args.profile.first().map(|p| p.as_str());
+    let redactor = args.filters.redact_rules.as_ref().map(|path| {
+        redact::Redactor::from_file(std::path::Path::new(path)).unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        })
+    });
+    let jq_filter = args.filters.jq.as_deref().map(|expr| {
+        jq::JqFilter::compile(expr).unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        })
+    });
+    let grep = args.fetch.grep.as_ref().map(|pattern| {
+        regex::Regex::new(pattern).unwrap_or_else(|e| {
+            println!("Error: invalid --grep: {}", e);
+            std::process::exit(1);
+        })
+    });
+    let pipeline = FetchPipeline {
+        start_time,
+        end_time,
+        grep: grep.as_ref(),
+        jq_filter: jq_filter.as_ref(),
+        redactor: redactor.as_ref(),
+        line_format: stdout_format,
+    };
+    if let Some(ref path) = args.input_file {
+        read_input_file(path, &args, &pipeline);
+        return;
+    }
+    if args.store.offline {
+        read_offline(&args, &pipeline);
+        return;
+    }
+    if let Some(ref from) = args.import_from {
+        import_export(from, &args, profile, recording.as_ref()).await;
+        return;
+    }
+    if args.fan_out.org_accounts
+        || args.fan_out.accounts.is_some()
+        || args.fan_out.all_regions
+        || !args.fan_out.regions.is_empty()
+    {
+        org_fan_out(&args, profile, recording.as_ref(), &pipeline).await;
+        return;
+    }
+    let mut aws_config = load_aws_config(&args, profile, recording.as_ref()).await;
+    if recording.is_none() {
+        let renewed = sso::check_session(&aws_config, profile)
+            .await
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            });
+        if renewed {
+            aws_config = load_aws_config(&args, profile, None).await;
+        }
+    }
+    let cwl_client = aws_sdk_cloudwatchlogs::Client::new(&aws_config);
+    let client = &cwl_client;
+
+    if args.retention_report {
+        let recommendations = retention::recommend_retention(client, args.log_group.as_deref())
+            .await
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            });
+        let total: f64 = recommendations.iter().map(|r| r.monthly_savings_usd).sum();
+        println!(
+            "{}",
+            insights::format_table(&retention::to_rows(&recommendations))
+        );
+        println!("projected savings: ${total:.2}/month");
+        return;
+    }
+    if args.subscribe_wizard {
+        let profile = args.profile.first().map(|p| p.as_str());
+        subscribe::run_wizard(client, args.log_group.clone(), profile)
+            .await
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            });
+        return;
+    }
+    if args.listing.limit == Some(0) {
+        println!("--limit must be greater than 0");
+        return;
+    }
+    if args.describe_log_groups {
+        let max_pages = args.fetch.max_pages.unwrap_or(DEFAULT_MAX_GROUP_PAGES);
+        let glob = args.log_group.as_deref().filter(|g| glob::is_glob(g));
+        let name_prefix = args
+            .log_group_prefix
+            .as_deref()
+            .or(glob.and_then(glob::literal_prefix));
+        if args.listing.order_by == Some(streams::ListOrder::LastEvent) {
+            println!("--order-by last-event is only for streams, CloudWatch doesn't report when a log group last logged");
+            return;
+        }
+        // the first groups CloudWatch lists are only the first of the listing unfiltered and
+        // in name order
+        let in_name_order = !args.listing.desc
+            && args
+                .listing
+                .order_by
+                .is_none_or(|order| order == streams::ListOrder::Name);
+        let fetch_limit = args
+            .listing
+            .limit
+            .filter(|_| glob.is_none() && in_name_order);
+        let listing = groups::sorted_log_groups(
+            client,
+            name_prefix,
+            max_pages,
+            args.single_stream.resume_token.clone(),
+            fetch_limit,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        });
+        let log_groups = listing.groups;
+        // replayed listings are from whenever (and wherever) they were recorded, and only a
+        // full listing stands in for the groups when completing names, not one resumed or cut
+        // short by --max-pages
+        let complete = args.single_stream.resume_token.is_none() && listing.resume_token.is_none();
+        if args.replay.is_none() && name_prefix.is_none() && fetch_limit.is_none() && complete {
+            let names = log_groups
+                .iter()
+                .map(|g| g.name.clone())
+                .collect::<Vec<String>>();
+            cache_listing(client, profile, "log-groups", None, &names);
+        }
+        let mut log_groups = match glob {
+            Some(pattern) => {
+                let re = glob::glob_regex(pattern);
+                log_groups
+                    .into_iter()
+                    .filter(|g| re.is_match(&g.name))
+                    .collect()
+            }
+            None => log_groups,
+        };
+        if let Some(order) = args.listing.order_by {
+            groups::sort_groups(&mut log_groups, order, args.listing.desc);
+        }
+        if let Some(limit) = args.listing.limit {
+            log_groups.truncate(limit);
+        }
+        println!("Log Groups:");
+        if args.listing.details {
+            let rows = log_groups
+                .iter()
+                .map(groups::details_row)
+                .collect::<Vec<insights::Row>>();
+            println!("{}", insights::format_table(&rows));
+            return;
+        }
+        for group in log_groups {
+            println!("{}", group.name);
+        }
+        return;
+    }
+    if let Some(ref new_group) = args.create_log_group {
+        groups::create_log_group(client, new_group)
+            .await
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            });
+        eprintln!("created log group {}", new_group.name);
+        return;
+    }
+    if args.delete_log_group {
+        let named = !args.delete_log_groups.is_empty();
+        let names = match args.log_group_prefix.as_deref() {
+            Some(_) if named => {
+                println!("give either log groups or --log-group-prefix to delete, not both");
+                return;
+            }
+            Some(prefix) => {
+                let max_pages = args.fetch.max_pages.unwrap_or(DEFAULT_MAX_GROUP_PAGES);
+                get_sorted_log_group_names(client, Some(prefix), max_pages, None).await
+            }
+            None if !named => {
+                println!("a log group is required, e.g. alog delete-log-group /ecs/app, or --log-group-prefix /ecs/app- for every group starting with it");
+                return;
+            }
+            None => {
+                let mut names = vec![];
+                for pattern in &args.delete_log_groups {
+                    if glob::is_glob(pattern) {
+                        match glob::matching_log_groups(client, pattern).await {
+                            Ok(matching) => names.extend(matching),
+                            Err(e) => {
+                                println!("Error: {}", e);
+                                std::process::exit(e.exit_code());
+                            }
+                        }
+                    } else {
+                        names.push(pattern.clone());
+                    }
+                }
+                let mut seen = HashSet::new();
+                names.retain(|name| seen.insert(name.clone()));
+                Ok(names)
+            }
+        }
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        });
+        if names.is_empty() {
+            println!("no log groups to delete");
+            return;
+        }
+        if !args.yes {
+            if !picker::available() {
+                println!("not deleting without confirmation, run from a terminal or pass --yes");
+                return;
+            }
+            for name in &names {
+                eprintln!("{name}");
+            }
+            let prompt = match names.len() {
+                1 => "delete this log group and all of its events?".to_string(),
+                n => format!("delete these {n} log groups and all of their events?"),
+            };
+            match picker::confirm(&prompt) {
+                Ok(true) => {}
+                Ok(false) => return,
+                Err(e) => {
+                    println!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        let mut failed = 0;
+        for name in &names {
+            match groups::delete_log_group(client, name).await {
+                Ok(()) => eprintln!("deleted log group {name}"),
+                Err(e) => {
+                    println!("Error: {}", e);
+                    failed += 1;
+                }
+            }
+        }
+        if failed > 0 {
+            std::process::exit(1);
+        }
+        return;
+    }
+    let mut log_group = args.log_group.clone().unwrap_or(String::from(""));
+    // the repl has its own way of picking a group
+    if log_group.is_empty() && !args.interactive && picker::available() {
+        let names = get_sorted_log_group_names(
+            client,
+            args.log_group_prefix.as_deref(),
+            DEFAULT_MAX_GROUP_PAGES,
+            None,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        });
+        match picker::pick("log group", &names) {
+            Ok(Some(name)) => log_group = name,
+            Ok(None) => return,
+            Err(e) => {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    // a glob like /aws/lambda/orders-* stands for every log group it matches, and --log-group
+    // can be repeated
+    let mut log_groups: Vec<String> = vec![];
+    for pattern in std::iter::once(&log_group).chain(&args.extra_log_groups) {
+        if glob::is_glob(pattern) {
+            let names = glob::matching_log_groups(client, pattern)
+                .await
+                .unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(e.exit_code());
+                });
+            if names.is_empty() {
+                println!("no log groups match {pattern}");
+                return;
+            }
+            log_groups.extend(names);
+        } else {
+            log_groups.push(pattern.clone());
+        }
+    }
+    let mut seen = HashSet::new();
+    log_groups.retain(|name| seen.insert(name.clone()));
+    if log_groups.len() > 1 {
+        let given = [
+            ("--describe-log-streams", args.describe_log_streams),
+            ("`alog stats`", args.group_stats),
+            ("`alog export`", args.export_to.is_some()),
+            ("--export-iac", args.export_iac.is_some()),
+            ("--open", args.open),
+            ("--interactive", args.interactive),
+            ("--manifest", args.output.manifest),
+        ];
+        if let Some((flag, _)) = given.iter().find(|(_, set)| *set) {
+            println!(
+                "{flag} works on one log group, not the {} given",
+                log_groups.len()
+            );
+            return;
+        }
+    }
+    let multi_stream = is_query
+        || args.live
+        || args.streams.all_streams
+        || args.streams.stream_regex.is_some()
+        || args.streams.log_stream_prefix.is_some();
+    if let [only] = log_groups.as_slice() {
+        log_group = only.clone();
+    } else if !args.extra_log_groups.is_empty() && !multi_stream {
+        // the groups named are fetched whole and merged, like --all-streams
+        let given = [
+            ("--log-stream", args.streams.log_stream.is_some()),
+            ("--goto", args.goto.is_some()),
+            ("--follow", args.follow),
+            (
+                "--export-chunked",
+                args.single_stream.export_chunked.is_some(),
+            ),
+            ("--verify-count", args.single_stream.verify_count),
+            ("--resume-token", args.single_stream.resume_token.is_some()),
+        ];
+        if let Some((flag, _)) = given.iter().find(|(_, set)| *set) {
+            println!("{flag} cannot be used with several log groups");
+            return;
+        }
+        args.streams.all_streams = true;
+        eprintln!("fetching from {} log groups", log_groups.len());
+    } else if !multi_stream {
+        println!("{log_group} matches {} log groups, only Insights queries, --live and fetching with --all-streams, --stream-regex or --log-stream-prefix (or `alog grep`) work across several", log_groups.len());
+        return;
+    } else if args.extra_log_groups.is_empty() {
+        eprintln!("{} log groups match {log_group}", log_groups.len());
+    }
+    // records name their group, and their stream once it's known
+    let record_format = match args.render.format {
+        format::OutputFormat::Ndjson => Some(records::RecordFormat::Ndjson),
+        format::OutputFormat::Csv => Some(records::RecordFormat::Csv(&args.render.csv_columns.0)),
+        _ => None,
+    };
+    let record_renderer = record_format.map(|format| records::Records {
+        format,
+        log_group: &log_group,
+        log_stream: None,
+    });
+    let file_format = LineFormat {
+        records: record_renderer,
+        ..file_format
+    };
+    let stdout_format = LineFormat {
+        records: record_renderer,
+        ..stdout_format
+    };
+    if let Some(iac_format) = args.export_iac {
+        if log_group.is_empty() {
+            println!("--log-group is required when using --export-iac");
+            return;
+        }
+        let exported = group_config::fetch_group_config(client, &log_group)
+            .await
+            .and_then(|config| iac::export(&config, iac_format).map_err(AwsLogsError::Io))
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            });
+        print!("{exported}");
+        return;
+    }
+    if args.open {
+        if log_group.is_empty() {
+            println!("--log-group is required when using --open");
+            return;
+        }
+        let region = client
+            .config()
+            .region()
+            .map(|r| r.to_string())
+            .unwrap_or_else(|| {
+                println!("Error: no AWS region configured");
+                std::process::exit(1);
+            });
+        let url = console::log_console_url(
+            &region,
+            &log_group,
+            args.streams.log_stream.as_deref(),
+            args.start.since,
+        );
+        println!("{url}");
+        console::open_in_browser(&url).unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        });
+        return;
+    }
+    if args.group_stats {
+        if log_group.is_empty() {
+            println!("a log group is required, e.g. alog stats -g /ecs/app");
+            return;
+        }
+        let sample = args.stream_listing.with_sizes.then(|| {
+            let since = args.start.since.unwrap_or(DEFAULT_QUERY_WINDOW);
+            let end_time = timestamps::now_millis() / 1000;
+            let window = humantime::format_duration(since).to_string();
+            (end_time - since.as_secs() as i64, end_time, window)
+        });
+        let stats = stats::group_stats(client, &log_group, sample)
+            .await
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            });
+        if args.render.format == format::OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&stats).unwrap());
+        } else {
+            println!("{}", stats.report());
+        }
+        return;
+    }
+    if let Some(ref destination) = args.export_to {
+        if log_group.is_empty() {
+            println!("a log group is required, e.g. alog export -g /ecs/app s3://bucket/prefix --since 30d");
+            return;
+        }
+        let Some(from) = start_time else {
+            println!("an export needs the start of its window, e.g. --since 30d or --start-time");
+            return;
+        };
+        let to = end_time.unwrap_or_else(timestamps::now_millis);
+        let task_id = export::create_export(
+            client,
+            &log_group,
+            args.streams.log_stream_prefix.as_deref(),
+            from,
+            to,
+            destination,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        });
+        eprintln!("started export task {task_id}");
+        let wait = export::wait_for_export(client, &task_id, |code| {
+            eprintln!("export task {task_id}: {}", code.as_str().to_lowercase())
+        });
+        if let Err(e) = wait.await {
+            println!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        }
+        println!("{}", destination.task_url(&task_id));
+        if let Some(ref fpath) = args.output.output_file {
+            let s3_client = aws_sdk_s3::Client::new(&aws_config);
+            let compression = args
+                .output
+                .compress
+                .or_else(|| compress::Compression::from_path(std::path::Path::new(fpath)));
+            let mut output = OutputFile::create(fpath, compression, None).unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            });
+            if let Some(header) = file_format.records.and_then(|records| records.header()) {
+                output.write_header(&header);
+            }
+            // each object is filtered and written as soon as it's downloaded
+            let mut written = 0;
+            let mut first_event_ms = None;
+            export::download_export(
+                &s3_client,
+                destination,
+                &task_id,
+                |mut events| {
+                    events.retain(|e| grep_matches(grep.as_ref(), e));
+                    if let Some(ref level_filter) = args.filters.level {
+                        events.retain(|e| level_filter.matches(&e.message));
+                    }
+                    if let Some(ref jq_filter) = jq_filter {
+                        jq_filter.filter_events(&mut events);
+                    }
+                    if let Some(ref redactor) = redactor {
+                        redact_events(&mut events, redactor);
+                    }
+                    first_event_ms = first_event_ms.or(events.first().map(|e| e.timestamp));
+                    for line in render_lines_from(&events, &file_format, first_event_ms) {
+                        output.write_line(&line);
+                    }
+                    written += events.len();
+                    Ok(())
+                },
+                |number, total, key| eprintln!("[{number}/{total}] downloading {key}"),
+            )
             .await
             .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            });
+            output.finish().unwrap_or_else(|e| {
                 println!("Error: {}", e);
                 std::process::exit(1);
             });
-        let mut logstream_previews: HashMap<String, String> = HashMap::new();
-        let preview_requested = args.preview_lines > 0;
+            eprintln!("wrote {written} events to {fpath}");
+        }
+        return;
+    }
+    if args.describe_log_streams {
+        if log_group.is_empty() {
+            println!("--log-group is required when using --describe-log-streams");
+            return;
+        }
+        let name_pattern = args.streams.stream_regex.as_deref().map(|pattern| {
+            regex::Regex::new(pattern).unwrap_or_else(|e| {
+                println!("Error: invalid --stream-regex: {}", e);
+                std::process::exit(1);
+            })
+        });
+        let stream_filter = streams::StreamFilter {
+            name_prefix: args.streams.log_stream_prefix.clone(),
+            name_pattern,
+            created_after: parse_time_arg(&args.stream_listing.created_after),
+            created_before: parse_time_arg(&args.stream_listing.created_before),
+            last_event_after: parse_time_arg(&args.stream_listing.last_event_after),
+        };
+        let by_size = args.listing.order_by == Some(streams::ListOrder::Size);
+        if by_size && !args.stream_listing.with_sizes {
+            println!("--order-by size needs --with-sizes for streams, CloudWatch no longer reports their stored bytes");
+            return;
+        }
+        // by size or turned around, the listing is only in order once it's all there
+        let fetch_limit = args
+            .listing
+            .limit
+            .filter(|_| !by_size && !args.listing.desc);
+        let mut log_streams = streams::ordered_log_streams(
+            client,
+            &log_group,
+            &stream_filter,
+            args.listing.order_by,
+            fetch_limit,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        });
+        let stream_sizes = if args.stream_listing.with_sizes {
+            let since = args.start.since.unwrap_or(DEFAULT_QUERY_WINDOW);
+            let end_time = timestamps::now_millis() / 1000;
+            let start_time = end_time - since.as_secs() as i64;
+            insights::stream_sizes(client, &log_group, start_time, end_time)
+                .await
+                .unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(e.exit_code());
+                })
+        } else {
+            HashMap::new()
+        };
+        if let (Some(order), true) = (args.listing.order_by, by_size || args.listing.desc) {
+            streams::sort_streams(&mut log_streams, order, args.listing.desc, &stream_sizes);
+        }
+        if let Some(limit) = args.listing.limit {
+            log_streams.truncate(limit);
+        }
+        let log_stream_names = log_streams
+            .iter()
+            .map(|s| s.name.clone())
+            .collect::<Vec<String>>();
+        // a filtered listing is incomplete, don't let completion use it, nor a replayed one
+        let cacheable = args.replay.is_none()
+            && args.listing.limit.is_none()
+            && stream_filter.name_prefix.is_none()
+            && stream_filter.created_after.is_none()
+            && stream_filter.created_before.is_none()
+            && stream_filter.last_event_after.is_none();
+        if cacheable {
+            cache_listing(
+                client,
+                profile,
+                "log-streams",
+                Some(&log_group),
+                &log_stream_names,
+            );
+        }
+        let describe_stream = |name: &str| match stream_sizes.get(name) {
+            Some(size) => format!(
+                "{name}\t{} events\t{}",
+                size.events,
+                format::format_bytes(size.bytes as f64)
+            ),
+            None if args.stream_listing.with_sizes => format!("{name}\t0 events\t-"),
+            None => name.to_string(),
+        };
+        let mut logstream_previews: HashMap<String, Vec<Event>> = HashMap::new();
+        let preview_requested = args.stream_listing.preview_lines > 0;
+        if preview_requested && args.listing.details {
+            println!("--details cannot be used with --preview-lines");
+            return;
+        }
         if preview_requested {
             // get the first N lines of the last 20 log streams
-            let preview_streams = args.preview_streams;
+            let preview_streams = args.stream_listing.preview_streams;
             if preview_streams == 0 {
                 println!("--preview-streams must be greater than 0");
                 return;
             }
-            let preview_event_count = args.preview_lines;
+            let preview_event_count = args.stream_listing.preview_lines;
             let max_preview_events = 200;
             if preview_event_count > max_preview_events {
                 println!("Preview amount cannot be greater than {max_preview_events}");
                 return;
             }
-            let preview_log_stream_names = log_stream_names
+            // the most recently created, whatever the listing is ordered by
+            let mut newest_streams = log_streams.iter().collect::<Vec<&streams::StreamInfo>>();
+            newest_streams.sort_by_key(|s| std::cmp::Reverse(s.creation_time));
+            let preview_log_stream_names = newest_streams
                 .iter()
-                .rev()
                 .take(preview_streams as usize)
-                .map(|s| s.as_str())
+                .map(|s| s.name.as_str())
                 .collect::<Vec<&str>>();
             let mut preview_futures = vec![];
             for log_stream_name in preview_log_stream_names.clone() {
@@ -397,38 +3831,1037 @@ async fn main() {
 
             for (i, fut_result) in fut_results.into_iter().enumerate() {
                 let log_stream_name = preview_log_stream_names[i];
-                let events = fut_result;
-                let text = get_text_from_events(&events);
-                logstream_previews.insert(log_stream_name.to_string(), text);
+                let mut events = fut_result.unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(e.exit_code());
+                });
+                if let Some(ref level_filter) = args.filters.level {
+                    events.retain(|e| level_filter.matches(&e.message));
+                }
+                if let Some(ref jq_filter) = jq_filter {
+                    jq_filter.filter_events(&mut events);
+                }
+                if let Some(ref redactor) = redactor {
+                    redact_events(&mut events, redactor);
+                }
+                logstream_previews.insert(log_stream_name.to_string(), events);
             }
         }
+        if args.render.format == format::OutputFormat::Json {
+            let listing = preview::StreamListing {
+                log_group: log_group.clone(),
+                log_streams: log_streams
+                    .iter()
+                    .map(|stream| {
+                        preview::StreamEntry::new(
+                            stream,
+                            stream_sizes.get(&stream.name),
+                            logstream_previews.get(&stream.name).map(|e| e.as_slice()),
+                        )
+                    })
+                    .collect(),
+            };
+            println!("{}", serde_json::to_string_pretty(&listing).unwrap());
+            return;
+        }
         println!("Log Streams (log group: {log_group}):");
+        if args.listing.details {
+            let rows = log_streams
+                .iter()
+                .map(|stream| {
+                    preview::details_row(
+                        stream,
+                        stream_sizes.get(&stream.name),
+                        args.stream_listing.with_sizes,
+                    )
+                })
+                .collect::<Vec<insights::Row>>();
+            println!("{}", insights::format_table(&rows));
+            return;
+        }
         for name in log_stream_names {
             if preview_requested {
-                println!("\n------------------\n{}", name);
+                println!("\n------------------\n{}", describe_stream(&name));
                 // check if it's in the hashmap
                 let is_in_hashmap = logstream_previews.contains_key(&name);
                 if is_in_hashmap {
                     let preview = logstream_previews.get(&name).unwrap();
-                    println!("PREVIEW:\n{}", preview);
+                    println!(
+                        "PREVIEW:\n{}",
+                        get_text_from_events(preview, &stdout_format)
+                    );
                 }
             } else {
-                println!("{}", name);
+                println!("{}", describe_stream(&name));
+            }
+        }
+        return;
+    }
+
+    if args.interactive {
+        let log_group = (!log_group.is_empty()).then_some(log_group);
+        let since = args.start.since.unwrap_or(DEFAULT_QUERY_WINDOW);
+        repl::run(client, log_group, since)
+            .await
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            });
+        return;
+    }
+    let insights_query = match (&args.query, &args.stats, &args.count_by) {
+        (Some(query), _, _) => Some(query.clone()),
+        (_, Some(expression), _) => Some(insights::stats_query(expression)),
+        (_, _, Some(spec)) => Some(insights::count_by_query(spec).unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        })),
+        _ => None,
+    };
+    if let Some(query) = insights_query {
+        if log_group.is_empty() {
+            println!("--log-group is required when running an Insights query");
+            return;
+        }
+        let since = args.start.since.unwrap_or(DEFAULT_QUERY_WINDOW);
+        let end_time = timestamps::now_millis() / 1000;
+        let start_time = end_time - since.as_secs() as i64;
+        let rows = insights::run_query_on_groups(client, &log_groups, &query, start_time, end_time)
+            .await
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            });
+        match args.render.format {
+            format::OutputFormat::Grafana => println!("{}", grafana::to_json(&rows)),
+            // json is rejected for queries up front
+            format::OutputFormat::Text
+            | format::OutputFormat::Json
+            | format::OutputFormat::Ndjson
+            | format::OutputFormat::Csv => {
+                println!("{}", insights::format_table(&rows))
+            }
+        }
+        return;
+    }
+
+    if !args.panes.is_empty() {
+        let pipeline = FetchPipeline {
+            line_format: stdout_format,
+            ..pipeline
+        };
+        tail_in_panes(
+            client,
+            &log_group,
+            &args.panes,
+            &args,
+            &pipeline,
+            rule_set.as_ref(),
+        )
+        .await;
+        return;
+    }
+    if args.live {
+        if log_group.is_empty() {
+            println!("--log-group is required when using --live");
+            return;
+        }
+        let mut group_arns = vec![];
+        for group in &log_groups {
+            let group_arn = live::log_group_arn(client, group)
+                .await
+                .unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(e.exit_code());
+                });
+            group_arns.push(group_arn);
+        }
+        let mut live_tail = live::LiveTail::new(
+            client,
+            group_arns,
+            &args.live_stream_prefix,
+            args.fetch.filter_pattern.as_deref(),
+        );
+        loop {
+            let batch = live_tail.next_batch().await.unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            });
+            let Some(mut events) = batch else {
+                return;
+            };
+            if let Some(ref level_filter) = args.filters.level {
+                events.retain(|e| level_filter.matches(&e.message));
+            }
+            if let Some(ref jq_filter) = jq_filter {
+                jq_filter.filter_events(&mut events);
+            }
+            if let Some(ref redactor) = redactor {
+                redact_events(&mut events, redactor);
+            }
+            if args.filters.dedup {
+                events = dedup::dedup_events(events);
+            }
+            let mut exit_status = None;
+            if let Some(ref rule_set) = rule_set {
+                for i in 0..events.len() {
+                    let event_group = events[i].log_group.as_deref().unwrap_or(&log_group);
+                    let event_stream = events[i].log_stream.as_deref().unwrap_or_default();
+                    let triggered = rule_set.apply(&events[i], event_group, event_stream).await;
+                    if triggered.bell {
+                        eprint!("\x07");
+                    }
+                    if let Some(status) = triggered.exit_status {
+                        events.truncate(i + 1);
+                        exit_status = Some(status);
+                        break;
+                    }
+                }
+            }
+            for line in render_lines(&events, &stdout_format) {
+                println!("{line}");
+            }
+            if let Some(status) = exit_status {
+                std::process::exit(status);
+            }
+        }
+    }
+    // with --log-stream-prefix (or --stream-regex), log_stream is the prefix (or regex) and events
+    // are labeled with their stream
+    let multi_stream_filter = multi_stream_filter(&args, start_time);
+    let label_streams = multi_stream_filter.is_some();
+    // the (log group, log stream) of every stream to fetch
+    let (log_stream, sources) = match multi_stream_filter {
+        Some(filter) => {
+            if log_group.is_empty() {
+                println!("--log-group is required when using --log-stream-prefix, --stream-regex or --all-streams");
+                return;
+            }
+            let mut sources = vec![];
+            for group in &log_groups {
+                let names = get_sorted_log_stream_names(client, group, &filter)
+                    .await
+                    .unwrap_or_else(|e| {
+                        println!("Error: {}", e);
+                        std::process::exit(e.exit_code());
+                    });
+                sources.extend(names.into_iter().map(|name| (group.clone(), name)));
+            }
+            if sources.is_empty() {
+                println!("no log streams to fetch in {log_group}");
+                return;
+            }
+            eprintln!("fetching {} log streams", sources.len());
+            let label = args
+                .streams
+                .log_stream_prefix
+                .clone()
+                .or(args.streams.stream_regex.clone());
+            (label.unwrap_or_default(), sources)
+        }
+        None => {
+            let log_stream = match args.streams.log_stream.clone() {
+                Some(log_stream) => log_stream,
+                None if !log_group.is_empty() && picker::available() => {
+                    let mut names = get_sorted_log_stream_names(
+                        client,
+                        &log_group,
+                        &streams::StreamFilter::default(),
+                    )
+                    .await
+                    .unwrap_or_else(|e| {
+                        println!("Error: {}", e);
+                        std::process::exit(e.exit_code());
+                    });
+                    // newest first
+                    names.reverse();
+                    match picker::pick("log stream", &names) {
+                        Ok(Some(name)) => name,
+                        Ok(None) => return,
+                        Err(e) => {
+                            println!("Error: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                None => {
+                    println!("--log-group and --log-stream are required to fetch events (or run from a terminal to pick them)");
+                    return;
+                }
+            };
+            (log_stream.clone(), vec![(log_group.clone(), log_stream)])
+        }
+    };
+    let log_streams = sources
+        .iter()
+        .map(|(_, name)| name.clone())
+        .collect::<Vec<String>>();
+    let record_renderer = record_renderer.map(|renderer| records::Records {
+        log_stream: Some(&log_stream),
+        ..renderer
+    });
+    let file_format = LineFormat {
+        records: record_renderer,
+        ..file_format
+    };
+    let stdout_format = LineFormat {
+        records: record_renderer,
+        ..stdout_format
+    };
+    let recipients = encrypt::Recipients::from_args(&args.output.encrypt_to).unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(1);
+    });
+    if recipients.is_some() && args.output.output_file.is_none() {
+        println!("--output-file is required when using --encrypt-to");
+        return;
+    }
+    if args.output.meta_sidecar && args.output.output_file.is_none() {
+        println!("--output-file is required when using --meta-sidecar");
+        return;
+    }
+    if args.output.meta_sidecar && recipients.is_some() {
+        println!("--meta-sidecar cannot be used with --encrypt-to, the sidecar would be written unencrypted");
+        return;
+    }
+    let compression = args.output.compress.or_else(|| {
+        args.output
+            .output_file
+            .as_ref()
+            .and_then(|fpath| compress::Compression::from_path(std::path::Path::new(fpath)))
+    });
+    if let Some(ref fpath) = args.output.output_file {
+        if is_stream_target(fpath) && args.output.manifest {
+            println!("--manifest needs a regular --output-file, not {fpath}");
+            return;
+        }
+        if is_stream_target(fpath) && args.output.meta_sidecar {
+            println!("--meta-sidecar needs a regular --output-file, not {fpath}");
+            return;
+        }
+        if compression.is_some() && (recipients.is_some() || args.output.meta_sidecar) {
+            println!("a compressed --output-file (.gz, .zst, .xz or --compress) cannot be used with --encrypt-to or --meta-sidecar");
+            return;
+        }
+        if args.output.rotate_size.is_some()
+            && (is_stream_target(fpath)
+                || compression.is_some()
+                || recipients.is_some()
+                || args.output.meta_sidecar
+                || args.output.manifest
+                || args.output.bucket_by.is_some()
+                || args.single_stream.export_chunked.is_some())
+        {
+            println!("--rotate-size needs a plain file as --output-file, it can't be used with a stream, compression, --encrypt-to, --meta-sidecar, --manifest, --bucket-by or --export-chunked");
+            return;
+        }
+        if args.follow
+            && (recipients.is_some()
+                || args.output.meta_sidecar
+                || args.output.manifest
+                || args.output.bucket_by.is_some())
+        {
+            println!("--follow can only write to a plain or compressed --output-file, not with --encrypt-to, --meta-sidecar, --manifest or --bucket-by");
+            return;
+        }
+        if args.output.compress.is_some()
+            && (fpath == "-"
+                || args.output.bucket_by.is_some()
+                || args.single_stream.export_chunked.is_some())
+        {
+            println!("--compress needs a file as --output-file, it can't be used with `-`, --bucket-by or --export-chunked");
+            return;
+        }
+        if is_stream_target(fpath) && args.output.bucket_by.is_some() {
+            println!("--bucket-by needs a directory as --output-file, not {fpath}");
+            return;
+        }
+    }
+    if args.output.bucket_by.is_some() {
+        if args.output.output_file.is_none() {
+            println!("--output-file is required when using --bucket-by");
+            return;
+        }
+        if recipients.is_some() {
+            println!("--encrypt-to cannot be used with --bucket-by, encrypted files can't be appended to");
+            return;
+        }
+    }
+    if args.sink.sink == Some(sink::SinkKind::Sqs) && args.sink.queue_url.is_none() {
+        println!("--queue-url is required when using --sink sqs");
+        return;
+    }
+    if let Some(window) = args.single_stream.export_chunked {
+        let Some(ref dir) = args.output.output_file else {
+            println!("--output-file is required when using --export-chunked");
+            return;
+        };
+        if is_stream_target(dir)
+            || recipients.is_some()
+            || args.output.meta_sidecar
+            || args.output.manifest
+        {
+            println!("--export-chunked writes plain text files into a directory, it can't be used with --encrypt-to, --meta-sidecar, --manifest or a stream as --output-file");
+            return;
+        }
+        let dir = std::path::Path::new(dir);
+        // each page is filtered like the events of any fetch before it's written
+        let files = chunked::export_chunked(
+            client,
+            &log_group,
+            &log_stream,
+            window,
+            dir,
+            |mut events| {
+                if let Some(ref level_filter) = args.filters.level {
+                    events.retain(|e| level_filter.matches(&e.message));
+                }
+                if let Some(ref jq_filter) = jq_filter {
+                    jq_filter.filter_events(&mut events);
+                }
+                if let Some(ref redactor) = redactor {
+                    redact_events(&mut events, redactor);
+                }
+                render_lines(&events, &file_format)
+            },
+            |chunk| {
+                eprintln!(
+                    "[{}/{}] {}: {} events",
+                    chunk.number, chunk.total, chunk.file_name, chunk.events
+                )
+            },
+        )
+        .await
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        });
+        if files.is_empty() {
+            eprintln!("{log_stream} has no events");
+        } else {
+            eprintln!("exported {} chunks to {}", files.len(), dir.display());
+        }
+        return;
+    }
+    if args.follow {
+        let mut follower = follow::Follower::new(
+            client,
+            &log_group,
+            &log_stream,
+            start_time,
+            args.follow_interval,
+        );
+        let mut output = args.output.output_file.as_ref().map(|fpath| {
+            let mut output = OutputFile::create(fpath, compression, args.output.rotate_size)
+                .unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(1);
+                });
+            if let Some(header) = file_format.records.and_then(|records| records.header()) {
+                output.write_header(&header);
+            }
+            output
+        });
+        let finish_output = |output: Option<OutputFile>| {
+            if let Some(output) = output {
+                output.finish().unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(1);
+                });
+            }
+        };
+        let mut rollup = args.analysis.rollup.map(rollup::Rollup::new);
+        // events already in the stream when following started don't ring, call webhooks or exit
+        let rules_start = timestamps::now_millis();
+        loop {
+            // a rollup window ends on time, even when no event comes after it
+            let batch = match rollup.as_ref().and_then(rollup::Rollup::window_end) {
+                Some(end) => {
+                    let wait = (end - timestamps::now_millis()).max(0) as u64;
+                    tokio::time::timeout(
+                        std::time::Duration::from_millis(wait),
+                        follower.next_batch(),
+                    )
+                    .await
+                    .unwrap_or(Ok(Some(vec![])))
+                }
+                None => follower.next_batch().await,
+            };
+            let batch = batch.unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            });
+            let Some(mut events) = batch else {
+                // the repeats of the window cut short by Ctrl-C
+                if let Some(ref mut rollup) = rollup {
+                    let summaries = rollup.flush(timestamps::now_millis());
+                    match output {
+                        Some(ref mut output) => {
+                            for line in render_lines(&summaries, &file_format) {
+                                output.write_line(&line);
+                            }
+                        }
+                        None => {
+                            for line in render_lines(&summaries, &stdout_format) {
+                                println!("{line}");
+                            }
+                        }
+                    }
+                }
+                finish_output(output);
+                return;
+            };
+            if let Some(ref level_filter) = args.filters.level {
+                events.retain(|e| level_filter.matches(&e.message));
+            }
+            if let Some(ref jq_filter) = jq_filter {
+                jq_filter.filter_events(&mut events);
+            }
+            if let Some(ref redactor) = redactor {
+                redact_events(&mut events, redactor);
+            }
+            if args.filters.dedup {
+                events = dedup::dedup_events(events);
+            }
+            if let Some(ref mut rollup) = rollup {
+                let mut rolled = events
+                    .into_iter()
+                    .flat_map(|event| rollup.push(event))
+                    .collect::<Vec<Event>>();
+                if let Some(end) = rollup
+                    .window_end()
+                    .filter(|end| *end <= timestamps::now_millis())
+                {
+                    rolled.extend(rollup.flush(end));
+                }
+                events = rolled;
+            }
+            let mut exit_status = None;
+            if let Some(ref rule_set) = rule_set {
+                for i in 0..events.len() {
+                    if events[i].ingestion_time < rules_start {
+                        continue;
+                    }
+                    let triggered = rule_set.apply(&events[i], &log_group, &log_stream).await;
+                    if triggered.bell {
+                        eprint!("\x07");
+                    }
+                    if let Some(status) = triggered.exit_status {
+                        events.truncate(i + 1);
+                        exit_status = Some(status);
+                        break;
+                    }
+                }
+            }
+            match output {
+                Some(ref mut output) => {
+                    for line in render_lines(&events, &file_format) {
+                        output.write_line(&line);
+                    }
+                    // each batch is on disk before waiting for the next
+                    output.flush();
+                }
+                None => {
+                    for line in render_lines(&events, &stdout_format) {
+                        println!("{line}");
+                    }
+                }
             }
+            if let Some(status) = exit_status {
+                finish_output(output);
+                std::process::exit(status);
+            }
+        }
+    }
+    let tail: Option<u32> = args.fetch.tail;
+    if args.fetch.concurrency == 0 {
+        println!("--concurrency must be greater than 0");
+        return;
+    }
+    if let Some(page_limit) = args.fetch.page_limit {
+        if page_limit == 0 || page_limit > MAX_PAGE_LIMIT {
+            println!("--page-limit must be between 1 and {MAX_PAGE_LIMIT}");
+            return;
+        }
+    }
+    let pagination = Pagination {
+        page_limit: args.fetch.page_limit.map(|n| n as i32),
+        max_pages: args.fetch.max_pages,
+        resume_token: args.single_stream.resume_token.clone(),
+    };
+    // a single stream going straight to a file is written as it's fetched, unless something
+    // needs all of its events first or the file is rewritten afterwards
+    let write_incrementally = !label_streams
+        && tail.is_none()
+        && args.fetch.filter_pattern.is_none()
+        && args.output.bucket_by.is_none()
+        && args.sink.sink.is_none()
+        && recipients.is_none()
+        && !args.output.meta_sidecar
+        && !args.output.manifest
+        && !args.single_stream.verify_count
+        && !args.analysis.stitch_split_events
+        && args.analysis.gap_threshold.is_none()
+        && args.analysis.rollup.is_none()
+        && !args.filters.dedup
+        && args.analysis.histogram.is_none()
+        && !args.view.copy
+        && !args.fetch.cache;
+    // only a plain file can be cut back to a checkpoint and appended to
+    let checkpoint_output = args
+        .output
+        .output_file
+        .as_deref()
+        .filter(|fpath| {
+            write_incrementally
+                && !is_stream_target(fpath)
+                && compression.is_none()
+                && args.output.rotate_size.is_none()
+        })
+        .map(std::path::Path::new);
+    if args.single_stream.resume && checkpoint_output.is_none() {
+        println!("--resume needs a single stream written to a plain --output-file as it's fetched, without compression, --rotate-size or options that need every event first (like --tail or --filter-pattern)");
+        return;
+    }
+    let resumed = checkpoint_output
+        .filter(|_| args.single_stream.resume)
+        .and_then(|path| {
+            checkpoint::Checkpoint::load(path).unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            })
+        });
+    if let Some(ref resumed) = resumed {
+        if resumed.log_group != log_group || resumed.log_stream != log_stream {
+            println!(
+                "the checkpoint of --output-file is for {} {}, not this stream",
+                resumed.log_group, resumed.log_stream
+            );
+            return;
+        }
+        eprintln!(
+            "resuming after {} events already in the file",
+            resumed.events
+        );
+    } else if args.single_stream.resume {
+        eprintln!("no checkpoint to resume from, starting from the head of the stream");
+    }
+    if let (Some(ref fpath), true) = (&args.output.output_file, write_incrementally) {
+        info!("writing to file as events are fetched: {fpath}");
+        let output = match resumed {
+            Some(ref resumed) => OutputFile::resume(fpath, resumed),
+            None => OutputFile::create(fpath, compression, args.output.rotate_size),
         }
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        });
+        let pagination = Pagination {
+            resume_token: resumed
+                .as_ref()
+                .map(|c| c.forward_token.clone())
+                .or_else(|| pagination.resume_token.clone()),
+            ..pagination.clone()
+        };
+        let written = write_events_incrementally(
+            client,
+            &log_group,
+            &log_stream,
+            start_time,
+            end_time,
+            &pagination,
+            grep.as_ref(),
+            args.filters.level.as_ref(),
+            jq_filter.as_ref(),
+            redactor.as_ref(),
+            &file_format,
+            output,
+            checkpoint_output,
+            resumed.as_ref(),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        });
+        info!("wrote {written} events to {fpath}");
         return;
     }
+    let event_store = args.fetch.cache.then(|| {
+        event_store_path(&args)
+            .ok_or_else(|| {
+                "unable to find a cache directory, set HOME or XDG_CACHE_HOME".to_string()
+            })
+            .and_then(|path| store::EventStore::open(&path))
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            })
+    });
+    // the same stream name in another account, region or profile is another stream
+    let cache_source = match event_store {
+        Some(_) => Some(store::Source {
+            account: org::caller_identity(&aws_config)
+                .await
+                .map(|caller| caller.account)
+                .unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(1);
+                }),
+            region: client
+                .config()
+                .region()
+                .map(|r| r.to_string())
+                .unwrap_or_default(),
+            profile: profile.unwrap_or("default").to_string(),
+        }),
+        None => None,
+    };
+    let filter_pattern = args.fetch.filter_pattern.as_deref();
+    let event_store = event_store.as_ref().zip(cache_source.as_ref());
+    let pagination = &pagination;
+    let grep = grep.as_ref();
+    let log_group_count = log_groups.len();
+    // streams are downloaded --concurrency at a time, and kept in the order they were listed in
+    let stream_events_list = futures::stream::iter(&sources)
+        .map(|(stream_group, stream)| async move {
+            let mut stream_events = match filter_pattern {
+                Some(filter_pattern) => {
+                    fetch_filtered_log(
+                        client,
+                        stream_group,
+                        stream,
+                        filter_pattern,
+                        tail,
+                        start_time,
+                        end_time,
+                        pagination,
+                        grep,
+                    )
+                    .await
+                }
+                None => match (event_store, tail) {
+                    (Some((event_store, source)), None) => {
+                        store::fetch_cached_log(
+                            client,
+                            event_store,
+                            source,
+                            stream_group,
+                            stream,
+                            start_time,
+                            end_time,
+                            grep,
+                        )
+                        .await
+                    }
+                    _ => {
+                        fetch_entire_log(
+                            client,
+                            stream_group,
+                            stream,
+                            tail,
+                            start_time,
+                            end_time,
+                            pagination,
+                            grep,
+                        )
+                        .await
+                    }
+                },
+            }
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            });
+            if label_streams {
+                // streams of different groups can share a name, so they're labeled with both
+                let log_group = (log_group_count > 1).then(|| stream_group.clone());
+                for event in stream_events.iter_mut() {
+                    event.log_stream = Some(stream.clone());
+                    event.log_group = log_group.clone();
+                }
+            }
+            stream_events
+        })
+        .buffered(args.fetch.concurrency)
+        .collect::<Vec<Vec<Event>>>()
+        .await;
+    let mut events: Vec<Event> = if args.streams.all_streams || args.streams.stream_regex.is_some()
+    {
+        merge::merge_by_timestamp(stream_events_list)
+    } else {
+        stream_events_list.into_iter().flatten().collect()
+    };
+    // every stream was fetched with its own last --tail events, only the last of all of them
+    // are output
+    if let (Some(tail), true) = (tail, label_streams) {
+        let skip = events.len().saturating_sub(tail as usize);
+        events.drain(..skip);
+    }
+    // --level, --jq and the rest only filter after this, but --grep already has
+    if args.single_stream.verify_count && grep.is_some() {
+        eprintln!("--verify-count is skipped when using --grep, only matching events are kept");
+    } else if args.single_stream.verify_count {
+        verify_event_count(
+            client,
+            &log_group,
+            &log_stream,
+            tail,
+            start_time,
+            end_time,
+            &events,
+        )
+        .await;
+    }
+    if args.analysis.stitch_split_events {
+        events = stitch::stitch_split_events(events);
+    } else if stitch::has_split_events(&events) {
+        eprintln!("WARNING: some messages look split at the event size limit, use --stitch-split-events to rejoin them");
+    }
+    // gaps are measured before filtering, a quiet stretch only counts if nothing was logged.
+    // a silence up to the end of the window (now by default) counts too, and one since its
+    // start unless only the last events were fetched
+    let event_gaps = args.analysis.gap_threshold.map(|threshold| {
+        let window_start = start_time.filter(|_| tail.is_none());
+        let window_end = end_time.unwrap_or_else(timestamps::now_millis);
+        gaps::find_gaps(&events, threshold, window_start, Some(window_end))
+    });
+    if let Some(ref level_filter) = args.filters.level {
+        events.retain(|e| level_filter.matches(&e.message));
+    }
+    if let Some(ref jq_filter) = jq_filter {
+        jq_filter.filter_events(&mut events);
+    }
+    if let Some(ref redactor) = redactor {
+        redact_events(&mut events, redactor);
+    }
+    let chart = match args
+        .analysis
+        .histogram
+        .map(|bucket| histogram::render(&events, bucket))
+        .transpose()
+    {
+        Ok(chart) => chart,
+        Err(e) => {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if args.filters.dedup {
+        events = dedup::dedup_events(events);
+    }
+    if let Some(window) = args.analysis.rollup {
+        events = rollup::rollup_events(window, events);
+    }
+    if let Some(ref event_gaps) = event_gaps {
+        events = gaps::mark_gaps(events, event_gaps);
+    }
 
-    let log_stream = args.log_stream.expect("log-stream argument not supplied");
-    let tail: Option<u32> = args.tail;
-    let events: Vec<Event> = fetch_entire_log(client, &log_group, &log_stream, tail).await;
-    let full_log_text = get_text_from_events(&events);
+    if args.view.copy {
+        let text = get_text_from_events(&events, &file_format);
+        let (text, truncated) =
+            clipboard::truncate_to_last_lines(&text, clipboard::MAX_CLIPBOARD_BYTES);
+        match clipboard::copy_to_clipboard(text) {
+            Ok(()) if truncated => eprintln!(
+                "copied the last {} bytes of output to the clipboard (truncated)",
+                text.len()
+            ),
+            Ok(()) => eprintln!("copied {} events to the clipboard", events.len()),
+            Err(e) => eprintln!("WARNING: unable to copy to the clipboard: {e}"),
+        }
+    }
 
-    if let Some(fpath) = args.output_file {
+    if let (Some(sink::SinkKind::Sqs), Some(queue_url)) = (args.sink.sink, &args.sink.queue_url) {
+        let sqs_client = aws_sdk_sqs::Client::new(&aws_config);
+        let sent = sink::send_to_sqs(&sqs_client, queue_url, &log_group, &log_stream, &events)
+            .await
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(e.exit_code());
+            });
+        eprintln!("sent {sent} events to {queue_url}");
+    } else if let (Some(dir), Some(bucket_by)) = (&args.output.output_file, args.output.bucket_by) {
+        let dir = std::path::Path::new(dir);
+        let sidecar_stream = args.output.meta_sidecar.then_some(log_stream.as_str());
+        let (mut bucket_files, appended) =
+            write_bucketed_events(dir, bucket_by, &events, &file_format, sidecar_stream)
+                .unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(1);
+                });
+        if args.output.manifest {
+            let mut event_count = events.len();
+            let mut first_event_time = events.iter().map(|e| e.timestamp).min();
+            let mut last_event_time = events.iter().map(|e| e.timestamp).max();
+            // appended bucket files also hold the events of earlier runs, carry those over from
+            // the manifest they wrote, along with the bucket files this run didn't touch
+            let previous = if appended {
+                manifest::Manifest::read(dir).unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(1);
+                })
+            } else {
+                None
+            };
+            let mut manifest_streams = log_streams.clone();
+            match previous {
+                Some(previous) => {
+                    event_count += previous.event_count;
+                    for log_stream in previous.log_streams {
+                        if !manifest_streams.contains(&log_stream) {
+                            manifest_streams.push(log_stream);
+                        }
+                    }
+                    let parse = |time: Option<String>| {
+                        time.and_then(|time| timestamps::parse_time(&time).ok())
+                    };
+                    let previous_first = parse(previous.first_event_time);
+                    let previous_last = parse(previous.last_event_time);
+                    first_event_time = first_event_time.into_iter().chain(previous_first).min();
+                    last_event_time = last_event_time.into_iter().chain(previous_last).max();
+                    for file in &previous.files {
+                        let path = manifest::Manifest::file_path(file, dir);
+                        if path.exists() && !bucket_files.contains(&path) {
+                            bucket_files.push(path);
+                        }
+                    }
+                    bucket_files.sort();
+                }
+                None if appended => eprintln!(
+                    "no earlier manifest found for {}, its event count only covers the events written now",
+                    dir.display()
+                ),
+                None => {}
+            }
+            let mut manifest = manifest::Manifest::new(
+                &log_group,
+                manifest_streams,
+                first_event_time,
+                last_event_time,
+                event_count,
+            );
+            bucket_files
+                .iter()
+                .try_for_each(|path| manifest.add_file(path, dir))
+                .and_then(|_| manifest.write(dir))
+                .unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(1);
+                });
+        }
+    } else if let Some(fpath) = args.output.output_file {
+        let lines = render_lines(&events, &file_format);
+        let header = file_format.records.and_then(|records| records.header());
+        let mut full_log_text = with_header(header.clone(), lines.join("\n"));
+        if is_stream_target(&fpath) && !lines.is_empty() {
+            full_log_text.push('\n');
+        }
         let error_msg = format!("Unable to write file: {fpath}");
         info!("writing to file: {fpath}");
-        std::fs::write(&fpath, full_log_text).expect(&error_msg);
-    } else {
-        println!("FULL LOG TEXT:\n{full_log_text}");
+        if let Some(ref recipients) = recipients {
+            encrypt::write_encrypted(
+                std::path::Path::new(&fpath),
+                full_log_text.as_bytes(),
+                recipients,
+            )
+            .expect(&error_msg);
+        } else if fpath == "-" {
+            std::io::stdout()
+                .write_all(full_log_text.as_bytes())
+                .expect(&error_msg);
+        } else if let Some(compression) = compression {
+            compress::write_compressed(
+                std::path::Path::new(&fpath),
+                full_log_text.as_bytes(),
+                compression,
+            )
+            .unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            });
+        } else if args.output.rotate_size.is_some() {
+            let mut output = OutputFile::create(&fpath, None, args.output.rotate_size)
+                .unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(1);
+                });
+            if let Some(ref header) = header {
+                output.write_header(header);
+            }
+            for line in &lines {
+                output.write_line(line);
+            }
+            output.finish().unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            });
+        } else {
+            std::fs::write(&fpath, full_log_text).expect(&error_msg);
+        }
+        let path = std::path::Path::new(&fpath);
+        let sidecar_path = args.output.meta_sidecar.then(|| {
+            sidecar::write_sidecar(path, &log_stream, &events, &lines, 0).unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            })
+        });
+        if args.output.manifest {
+            let mut manifest = manifest::Manifest::new(
+                &log_group,
+                log_streams.clone(),
+                events.first().map(|e| e.timestamp),
+                events.last().map(|e| e.timestamp),
+                events.len(),
+            );
+            manifest
+                .add_file(path, path)
+                .and_then(|_| match sidecar_path {
+                    Some(ref sidecar_path) => manifest.add_file(sidecar_path, path),
+                    None => Ok(()),
+                })
+                .and_then(|_| manifest.write(path))
+                .unwrap_or_else(|e| {
+                    println!("Error: {}", e);
+                    std::process::exit(1);
+                });
+        }
+    } else if chart.is_none() {
+        if args.output.manifest {
+            eprintln!("--manifest is ignored without --output-file");
+        }
+        if args.view.pager {
+            let header = stdout_format.records.and_then(|records| records.header());
+            let lines = header
+                .into_iter()
+                .chain(render_lines(&events, &stdout_format))
+                .collect::<Vec<String>>();
+            picker::view(&lines).unwrap_or_else(|e| {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            });
+        } else {
+            let full_log_text = get_text_from_events(&events, &stdout_format);
+            // records go out as is, ready to pipe into jq or a csv reader
+            let full_log_text = match stdout_format.records {
+                Some(records) => with_header(records.header(), full_log_text),
+                None => format!("FULL LOG TEXT:\n{full_log_text}"),
+            };
+            println!("{full_log_text}");
+        }
+    }
+    if let Some(chart) = chart {
+        println!("{chart}");
+    }
+    if let (Some(event_gaps), Some(threshold)) = (event_gaps, args.analysis.gap_threshold) {
+        let threshold = humantime::format_duration(threshold);
+        if event_gaps.is_empty() {
+            eprintln!("no gaps longer than {threshold}");
+        } else {
+            eprintln!("{} gaps longer than {threshold}:", event_gaps.len());
+            for gap in event_gaps {
+                eprintln!("  {}", gap.describe());
+            }
+        }
     }
 }
@@ -0,0 +1,149 @@
+use std::path::Path;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::timestamps::format_rfc3339;
+
+/// describes a set of files written by a fetch, so they can later be proven intact.
+/// the manifest is written as pretty printed json with a stable field order, which makes it
+/// suitable for a detached signature (e.g. gpg --detach-sign)
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    #[serde(rename = "toolVersion")]
+    pub tool_version: String,
+
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+
+    #[serde(rename = "logGroup")]
+    pub log_group: String,
+
+    #[serde(rename = "logStreams")]
+    pub log_streams: Vec<String>,
+
+    #[serde(rename = "firstEventTime")]
+    pub first_event_time: Option<String>,
+
+    #[serde(rename = "lastEventTime")]
+    pub last_event_time: Option<String>,
+
+    #[serde(rename = "eventCount")]
+    pub event_count: usize,
+
+    #[serde(rename = "files")]
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ManifestFile {
+    #[serde(rename = "path")]
+    pub path: String,
+
+    #[serde(rename = "bytes")]
+    pub bytes: u64,
+
+    #[serde(rename = "sha256")]
+    pub sha256: String,
+}
+
+/// hex encoded SHA-256 digest of a file's contents, plus its size in bytes
+pub fn sha256_file(path: &Path) -> Result<(String, u64), String> {
+    let mut file =
+        std::fs::File::open(path).map_err(|e| format!("unable to open {}: {e}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let bytes = std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("unable to read {}: {e}", path.display()))?;
+    Ok((hex::encode(hasher.finalize()), bytes))
+}
+
+impl Manifest {
+    pub fn new(
+        log_group: &str,
+        log_streams: Vec<String>,
+        first_event_time: Option<i64>,
+        last_event_time: Option<i64>,
+        event_count: usize,
+    ) -> Self {
+        Manifest {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: jiff::Timestamp::now().to_string(),
+            log_group: log_group.to_string(),
+            log_streams,
+            first_event_time: first_event_time.map(format_rfc3339),
+            last_event_time: last_event_time.map(format_rfc3339),
+            event_count,
+            files: vec![],
+        }
+    }
+
+    /// checksum an output file and record it in the manifest.
+    /// base is the path the manifest will be written next to, see write()
+    pub fn add_file(&mut self, path: &Path, base: &Path) -> Result<(), String> {
+        let (sha256, bytes) = sha256_file(path)?;
+        self.files.push(ManifestFile {
+            path: relative_path(path, base),
+            bytes,
+            sha256,
+        });
+        Ok(())
+    }
+
+    /// read the manifest an earlier run wrote next to base, None when there isn't one
+    pub fn read(base: &Path) -> Result<Option<Manifest>, String> {
+        let manifest_path = with_suffix(base, ".manifest.json");
+        let json = match std::fs::read_to_string(&manifest_path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("unable to read {}: {e}", manifest_path.display())),
+        };
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("unable to parse {}: {e}", manifest_path.display()))
+    }
+
+    /// the path of a listed file, they are relative to the directory the manifest is in
+    pub fn file_path(file: &ManifestFile, base: &Path) -> std::path::PathBuf {
+        base.parent().unwrap_or(Path::new("")).join(&file.path)
+    }
+
+    /// write `<base>.sha256` (in `sha256sum -c` format) and `<base>.manifest.json` next to the output
+    pub fn write(&self, base: &Path) -> Result<(), String> {
+        let checksums = self
+            .files
+            .iter()
+            .map(|f| format!("{}  {}\n", f.sha256, f.path))
+            .collect::<String>();
+        let checksum_path = with_suffix(base, ".sha256");
+        std::fs::write(&checksum_path, checksums)
+            .map_err(|e| format!("unable to write {}: {e}", checksum_path.display()))?;
+
+        let manifest_path = with_suffix(base, ".manifest.json");
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("unable to serialize manifest: {e}"))?;
+        std::fs::write(&manifest_path, json + "\n")
+            .map_err(|e| format!("unable to write {}: {e}", manifest_path.display()))?;
+        info!(
+            "wrote checksums: {}, manifest: {}",
+            checksum_path.display(),
+            manifest_path.display()
+        );
+        Ok(())
+    }
+}
+
+/// file paths in the manifest are relative to the directory the manifest is written to
+fn relative_path(path: &Path, base: &Path) -> String {
+    let manifest_dir = base.parent().unwrap_or(Path::new(""));
+    path.strip_prefix(manifest_dir)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> std::path::PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    name.into()
+}
@@ -0,0 +1,35 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::Event;
+
+/// merge lists of time sorted events (e.g. one per stream) into one time sorted list with a
+/// k-way merge. events with the same timestamp come out in the order of their lists
+pub fn merge_by_timestamp(lists: Vec<Vec<Event>>) -> Vec<Event> {
+    let total = lists.iter().map(Vec::len).sum();
+    let mut lists = lists
+        .into_iter()
+        .map(|list| list.into_iter())
+        .collect::<Vec<_>>();
+    let mut heads = lists
+        .iter_mut()
+        .map(|list| list.next())
+        .collect::<Vec<Option<Event>>>();
+    // the smallest (timestamp, list) on top
+    let mut heap = heads
+        .iter()
+        .enumerate()
+        .filter_map(|(i, head)| head.as_ref().map(|e| Reverse((e.timestamp, i))))
+        .collect::<BinaryHeap<Reverse<(i64, usize)>>>();
+    let mut merged = Vec::with_capacity(total);
+    while let Some(Reverse((_, i))) = heap.pop() {
+        let next = lists[i].next();
+        if let Some(ref event) = next {
+            heap.push(Reverse((event.timestamp, i)));
+        }
+        if let Some(event) = std::mem::replace(&mut heads[i], next) {
+            merged.push(event);
+        }
+    }
+    merged
+}
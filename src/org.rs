@@ -0,0 +1,179 @@
+use std::path::Path;
+
+use aws_config::sts::AssumeRoleProvider;
+use aws_config::SdkConfig;
+use aws_sdk_cloudwatchlogs::config::SharedCredentialsProvider;
+use aws_sdk_organizations::types::AccountStatus;
+use log::{debug, info};
+use serde::Deserialize;
+
+use crate::error::AwsLogsError;
+
+/// the role Organizations creates in member accounts it creates
+pub const DEFAULT_ROLE_NAME: &str = "OrganizationAccountAccessRole";
+
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+}
+
+impl Account {
+    /// how output from this account is labeled
+    pub fn label(&self) -> String {
+        format!("{} ({})", self.name, self.id)
+    }
+
+    /// reached by assuming role_name in it. partition is that of the organization, e.g. aws or
+    /// aws-cn
+    pub fn target(&self, partition: &str, role_name: &str) -> Target {
+        Target {
+            label: self.label(),
+            role_arn: format!("arn:{partition}:iam::{}:role/{role_name}", self.id),
+            external_id: None,
+        }
+    }
+}
+
+/// an account output is fetched from, and the role assumed to reach it
+#[derive(Debug, Clone, Deserialize)]
+pub struct Target {
+    /// how output from this account is labeled
+    #[serde(rename = "alias")]
+    pub label: String,
+    pub role_arn: String,
+    #[serde(default)]
+    pub external_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RolesFile {
+    #[serde(rename = "account", default)]
+    accounts: Vec<Target>,
+}
+
+/// the accounts of an --accounts file, one `[[account]]` table each:
+///
+/// ```toml
+/// [[account]]
+/// alias = "prod"
+/// role_arn = "arn:aws:iam::111111111111:role/LogReader"
+///
+/// [[account]]
+/// alias = "staging"
+/// role_arn = "arn:aws:iam::222222222222:role/LogReader"
+/// external_id = "alog-staging"
+/// ```
+pub fn load_targets(path: &Path) -> Result<Vec<Target>, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("unable to read {}: {e}", path.display()))?;
+    let roles: RolesFile =
+        toml::from_str(&contents).map_err(|e| format!("invalid {}: {e}", path.display()))?;
+    if roles.accounts.is_empty() {
+        return Err(format!("no [[account]] entries in {}", path.display()));
+    }
+    Ok(roles.accounts)
+}
+
+/// who the credentials of config belong to
+#[derive(Debug, Clone)]
+pub struct CallerIdentity {
+    pub account: String,
+    /// the partition of the caller's ARN: aws, aws-cn, aws-us-gov, ...
+    pub partition: String,
+}
+
+/// the account and partition the credentials of config belong to, from STS GetCallerIdentity
+pub async fn caller_identity(config: &SdkConfig) -> Result<CallerIdentity, AwsLogsError> {
+    let response = aws_sdk_sts::Client::new(config)
+        .get_caller_identity()
+        .send()
+        .await
+        .map_err(|e| AwsLogsError::sdk("failed to get the caller identity", &e))?;
+    let account = response
+        .account
+        .ok_or_else(|| AwsLogsError::Sdk("no account in the caller identity".to_string()))?;
+    // arn:<partition>:sts::<account>:assumed-role/...
+    let partition = response
+        .arn
+        .as_deref()
+        .and_then(|arn| arn.split(':').nth(1))
+        .filter(|partition| !partition.is_empty())
+        .ok_or_else(|| AwsLogsError::Sdk("no ARN in the caller identity".to_string()))?
+        .to_string();
+    Ok(CallerIdentity { account, partition })
+}
+
+/// the active accounts of the organization the configured credentials belong to
+pub async fn list_accounts(config: &SdkConfig) -> Result<Vec<Account>, AwsLogsError> {
+    let client = aws_sdk_organizations::Client::new(config);
+    let mut accounts = vec![];
+    let mut next_token: Option<String> = None;
+    loop {
+        let response = client
+            .list_accounts()
+            .set_next_token(next_token)
+            .send()
+            .await
+            .map_err(|e| AwsLogsError::sdk("failed to list organization accounts", &e))?;
+        for account in response.accounts.unwrap_or_default() {
+            if account.status != Some(AccountStatus::Active) {
+                debug!("skipping inactive account: {:?}", account.id);
+                continue;
+            }
+            if let Some(id) = account.id {
+                let name = account.name.unwrap_or_else(|| id.clone());
+                accounts.push(Account { id, name });
+            }
+        }
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+    accounts.sort_by(|a, b| a.name.cmp(&b.name));
+    info!("found {} active organization accounts", accounts.len());
+    Ok(accounts)
+}
+
+/// config whose credentials come from assuming role_arn with config's credentials, for
+/// --role-arn. the role is only assumed on the first call, like in account_client
+pub async fn assume_role(
+    config: SdkConfig,
+    role_arn: &str,
+    external_id: Option<&str>,
+    session_name: &str,
+) -> SdkConfig {
+    debug!("assuming role: {role_arn}");
+    let mut builder = AssumeRoleProvider::builder(role_arn).session_name(session_name);
+    if let Some(external_id) = external_id {
+        builder = builder.external_id(external_id);
+    }
+    let provider = builder.configure(&config).build().await;
+    config
+        .into_builder()
+        .credentials_provider(SharedCredentialsProvider::new(provider))
+        .build()
+}
+
+/// a client for the account of target that assumes its role, in region instead of config's
+/// when given. the role is only assumed on the first call, so a missing or untrusted role shows
+/// up as an error from that call
+pub async fn account_client(
+    config: &SdkConfig,
+    target: &Target,
+    region: Option<&str>,
+) -> aws_sdk_cloudwatchlogs::Client {
+    debug!("assuming role: {}", target.role_arn);
+    let mut builder = AssumeRoleProvider::builder(&target.role_arn).session_name("alog");
+    if let Some(ref external_id) = target.external_id {
+        builder = builder.external_id(external_id);
+    }
+    let provider = builder.configure(config).build().await;
+    let mut conf =
+        aws_sdk_cloudwatchlogs::config::Builder::from(config).credentials_provider(provider);
+    if let Some(region) = region {
+        conf = conf.region(aws_config::Region::new(region.to_string()));
+    }
+    aws_sdk_cloudwatchlogs::Client::from_conf(conf.build())
+}
@@ -0,0 +1,318 @@
+use std::collections::VecDeque;
+use std::sync::mpsc;
+
+use ::console::{pad_str, style, Alignment, Key, Term};
+
+/// how many streams can be tailed side by side
+pub const MAX_PANES: usize = 4;
+
+/// lines each pane keeps to scroll back through, the oldest are dropped after that
+const SCROLLBACK: usize = 10_000;
+
+/// the keys of the panes, on the status line
+const PANE_KEYS: &str = "tab next pane  ↑↓ PgUp PgDn scroll  G latest  q quit";
+
+/// where a pane goes on the screen, in columns and rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+/// the screen (without its status line) split for count panes: side by side for two, two
+/// rows of two for four, and the third of three across the bottom row. columns are one apart,
+/// for the separator
+fn layout(count: usize, width: usize, height: usize) -> Vec<Rect> {
+    let columns = count.min(2);
+    let rows = count.div_ceil(2);
+    (0..count)
+        .map(|i| {
+            let (row, column) = (i / columns, i % columns);
+            // the last pane of an odd count has its row to itself
+            let spans = i + 1 == count && column == 0;
+            let (x, pane_width) = match (spans, column) {
+                (true, _) => (0, width),
+                (false, 0) => (0, (width.saturating_sub(1)) / 2),
+                (false, _) => {
+                    let left = (width.saturating_sub(1)) / 2;
+                    (left + 1, width.saturating_sub(left + 1))
+                }
+            };
+            let y = height * row / rows;
+            let next_y = height * (row + 1) / rows;
+            Rect {
+                x,
+                y,
+                width: pane_width,
+                height: next_y - y,
+            }
+        })
+        .collect()
+}
+
+/// a stream's lines and how far back it's scrolled
+struct Pane {
+    log_stream: String,
+    lines: VecDeque<String>,
+    /// how many lines up from the newest the pane is scrolled, 0 to keep up with new ones
+    scroll: usize,
+}
+
+impl Pane {
+    fn push(&mut self, lines: Vec<String>) {
+        for line in lines {
+            // a message spanning several lines takes that many rows
+            for row in line.split('\n') {
+                self.lines.push_back(row.to_string());
+                if self.scroll > 0 {
+                    self.scroll += 1;
+                }
+            }
+        }
+        let dropped = self.lines.len().saturating_sub(SCROLLBACK);
+        self.lines.drain(..dropped);
+        self.scroll = self.scroll.min(self.lines.len());
+    }
+
+    fn scroll(&mut self, by: isize, rows: usize) {
+        let oldest = self.lines.len().saturating_sub(rows);
+        self.scroll = self.scroll.saturating_add_signed(by).min(oldest);
+    }
+
+    /// the lines showing in rows rows, oldest first
+    fn visible(&self, rows: usize) -> impl Iterator<Item = &String> {
+        let end = self.lines.len() - self.scroll;
+        self.lines.range(end.saturating_sub(rows)..end)
+    }
+}
+
+/// several streams tailed side by side on the terminal, each in its own pane that scrolls back
+/// on its own. keys are read on a thread of their own, one at a time when asked for, so none is
+/// left waiting for a key (with the terminal in raw mode) once the panes are closed
+pub struct Panes {
+    term: Term,
+    panes: Vec<Pane>,
+    /// the pane the keys scroll
+    focus: usize,
+    /// shown on the status line instead of the keys
+    notice: Option<String>,
+    /// whether the next key quits, whichever it is
+    closing: bool,
+    keys: tokio::sync::mpsc::Receiver<std::io::Result<Key>>,
+    /// told whether to read another key after each one
+    read_more: mpsc::Sender<bool>,
+}
+
+/// what a key does to the panes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Redraw,
+    Quit,
+}
+
+impl Panes {
+    /// take over the terminal (its alternate screen) with a pane per stream
+    pub fn open(log_streams: &[String]) -> std::io::Result<Panes> {
+        let term = Term::buffered_stdout();
+        term.write_str("\x1b[?1049h")?;
+        term.hide_cursor()?;
+        let (key_sender, keys) = tokio::sync::mpsc::channel(1);
+        let (read_more, asked) = mpsc::channel::<bool>();
+        let reader = Term::stdout();
+        std::thread::spawn(move || loop {
+            if key_sender.blocking_send(reader.read_key()).is_err() {
+                return;
+            }
+            if asked.recv() != Ok(true) {
+                return;
+            }
+        });
+        Ok(Panes {
+            term,
+            panes: log_streams
+                .iter()
+                .map(|log_stream| Pane {
+                    log_stream: log_stream.clone(),
+                    lines: VecDeque::new(),
+                    scroll: 0,
+                })
+                .collect(),
+            focus: 0,
+            notice: None,
+            closing: false,
+            keys,
+            read_more,
+        })
+    }
+
+    /// add the rendered lines of new events to pane i
+    pub fn push(&mut self, i: usize, lines: Vec<String>) {
+        self.panes[i].push(lines);
+    }
+
+    /// show message on the status line and quit on the next key, whichever it is
+    pub fn finish(&mut self, message: String) {
+        self.notice = Some(message);
+        self.closing = true;
+    }
+
+    /// ring the terminal bell
+    pub fn bell(&self) -> std::io::Result<()> {
+        self.term.write_str("\x07")
+    }
+
+    /// the next key pressed, Ctrl-C (or a terminal that can't be read) quitting
+    pub async fn next_key(&mut self) -> KeyAction {
+        let key = self.keys.recv().await;
+        let (height, width) = self.term.size();
+        let rect = layout(
+            self.panes.len(),
+            width as usize,
+            height.saturating_sub(1) as usize,
+        )[self.focus];
+        // the rows under the pane's title
+        let page = rect.height.saturating_sub(1).max(1);
+        let pane = &mut self.panes[self.focus];
+        let action = match key {
+            Some(Ok(_)) if self.closing => KeyAction::Quit,
+            Some(Ok(Key::Char('q'))) | Some(Ok(Key::Escape)) | Some(Err(_)) | None => {
+                KeyAction::Quit
+            }
+            Some(Ok(key)) => {
+                match key {
+                    Key::Tab => self.focus = (self.focus + 1) % self.panes.len(),
+                    Key::BackTab => {
+                        self.focus = (self.focus + self.panes.len() - 1) % self.panes.len()
+                    }
+                    Key::ArrowUp | Key::Char('k') => pane.scroll(1, page),
+                    Key::ArrowDown | Key::Char('j') => pane.scroll(-1, page),
+                    Key::PageUp | Key::Char('b') => pane.scroll(page as isize, page),
+                    Key::PageDown | Key::Char(' ') | Key::Char('f') => {
+                        pane.scroll(-(page as isize), page)
+                    }
+                    Key::Home | Key::Char('g') => pane.scroll(isize::MAX, page),
+                    Key::End | Key::Char('G') => pane.scroll = 0,
+                    _ => {}
+                }
+                KeyAction::Redraw
+            }
+        };
+        // the reader is only told to carry on once the key was taken
+        let _ = self.read_more.send(action == KeyAction::Redraw);
+        action
+    }
+
+    pub fn draw(&self) -> std::io::Result<()> {
+        let (height, width) = self.term.size();
+        let (height, width) = (height.saturating_sub(1) as usize, width as usize);
+        let rects = layout(self.panes.len(), width, height);
+        // the screen a row at a time, each row with the part of every pane crossing it
+        let mut screen = vec![String::new(); height];
+        for (i, (pane, rect)) in self.panes.iter().zip(&rects).enumerate() {
+            let mut title = pane.log_stream.clone();
+            if pane.scroll > 0 {
+                title.push_str(&format!(" (scrolled back {} lines)", pane.scroll));
+            }
+            let title = pad_str(&title, rect.width, Alignment::Left, Some("…"));
+            let title = match i == self.focus {
+                true => style(title).reverse().force_styling(true).to_string(),
+                false => style(title).underlined().force_styling(true).to_string(),
+            };
+            let rows = rect.height.saturating_sub(1);
+            // fewer lines than rows start at the top
+            let cells = std::iter::once(title.as_str())
+                .chain(pane.visible(rows).map(String::as_str))
+                .chain(std::iter::repeat(""));
+            for (line, text) in screen[rect.y..rect.y + rect.height].iter_mut().zip(cells) {
+                if rect.x > 0 {
+                    line.push('│');
+                }
+                line.push_str(&pad_str(text, rect.width, Alignment::Left, Some("")));
+                // the colors of a line cut short don't run into the pane next to it
+                line.push_str("\x1b[0m");
+            }
+        }
+        self.term.move_cursor_to(0, 0)?;
+        for line in &screen {
+            self.term.write_str(line)?;
+            self.term.write_str("\x1b[K\r\n")?;
+        }
+        let status = self.notice.as_deref().unwrap_or(PANE_KEYS);
+        let status = ::console::truncate_str(status, width, "");
+        self.term.write_str(&format!(
+            "{}\x1b[K",
+            style(status).reverse().force_styling(true)
+        ))?;
+        self.term.flush()
+    }
+
+    /// give the terminal back the way it was
+    pub fn close(self) -> std::io::Result<()> {
+        let _ = self.read_more.send(false);
+        self.term.write_str("\x1b[?1049l")?;
+        self.term.show_cursor()?;
+        self.term.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_panes_are_side_by_side() {
+        let rects = layout(2, 81, 40);
+        assert_eq!(
+            rects,
+            vec![
+                Rect {
+                    x: 0,
+                    y: 0,
+                    width: 40,
+                    height: 40
+                },
+                Rect {
+                    x: 41,
+                    y: 0,
+                    width: 40,
+                    height: 40
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn the_third_of_three_panes_spans_the_bottom() {
+        let rects = layout(3, 80, 40);
+        assert_eq!(rects[2].x, 0);
+        assert_eq!(rects[2].width, 80);
+        assert_eq!((rects[2].y, rects[2].height), (20, 20));
+        assert_eq!((rects[0].y, rects[1].y), (0, 0));
+    }
+
+    #[test]
+    fn four_panes_are_two_rows_of_two() {
+        let rects = layout(4, 80, 41);
+        assert_eq!((rects[0].height, rects[2].height), (20, 21));
+        assert_eq!((rects[2].x, rects[3].x), (0, 40));
+    }
+
+    #[test]
+    fn scrolled_back_pane_stays_put_on_new_lines() {
+        let mut pane = Pane {
+            log_stream: "app/1".to_string(),
+            lines: VecDeque::new(),
+            scroll: 0,
+        };
+        pane.push((0..10).map(|i| i.to_string()).collect());
+        pane.scroll(3, 5);
+        pane.push(vec!["10\n11".to_string()]);
+        assert_eq!(pane.scroll, 5);
+        assert_eq!(
+            pane.visible(5).cloned().collect::<Vec<String>>(),
+            ["2", "3", "4", "5", "6"]
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use std::io::{IsTerminal, Write};
+
+use ::console::{style, Key, Term};
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, FuzzySelect};
+
+/// how many names the picker shows at once
+const VISIBLE_NAMES: usize = 15;
+
+/// the keys of view(), on its status line
+const VIEW_KEYS: &str = "/ search  n N next/previous match  & filter  q quit";
+
+/// whether there's someone to pick a name, with stdin and stderr both terminals. stdout can
+/// still be piped or redirected
+pub fn available() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// let the user pick one of names by typing part of it, on stderr. None when they cancel
+/// with escape
+pub fn pick(prompt: &str, names: &[String]) -> Result<Option<String>, String> {
+    let picked = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .items(names)
+        .default(0)
+        .max_length(VISIBLE_NAMES)
+        .interact_opt()
+        .map_err(|e| format!("unable to pick a {prompt}: {e}"))?;
+    Ok(picked.map(|i| names[i].clone()))
+}
+
+/// ask the user a yes or no question on stderr, no unless they answer yes
+pub fn confirm(prompt: &str) -> Result<bool, String> {
+    let answer = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(false)
+        .interact_opt()
+        .map_err(|e| format!("unable to ask for confirmation: {e}"))?;
+    Ok(answer == Some(true))
+}
+
+/// what a pattern typed on view()'s status line does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    /// `/pattern`, go to the first line containing it
+    Search,
+    /// `&pattern`, only show the lines containing it
+    Filter,
+}
+
+impl Prompt {
+    fn prefix(&self) -> char {
+        match self {
+            Prompt::Search => '/',
+            Prompt::Filter => '&',
+        }
+    }
+}
+
+/// the state of view(): which lines the filter keeps and where the screen is
+struct View<'a> {
+    lines: &'a [String],
+    /// the lines without colors and in lower case, for matching
+    plain: Vec<String>,
+    /// the indexes of the lines the filter keeps
+    visible: Vec<usize>,
+    /// the position in visible of the line at the top of the screen
+    top: usize,
+    search: String,
+    filter: String,
+}
+
+impl<'a> View<'a> {
+    fn new(lines: &'a [String]) -> Self {
+        View {
+            lines,
+            plain: lines
+                .iter()
+                .map(|line| ::console::strip_ansi_codes(line).to_ascii_lowercase())
+                .collect(),
+            visible: (0..lines.len()).collect(),
+            top: 0,
+            search: String::new(),
+            filter: String::new(),
+        }
+    }
+
+    /// whether line i contains pattern, ignoring case
+    fn contains(&self, i: usize, pattern: &str) -> bool {
+        self.plain[i].contains(&pattern.to_ascii_lowercase())
+    }
+
+    fn apply_filter(&mut self) {
+        self.visible = (0..self.lines.len())
+            .filter(|i| self.contains(*i, &self.filter))
+            .collect();
+        self.top = 0;
+    }
+
+    /// scroll so the first line matching the search at or after (or, going back, at or before)
+    /// position from is at the top, staying put when there's none
+    fn find(&mut self, from: usize, forward: bool) {
+        if self.search.is_empty() || self.visible.is_empty() {
+            return;
+        }
+        let found = if forward {
+            (from..self.visible.len()).find(|&pos| self.contains(self.visible[pos], &self.search))
+        } else {
+            (0..=from.min(self.visible.len().saturating_sub(1)))
+                .rev()
+                .find(|&pos| self.contains(self.visible[pos], &self.search))
+        };
+        if let Some(pos) = found {
+            self.top = pos;
+        }
+    }
+
+    fn scroll(&mut self, by: isize, rows: usize) {
+        let last_top = self.visible.len().saturating_sub(rows);
+        self.top = self.top.saturating_add_signed(by).min(last_top);
+    }
+
+    /// a line with the search highlighted, colors dropped from lines that have a match
+    fn render_line(&self, i: usize) -> String {
+        if self.search.is_empty() || !self.contains(i, &self.search) {
+            return self.lines[i].clone();
+        }
+        let text = ::console::strip_ansi_codes(&self.lines[i]).to_string();
+        let pattern = self.search.to_ascii_lowercase();
+        let mut rendered = String::new();
+        let mut end = 0;
+        for (start, _) in self.plain[i].match_indices(&pattern) {
+            rendered.push_str(&text[end..start]);
+            end = start + pattern.len();
+            rendered.push_str(
+                &style(&text[start..end])
+                    .reverse()
+                    .force_styling(true)
+                    .to_string(),
+            );
+        }
+        rendered.push_str(&text[end..]);
+        rendered
+    }
+
+    fn draw(&self, term: &Term, status: &str) -> std::io::Result<()> {
+        let (height, width) = term.size();
+        let (rows, width) = (height.saturating_sub(1) as usize, width as usize);
+        term.move_cursor_to(0, 0)?;
+        for row in 0..rows {
+            if let Some(&i) = self.visible.get(self.top + row) {
+                term.write_str(&::console::truncate_str(&self.render_line(i), width, ""))?;
+            }
+            term.write_str("\x1b[K\r\n")?;
+        }
+        let status = ::console::truncate_str(status, width, "");
+        term.write_str(&format!(
+            "{}\x1b[K",
+            style(status).reverse().force_styling(true)
+        ))?;
+        term.flush()
+    }
+
+    /// where the screen is, what's searched and filtered, and the keys
+    fn status(&self, rows: usize) -> String {
+        let first = (self.top + 1).min(self.visible.len());
+        let last = (self.top + rows).min(self.visible.len());
+        let mut status = format!("lines {first}-{last} of {}", self.visible.len());
+        if !self.filter.is_empty() {
+            status.push_str(&format!(" (&{})", self.filter));
+        }
+        if !self.search.is_empty() {
+            status.push_str(&format!(" (/{})", self.search));
+        }
+        format!("{status}  {VIEW_KEYS}")
+    }
+
+    /// read a pattern on the status line, searching or filtering as it is typed. escape puts
+    /// back what was there before
+    fn read_pattern(&mut self, term: &Term, prompt: Prompt) -> std::io::Result<()> {
+        let (previous, origin) = match prompt {
+            Prompt::Search => (self.search.clone(), self.top),
+            Prompt::Filter => (self.filter.clone(), self.top),
+        };
+        let mut pattern = String::new();
+        loop {
+            match prompt {
+                Prompt::Search => {
+                    self.search = pattern.clone();
+                    self.top = origin;
+                    self.find(origin, true);
+                }
+                Prompt::Filter => {
+                    self.filter = pattern.clone();
+                    self.apply_filter();
+                }
+            }
+            self.draw(term, &format!("{}{pattern}", prompt.prefix()))?;
+            match term.read_key()? {
+                Key::Enter => return Ok(()),
+                Key::Escape => {
+                    match prompt {
+                        Prompt::Search => self.search = previous,
+                        Prompt::Filter => {
+                            self.filter = previous;
+                            self.apply_filter();
+                        }
+                    }
+                    self.top = origin.min(self.visible.len().saturating_sub(1));
+                    return Ok(());
+                }
+                Key::Backspace => {
+                    pattern.pop();
+                }
+                Key::Char(c) if !c.is_control() => pattern.push(c),
+                _ => {}
+            }
+        }
+    }
+
+    fn run(&mut self, term: &Term) -> std::io::Result<()> {
+        loop {
+            let rows = term.size().0.saturating_sub(1) as usize;
+            self.draw(term, &self.status(rows))?;
+            match term.read_key()? {
+                Key::Char('q') | Key::Escape => return Ok(()),
+                Key::ArrowDown | Key::Enter | Key::Char('j') => self.scroll(1, rows),
+                Key::ArrowUp | Key::Char('k') => self.scroll(-1, rows),
+                Key::PageDown | Key::Char(' ') | Key::Char('f') => self.scroll(rows as isize, rows),
+                Key::PageUp | Key::Char('b') => self.scroll(-(rows as isize), rows),
+                Key::Home | Key::Char('g') => self.top = 0,
+                Key::End | Key::Char('G') => self.scroll(isize::MAX, rows),
+                Key::Char('/') => self.read_pattern(term, Prompt::Search)?,
+                Key::Char('&') => self.read_pattern(term, Prompt::Filter)?,
+                Key::Char('n') => self.find(self.top + 1, true),
+                Key::Char('N') => self.find(self.top.saturating_sub(1), false),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// page through lines on the terminal like less: `/pattern` searches as it is typed, n and N
+/// go to the next and previous line containing it, `&pattern` only shows the lines containing
+/// it (an empty one shows them all again). patterns ignore case. lines are printed instead when
+/// stdin or stdout isn't a terminal
+pub fn view(lines: &[String]) -> Result<(), String> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        let mut stdout = std::io::stdout().lock();
+        for line in lines {
+            writeln!(stdout, "{line}").map_err(|e| format!("unable to print events: {e}"))?;
+        }
+        return Ok(());
+    }
+    let term = Term::buffered_stdout();
+    // the alternate screen, so the terminal is left as it was
+    let shown = term
+        .write_str("\x1b[?1049h")
+        .and_then(|_| term.hide_cursor())
+        .and_then(|_| View::new(lines).run(&term));
+    let restored = term
+        .write_str("\x1b[?1049l")
+        .and_then(|_| term.show_cursor())
+        .and_then(|_| term.flush());
+    shown
+        .and(restored)
+        .map_err(|e| format!("unable to show events: {e}"))
+}
@@ -0,0 +1,96 @@
+use serde::Serialize;
+
+use crate::format::format_bytes;
+use crate::insights::{Row, StreamSize};
+use crate::streams::StreamInfo;
+use crate::timestamps::format_display;
+use crate::Event;
+
+/// --describe-log-streams output with --format json
+#[derive(Serialize, Debug)]
+pub struct StreamListing {
+    #[serde(rename = "logGroup")]
+    pub log_group: String,
+
+    #[serde(rename = "logStreams")]
+    pub log_streams: Vec<StreamEntry>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StreamEntry {
+    #[serde(rename = "name")]
+    pub name: String,
+
+    #[serde(rename = "creationTime")]
+    pub creation_time: String,
+
+    #[serde(rename = "firstEventTime")]
+    pub first_event_time: Option<String>,
+
+    #[serde(rename = "lastEventTime")]
+    pub last_event_time: Option<String>,
+
+    /// with --with-sizes
+    #[serde(rename = "events", skip_serializing_if = "Option::is_none")]
+    pub events: Option<i64>,
+
+    #[serde(rename = "bytes", skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<i64>,
+
+    /// only for the streams previewed with --preview-lines
+    #[serde(rename = "preview", skip_serializing_if = "Option::is_none")]
+    pub preview: Option<Vec<PreviewEvent>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PreviewEvent {
+    #[serde(rename = "timestamp")]
+    pub timestamp: String,
+
+    #[serde(rename = "message")]
+    pub message: String,
+}
+
+impl StreamEntry {
+    pub fn new(
+        stream: &StreamInfo,
+        size: Option<&StreamSize>,
+        preview: Option<&[Event]>,
+    ) -> StreamEntry {
+        StreamEntry {
+            name: stream.name.clone(),
+            creation_time: format_display(stream.creation_time),
+            first_event_time: stream.first_event_time.map(format_display),
+            last_event_time: stream.last_event_time.map(format_display),
+            events: size.map(|s| s.events),
+            bytes: size.map(|s| s.bytes),
+            preview: preview.map(|events| {
+                events
+                    .iter()
+                    .map(|e| PreviewEvent {
+                        timestamp: format_display(e.timestamp),
+                        message: e.message.trim().to_string(),
+                    })
+                    .collect()
+            }),
+        }
+    }
+}
+
+/// a row of the --details table of a stream, with its events and bytes when sized with
+/// --with-sizes. CloudWatch no longer reports the stored bytes of a stream
+pub fn details_row(stream: &StreamInfo, size: Option<&StreamSize>, with_sizes: bool) -> Row {
+    let or_dash = |time: Option<i64>| time.map_or("-".to_string(), format_display);
+    let mut row: Row = vec![
+        ("stream".to_string(), stream.name.clone()),
+        ("created".to_string(), format_display(stream.creation_time)),
+        ("first event".to_string(), or_dash(stream.first_event_time)),
+        ("last event".to_string(), or_dash(stream.last_event_time)),
+    ];
+    if with_sizes {
+        let size = size.copied().unwrap_or_default();
+        row.push(("events".to_string(), size.events.to_string()));
+        row.push(("bytes".to_string(), format_bytes(size.bytes as f64)));
+    }
+    row
+}
@@ -0,0 +1,77 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+
+use rust_aws_logs::{Event, PageWatcher};
+
+/// where download progress is drawn once enabled, shared so concurrent downloads each get a line.
+/// unset means no progress is shown
+static DOWNLOADS: OnceLock<MultiProgress> = OnceLock::new();
+
+/// show progress of downloads from now on, on stderr
+pub fn enable() {
+    let _ = DOWNLOADS.set(MultiProgress::new());
+    rust_aws_logs::set_page_observer(observe);
+}
+
+/// a progress line for a stream being fetched, counting the pages it's handed
+fn observe(log_stream: &str) -> PageWatcher {
+    let mut download = Download::start(log_stream);
+    Box::new(move |events| download.page(events))
+}
+
+/// the pages, events and bytes fetched so far for one log stream, and how long it has taken.
+/// does nothing unless progress was enabled
+pub struct Download {
+    bar: Option<ProgressBar>,
+    pages: u64,
+    bytes: u64,
+}
+
+impl Download {
+    pub fn start(log_stream: &str) -> Download {
+        let bar = DOWNLOADS.get().map(|downloads| {
+            let bar = downloads.add(ProgressBar::new_spinner());
+            bar.set_style(
+                ProgressStyle::with_template(
+                    "{spinner} [{elapsed_precise}] {prefix}: {msg}, {pos} events",
+                )
+                .expect("valid progress template"),
+            );
+            bar.set_prefix(log_stream.to_string());
+            bar.set_message("0 pages, 0 B");
+            bar.enable_steady_tick(Duration::from_millis(120));
+            bar
+        });
+        Download {
+            bar,
+            pages: 0,
+            bytes: 0,
+        }
+    }
+
+    /// count a fetched page
+    pub fn page(&mut self, events: &[Event]) {
+        let Some(ref bar) = self.bar else {
+            return;
+        };
+        self.pages += 1;
+        self.bytes += events.iter().map(|e| e.message.len() as u64).sum::<u64>();
+        bar.inc(events.len() as u64);
+        bar.set_message(format!(
+            "{} pages, {}",
+            self.pages,
+            indicatif::HumanBytes(self.bytes)
+        ));
+    }
+}
+
+impl Drop for Download {
+    fn drop(&mut self) {
+        // cleared rather than left behind, so it doesn't end up between the output lines
+        if let Some(ref bar) = self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use log::debug;
+use tokio::sync::Mutex;
+
+/// the limit --max-rps puts on GetLogEvents calls, shared by every fetch. unset means unlimited
+static GET_LOG_EVENTS: OnceLock<TokenBucket> = OnceLock::new();
+
+/// allows `rate` calls per second on average, and bursts of up to `rate` calls (at least one)
+/// after a quiet stretch
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> TokenBucket {
+        let capacity = rate.max(1.0);
+        TokenBucket {
+            rate,
+            capacity,
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// wait until a token is available and take it
+    async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().await;
+                let (ref mut tokens, ref mut refilled) = *state;
+                let now = Instant::now();
+                *tokens = (*tokens + now.duration_since(*refilled).as_secs_f64() * self.rate)
+                    .min(self.capacity);
+                *refilled = now;
+                if *tokens >= 1.0 {
+                    *tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - *tokens) / self.rate)
+            };
+            debug!("rate limited, waiting {wait:?}");
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// limit GetLogEvents to per_second calls per second from now on, only the first call has an
+/// effect
+pub fn limit_get_log_events(per_second: f64) {
+    let _ = GET_LOG_EVENTS.set(TokenBucket::new(per_second));
+}
+
+/// wait for the rate limit before calling GetLogEvents, returns right away without one
+pub async fn get_log_events_turn() {
+    if let Some(bucket) = GET_LOG_EVENTS.get() {
+        bucket.acquire().await;
+    }
+}
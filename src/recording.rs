@@ -0,0 +1,277 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpClient,
+    SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_types::body::SdkBody;
+use aws_smithy_types::byte_stream::ByteStream;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+/// one API call as saved by --record
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Exchange {
+    #[serde(rename = "operation")]
+    operation: String,
+
+    /// the JSON request body, kept to make recordings readable
+    #[serde(rename = "request")]
+    request: Payload,
+
+    #[serde(rename = "status")]
+    status: u16,
+
+    #[serde(rename = "headers")]
+    headers: Vec<(String, String)>,
+
+    #[serde(rename = "body")]
+    body: Payload,
+}
+
+/// a recorded body, as text when it is valid UTF-8 (so recordings stay readable) and hex
+/// encoded otherwise, replayed byte for byte either way
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum Payload {
+    Text(String),
+    Binary {
+        #[serde(rename = "hex")]
+        hex: String,
+    },
+}
+
+impl Payload {
+    fn new(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Payload::Text(text.to_string()),
+            Err(_) => Payload::Binary {
+                hex: hex::encode(bytes),
+            },
+        }
+    }
+
+    fn bytes(&self) -> Result<Vec<u8>, String> {
+        match self {
+            Payload::Text(text) => Ok(text.as_bytes().to_vec()),
+            Payload::Binary { hex } => {
+                hex::decode(hex).map_err(|e| format!("invalid hex encoded body: {e}"))
+            }
+        }
+    }
+}
+
+/// where API responses come from
+#[derive(Debug, Clone)]
+pub enum Recording {
+    /// call AWS and save every response into the directory
+    Record(PathBuf),
+    /// serve the responses saved in the directory without calling AWS
+    Replay(PathBuf),
+}
+
+impl Recording {
+    pub fn http_client(&self) -> Result<SharedHttpClient, String> {
+        match self {
+            Recording::Record(dir) => {
+                std::fs::create_dir_all(dir)
+                    .map_err(|e| format!("unable to create directory {}: {e}", dir.display()))?;
+                if recording_files(dir)?.next().is_some() {
+                    return Err(format!(
+                        "{} already contains recorded responses, record into an empty directory",
+                        dir.display()
+                    ));
+                }
+                let inner = aws_smithy_runtime::client::http::hyper_014::default_client()
+                    .ok_or("no default HTTP client available to record with")?;
+                Ok(SharedHttpClient::new(RecordingClient {
+                    inner,
+                    dir: dir.clone(),
+                    count: Arc::new(AtomicUsize::new(0)),
+                }))
+            }
+            Recording::Replay(dir) => {
+                let mut responses: HashMap<String, VecDeque<Exchange>> = HashMap::new();
+                let mut files = recording_files(dir)?.collect::<Vec<PathBuf>>();
+                files.sort();
+                for path in &files {
+                    let exchange: Exchange = std::fs::read_to_string(path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+                        .map_err(|e| format!("unable to read {}: {e}", path.display()))?;
+                    responses
+                        .entry(exchange.operation.clone())
+                        .or_default()
+                        .push_back(exchange);
+                }
+                info!(
+                    "replaying {} recorded responses from {}",
+                    files.len(),
+                    dir.display()
+                );
+                Ok(SharedHttpClient::new(ReplayingClient {
+                    dir: dir.clone(),
+                    responses: Arc::new(Mutex::new(responses)),
+                }))
+            }
+        }
+    }
+}
+
+fn recording_files(dir: &Path) -> Result<impl Iterator<Item = PathBuf>, String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("unable to read directory {}: {e}", dir.display()))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json")))
+}
+
+/// the API operation of a request, e.g. `GetLogEvents` from `Logs_20140328.GetLogEvents`
+fn operation(request: &HttpRequest) -> String {
+    let target = request.headers().get("x-amz-target").unwrap_or("unknown");
+    target.rsplit('.').next().unwrap_or(target).to_string()
+}
+
+fn response_from(exchange: &Exchange) -> Result<HttpResponse, ConnectorError> {
+    let status = exchange
+        .status
+        .try_into()
+        .map_err(|e| ConnectorError::other(Box::new(e), None))?;
+    let body = exchange
+        .body
+        .bytes()
+        .map_err(|e| ConnectorError::other(e.into(), None))?;
+    let mut response = HttpResponse::new(status, SdkBody::from(body));
+    for (name, value) in &exchange.headers {
+        response.headers_mut().insert(name.clone(), value.clone());
+    }
+    Ok(response)
+}
+
+#[derive(Debug, Clone)]
+struct RecordingClient {
+    inner: SharedHttpClient,
+    dir: PathBuf,
+    count: Arc<AtomicUsize>,
+}
+
+impl HttpClient for RecordingClient {
+    fn http_connector(
+        &self,
+        settings: &HttpConnectorSettings,
+        components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(RecordingConnector {
+            inner: self.inner.http_connector(settings, components),
+            dir: self.dir.clone(),
+            count: self.count.clone(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct RecordingConnector {
+    inner: SharedHttpConnector,
+    dir: PathBuf,
+    count: Arc<AtomicUsize>,
+}
+
+impl HttpConnector for RecordingConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let operation = operation(&request);
+        let request_body = request
+            .body()
+            .bytes()
+            .map(Payload::new)
+            .unwrap_or_else(|| Payload::Text(String::new()));
+        let response = self.inner.call(request);
+        let dir = self.dir.clone();
+        let n = self.count.fetch_add(1, Ordering::SeqCst);
+        HttpConnectorFuture::new(async move {
+            let mut response = response.await?;
+            let body = ByteStream::new(response.take_body())
+                .collect()
+                .await
+                .map_err(|e| ConnectorError::io(Box::new(e)))?
+                .into_bytes();
+            let exchange = Exchange {
+                operation,
+                request: request_body,
+                status: response.status().as_u16(),
+                headers: response
+                    .headers()
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+                body: Payload::new(&body),
+            };
+            let path = dir.join(format!("{n:05}-{}.json", exchange.operation));
+            debug!(
+                "recording {} response to {}",
+                exchange.operation,
+                path.display()
+            );
+            serde_json::to_string_pretty(&exchange)
+                .map_err(|e| e.to_string())
+                .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()))
+                .map_err(|e| {
+                    ConnectorError::other(
+                        format!("unable to record response to {}: {e}", path.display()).into(),
+                        None,
+                    )
+                })?;
+            *response.body_mut() = SdkBody::from(body);
+            Ok(response)
+        })
+    }
+}
+
+/// recorded responses per operation, served in the order they were recorded
+#[derive(Debug, Clone)]
+struct ReplayingClient {
+    dir: PathBuf,
+    responses: Arc<Mutex<HashMap<String, VecDeque<Exchange>>>>,
+}
+
+impl HttpClient for ReplayingClient {
+    fn http_connector(
+        &self,
+        _settings: &HttpConnectorSettings,
+        _components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(self.clone())
+    }
+}
+
+impl HttpConnector for ReplayingClient {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let operation = operation(&request);
+        let exchange = self
+            .responses
+            .lock()
+            .unwrap()
+            .get_mut(&operation)
+            .and_then(|queue| queue.pop_front());
+        let result = match exchange {
+            Some(exchange) => {
+                debug!("replaying recorded {operation} response");
+                response_from(&exchange)
+            }
+            None => Err(ConnectorError::other(
+                format!(
+                    "no recorded {operation} response left in {}",
+                    self.dir.display()
+                )
+                .into(),
+                None,
+            )),
+        };
+        HttpConnectorFuture::ready(result)
+    }
+}
@@ -0,0 +1,261 @@
+use std::io::BufRead;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::level::detect_level;
+use crate::timestamps::format_display;
+use crate::Event;
+
+/// a column of --format csv output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// RFC3339
+    Timestamp,
+    /// RFC3339
+    IngestionTime,
+    LogGroup,
+    LogStream,
+    /// as detected in the message, empty when there isn't one
+    Level,
+    Message,
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "timestamp" => Ok(Column::Timestamp),
+            "ingestion_time" => Ok(Column::IngestionTime),
+            "log_group" => Ok(Column::LogGroup),
+            "log_stream" => Ok(Column::LogStream),
+            "level" => Ok(Column::Level),
+            "message" => Ok(Column::Message),
+            other => Err(format!(
+                "unknown column '{other}', expected one of: timestamp, ingestion_time, log_group, log_stream, level, message"
+            )),
+        }
+    }
+}
+
+impl Column {
+    fn name(&self) -> &'static str {
+        match self {
+            Column::Timestamp => "timestamp",
+            Column::IngestionTime => "ingestion_time",
+            Column::LogGroup => "log_group",
+            Column::LogStream => "log_stream",
+            Column::Level => "level",
+            Column::Message => "message",
+        }
+    }
+}
+
+/// the comma separated columns of --csv-columns, e.g. `timestamp,level,message`
+#[derive(Debug, Clone)]
+pub struct Columns(pub Vec<Column>);
+
+impl FromStr for Columns {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let columns = s
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<Column>, String>>()?;
+        Ok(Columns(columns))
+    }
+}
+
+/// how events are rendered as records instead of text lines
+#[derive(Debug, Clone, Copy)]
+pub enum RecordFormat<'a> {
+    /// one json object per line
+    Ndjson,
+    /// one csv row per line, with a header row
+    Csv(&'a [Column]),
+}
+
+/// renders events as records naming where they came from
+#[derive(Debug, Clone, Copy)]
+pub struct Records<'a> {
+    pub format: RecordFormat<'a>,
+    /// for events that aren't labeled with their own group
+    pub log_group: &'a str,
+    /// for events that aren't labeled with their own stream
+    pub log_stream: Option<&'a str>,
+}
+
+/// one line of --format ndjson output
+#[derive(Serialize, Debug)]
+struct EventRecord<'a> {
+    /// epoch millis
+    #[serde(rename = "timestamp")]
+    timestamp: i64,
+
+    /// epoch millis
+    #[serde(rename = "ingestionTime")]
+    ingestion_time: i64,
+
+    #[serde(rename = "message")]
+    message: &'a str,
+
+    #[serde(rename = "logStream", skip_serializing_if = "Option::is_none")]
+    log_stream: Option<&'a str>,
+
+    #[serde(rename = "logGroup")]
+    log_group: &'a str,
+}
+
+/// quote a csv field if it needs it, per RFC 4180
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl Records<'_> {
+    /// the line before the first record, if the format has one
+    pub fn header(&self) -> Option<String> {
+        match self.format {
+            RecordFormat::Ndjson => None,
+            RecordFormat::Csv(columns) => Some(
+                columns
+                    .iter()
+                    .map(|c| c.name())
+                    .collect::<Vec<&str>>()
+                    .join(","),
+            ),
+        }
+    }
+
+    /// an event as a single line record
+    pub fn render(&self, event: &Event) -> String {
+        let message = event.message.trim_end();
+        let log_stream = event.log_stream.as_deref().or(self.log_stream);
+        let log_group = event.log_group.as_deref().unwrap_or(self.log_group);
+        match self.format {
+            RecordFormat::Ndjson => {
+                let record = EventRecord {
+                    timestamp: event.timestamp,
+                    ingestion_time: event.ingestion_time,
+                    message,
+                    log_stream,
+                    log_group,
+                };
+                // only strings and numbers, serializing can't fail
+                serde_json::to_string(&record).unwrap_or_default()
+            }
+            RecordFormat::Csv(columns) => columns
+                .iter()
+                .map(|column| match column {
+                    Column::Timestamp => format_display(event.timestamp),
+                    Column::IngestionTime => format_display(event.ingestion_time),
+                    Column::LogGroup => csv_field(log_group),
+                    Column::LogStream => csv_field(log_stream.unwrap_or_default()),
+                    Column::Level => detect_level(message)
+                        .map(|level| format!("{level:?}").to_lowercase())
+                        .unwrap_or_default(),
+                    Column::Message => csv_field(message),
+                })
+                .collect::<Vec<String>>()
+                .join(","),
+        }
+    }
+}
+
+/// the events of --format ndjson output, e.g. a file written with it, one record per line.
+/// blank lines are skipped
+pub fn read_ndjson(reader: impl BufRead) -> Result<Vec<Event>, String> {
+    let mut events = vec![];
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("unable to read line {}: {e}", i + 1))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str::<Event>(&line)
+            .map_err(|e| format!("line {} isn't an ndjson event record: {e}", i + 1))?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(message: &str) -> Event {
+        Event {
+            ingestion_time: 1_710_347_309_000,
+            ..Event::at(1_710_347_308_123, message)
+        }
+    }
+
+    #[test]
+    fn quotes_csv_fields_that_need_it() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn parses_csv_columns() {
+        let Columns(columns) = "timestamp, level,message".parse::<Columns>().unwrap();
+        assert_eq!(columns, [Column::Timestamp, Column::Level, Column::Message]);
+        assert!("timestamp,severity".parse::<Columns>().is_err());
+    }
+
+    #[test]
+    fn renders_csv_rows() {
+        let columns = [
+            Column::Timestamp,
+            Column::LogStream,
+            Column::Level,
+            Column::Message,
+        ];
+        let records = Records {
+            format: RecordFormat::Csv(&columns),
+            log_group: "/ecs/app",
+            log_stream: Some("app/a"),
+        };
+        assert_eq!(
+            records.header().as_deref(),
+            Some("timestamp,log_stream,level,message")
+        );
+        assert_eq!(
+            records.render(&event("ERROR failed, \"db\" down\n")),
+            "2024-03-13T16:28:28.123Z,app/a,error,\"ERROR failed, \"\"db\"\" down\""
+        );
+    }
+
+    #[test]
+    fn ndjson_records_read_back() {
+        let records = Records {
+            format: RecordFormat::Ndjson,
+            log_group: "/ecs/app",
+            log_stream: Some("app/a"),
+        };
+        assert_eq!(records.header(), None);
+        let text = format!(
+            "{}\n\n{}\n",
+            records.render(&event("first")),
+            records.render(&event("second"))
+        );
+        let events = read_ndjson(text.as_bytes()).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].message, "first");
+        assert_eq!(events[1].timestamp, 1_710_347_308_123);
+        assert_eq!(events[1].log_stream.as_deref(), Some("app/a"));
+        assert_eq!(events[1].log_group.as_deref(), Some("/ecs/app"));
+    }
+
+    #[test]
+    fn rejects_lines_that_are_not_records() {
+        let error = read_ndjson("not json\n".as_bytes()).unwrap_err();
+        assert!(error.starts_with("line 1 "), "{error}");
+    }
+}
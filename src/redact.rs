@@ -0,0 +1,83 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use log::info;
+use regex::Regex;
+use serde::Deserialize;
+
+/// redaction rules file, e.g.
+///
+/// ```yaml
+/// rules:
+///   - name: email
+///     pattern: '[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]+'
+///   - name: card
+///     pattern: '\b(?:\d[ -]?){13,16}\b'
+///     replacement: '****'
+/// ```
+#[derive(Deserialize, Debug)]
+struct RulesFile {
+    #[serde(rename = "rules")]
+    rules: Vec<RuleDef>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RuleDef {
+    #[serde(rename = "name")]
+    name: String,
+
+    #[serde(rename = "pattern")]
+    pattern: String,
+
+    /// may reference capture groups ($1, $name), defaults to [REDACTED:<name>]
+    #[serde(rename = "replacement")]
+    replacement: Option<String>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    name: String,
+    regex: Regex,
+    replacement: String,
+}
+
+/// applies a list of named regex -> replacement rules to messages, in file order
+#[derive(Debug)]
+pub struct Redactor {
+    rules: Vec<Rule>,
+}
+
+impl Redactor {
+    pub fn from_file(path: &Path) -> Result<Redactor, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("unable to read redact rules {}: {e}", path.display()))?;
+        let file: RulesFile = serde_yaml::from_str(&contents)
+            .map_err(|e| format!("invalid redact rules {}: {e}", path.display()))?;
+        let mut rules = vec![];
+        for def in file.rules {
+            let regex = Regex::new(&def.pattern)
+                .map_err(|e| format!("invalid pattern for redact rule '{}': {e}", def.name))?;
+            let replacement = def
+                .replacement
+                .unwrap_or_else(|| format!("[REDACTED:{}]", def.name));
+            rules.push(Rule {
+                name: def.name,
+                regex,
+                replacement,
+            });
+        }
+        let names = rules.iter().map(|r| r.name.as_str()).collect::<Vec<&str>>();
+        info!("loaded {} redact rules: {}", rules.len(), names.join(", "));
+        Ok(Redactor { rules })
+    }
+
+    pub fn redact<'a>(&self, message: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(message);
+        for rule in &self.rules {
+            if let Cow::Owned(replaced) = rule.regex.replace_all(&text, rule.replacement.as_str()) {
+                text = Cow::Owned(replaced);
+            }
+        }
+        text
+    }
+}
@@ -0,0 +1,44 @@
+use aws_config::SdkConfig;
+use aws_sdk_account::types::RegionOptStatus;
+use log::info;
+
+use crate::error::AwsLogsError;
+
+/// the regions the account of config can use, those enabled by default and those opted into
+pub async fn enabled_regions(config: &SdkConfig) -> Result<Vec<String>, AwsLogsError> {
+    let client = aws_sdk_account::Client::new(config);
+    let mut regions = vec![];
+    let mut next_token: Option<String> = None;
+    loop {
+        let response = client
+            .list_regions()
+            .region_opt_status_contains(RegionOptStatus::Enabled)
+            .region_opt_status_contains(RegionOptStatus::EnabledByDefault)
+            .set_next_token(next_token)
+            .send()
+            .await
+            .map_err(|e| AwsLogsError::sdk("failed to list regions", &e))?;
+        regions.extend(
+            response
+                .regions
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|region| region.region_name),
+        );
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+    regions.sort();
+    info!("found {} enabled regions", regions.len());
+    Ok(regions)
+}
+
+/// a client for region with the credentials of config
+pub fn region_client(config: &SdkConfig, region: &str) -> aws_sdk_cloudwatchlogs::Client {
+    let conf = aws_sdk_cloudwatchlogs::config::Builder::from(config)
+        .region(aws_config::Region::new(region.to_string()))
+        .build();
+    aws_sdk_cloudwatchlogs::Client::from_conf(conf)
+}
@@ -0,0 +1,169 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use log::debug;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use crate::config::state_dir;
+use rust_aws_logs::insights;
+use rust_aws_logs::timestamps::now_millis;
+
+const HELP: &str = r"end a query with ; or an empty line to run it
+  \g <group>     switch log group
+  \s <duration>  change how far back queries look, e.g. \s 6h
+  \h             show this help
+  \q             quit (or ctrl-d)";
+
+/// `query_history` in the state dir, shared by all interactive sessions
+fn query_history_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("query_history"))
+}
+
+/// what a complete line of input asks for
+enum Input {
+    /// part of a query, the query is complete when `done` is set
+    Query {
+        text: String,
+        done: bool,
+    },
+    SwitchGroup(String),
+    SetSince(Duration),
+    Help,
+    Quit,
+    Invalid(String),
+}
+
+fn parse_input(line: &str, in_query: bool) -> Input {
+    let trimmed = line.trim();
+    if !in_query {
+        if let Some(command) = trimmed.strip_prefix('\\') {
+            let (name, arg) = command.split_once(' ').unwrap_or((command, ""));
+            let arg = arg.trim();
+            return match name {
+                "g" if !arg.is_empty() => Input::SwitchGroup(arg.to_string()),
+                "g" => Input::Invalid("usage: \\g <group>".to_string()),
+                "s" => match humantime::parse_duration(arg) {
+                    Ok(since) => Input::SetSince(since),
+                    Err(e) => Input::Invalid(format!("invalid duration '{arg}': {e}")),
+                },
+                "h" | "?" => Input::Help,
+                "q" => Input::Quit,
+                _ => Input::Invalid(format!("unknown command '\\{name}', see \\h")),
+            };
+        }
+        if trimmed == "exit" || trimmed == "quit" {
+            return Input::Quit;
+        }
+    }
+    match trimmed.strip_suffix(';') {
+        Some(text) => Input::Query {
+            text: text.to_string(),
+            done: true,
+        },
+        None => Input::Query {
+            text: trimmed.to_string(),
+            done: trimmed.is_empty(),
+        },
+    }
+}
+
+/// interactive Insights prompt: read queries (possibly spanning several lines), run them against
+/// the current log group over the last `since`, and print each result as a table
+pub async fn run(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: Option<String>,
+    since: Duration,
+) -> Result<(), String> {
+    let mut editor =
+        DefaultEditor::new().map_err(|e| format!("unable to start interactive prompt: {e}"))?;
+    let history_path = query_history_path();
+    if let Some(ref path) = history_path {
+        if let Err(e) = editor.load_history(path) {
+            debug!("no query history loaded from {}: {e}", path.display());
+        }
+    }
+    println!("{HELP}");
+    let mut log_group = log_group;
+    let mut since = since;
+    let mut query_lines: Vec<String> = vec![];
+    loop {
+        let prompt = match (&log_group, query_lines.is_empty()) {
+            (_, false) => "  ...> ".to_string(),
+            (Some(group), true) => format!("{group}> "),
+            (None, true) => "(no log group)> ".to_string(),
+        };
+        let line = match editor.readline(&prompt) {
+            Ok(line) => line,
+            // ctrl-c drops the query being typed
+            Err(ReadlineError::Interrupted) => {
+                query_lines.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(format!("unable to read input: {e}")),
+        };
+        let (text, done) = match parse_input(&line, !query_lines.is_empty()) {
+            Input::Query { text, done } => (text, done),
+            Input::SwitchGroup(group) => {
+                log_group = Some(group);
+                continue;
+            }
+            Input::SetSince(duration) => {
+                since = duration;
+                println!(
+                    "queries now look back {}",
+                    humantime::format_duration(since)
+                );
+                continue;
+            }
+            Input::Help => {
+                println!("{HELP}");
+                continue;
+            }
+            Input::Quit => break,
+            Input::Invalid(message) => {
+                println!("{message}");
+                continue;
+            }
+        };
+        if !text.is_empty() {
+            query_lines.push(text);
+        }
+        if !done || query_lines.is_empty() {
+            continue;
+        }
+        let query = query_lines.join("\n");
+        query_lines.clear();
+        if let Err(e) = editor.add_history_entry(format!("{query};")) {
+            debug!("unable to add query to history: {e}");
+        }
+        let Some(ref group) = log_group else {
+            println!("no log group selected, use \\g <group>");
+            continue;
+        };
+        let end_time = now_millis() / 1000;
+        let start_time = end_time - since.as_secs() as i64;
+        let started = Instant::now();
+        match insights::run_query(client, group, &query, start_time, end_time).await {
+            Ok(rows) => {
+                println!("{}", insights::format_table(&rows));
+                println!(
+                    "({} rows in {:.1}s)",
+                    rows.len(),
+                    started.elapsed().as_secs_f64()
+                );
+            }
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+    if let Some(ref path) = history_path {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Err(e) = editor.save_history(path) {
+            debug!("unable to save query history to {}: {e}", path.display());
+        }
+    }
+    Ok(())
+}
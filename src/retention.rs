@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use log::{debug, info};
+
+use crate::error::AwsLogsError;
+use crate::format::format_bytes;
+use crate::insights::Row;
+use crate::timestamps::now_millis;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// standard class storage price in us-east-1, close enough for ranking savings elsewhere
+const STORAGE_USD_PER_GB_MONTH: f64 = 0.03;
+
+/// the retention values CloudWatch Logs accepts
+const RETENTION_DAYS: [i32; 22] = [
+    1, 3, 5, 7, 14, 30, 60, 90, 120, 150, 180, 365, 400, 545, 731, 1096, 1827, 2192, 2557, 2922,
+    3288, 3653,
+];
+
+/// groups queried within this long are treated as actively read
+const RECENTLY_READ_DAYS: i64 = 30;
+
+/// retention for groups that are actively read / not read at all
+const READ_RETENTION_DAYS: i32 = 90;
+const UNREAD_RETENTION_DAYS: i32 = 30;
+
+/// a retention in days that CloudWatch Logs accepts, for --retention-days
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionDays(pub i32);
+
+impl FromStr for RetentionDays {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let days = s
+            .parse::<i32>()
+            .map_err(|_| format!("invalid retention '{s}', expected a number of days"))?;
+        if !RETENTION_DAYS.contains(&days) {
+            let accepted = RETENTION_DAYS.map(|d| d.to_string()).join(", ");
+            return Err(format!(
+                "CloudWatch Logs doesn't accept a retention of {days} days, only one of: {accepted}"
+            ));
+        }
+        Ok(RetentionDays(days))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Recommendation {
+    pub log_group: String,
+    /// None is never expire
+    pub current_days: Option<i32>,
+    pub recommended_days: i32,
+    pub stored_bytes: i64,
+    pub daily_bytes: f64,
+    /// epoch millis of the most recent Insights query against the group, if any is known
+    pub last_read: Option<i64>,
+    pub monthly_savings_usd: f64,
+    pub reason: String,
+}
+
+/// the most recent Insights query time per log group. CloudWatch only keeps a limited query
+/// history, so a missing group means "not queried recently" rather than "never queried"
+async fn last_query_times(
+    client: &aws_sdk_cloudwatchlogs::Client,
+) -> Result<HashMap<String, i64>, AwsLogsError> {
+    let mut last_read: HashMap<String, i64> = HashMap::new();
+    let mut next_token: Option<String> = None;
+    loop {
+        let response = client
+            .describe_queries()
+            .set_next_token(next_token)
+            .send()
+            .await
+            .map_err(|e| AwsLogsError::sdk("failed to describe insights queries", &e))?;
+        for query in response.queries.unwrap_or_default() {
+            if let (Some(group), Some(time)) = (query.log_group_name, query.create_time) {
+                let latest = last_read.entry(group).or_insert(time);
+                *latest = (*latest).max(time);
+            }
+        }
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+    debug!(
+        "found recent insights queries for {} log groups",
+        last_read.len()
+    );
+    Ok(last_read)
+}
+
+/// the smallest accepted retention that covers `days`
+fn retention_at_least(days: i32) -> i32 {
+    RETENTION_DAYS
+        .iter()
+        .copied()
+        .find(|&d| d >= days)
+        .unwrap_or(RETENTION_DAYS[RETENTION_DAYS.len() - 1])
+}
+
+/// recommend a retention for one group. daily ingestion is estimated from the bytes stored
+/// over the days the group currently covers (its age, capped at its retention)
+fn recommend(
+    log_group: String,
+    current_days: Option<i32>,
+    creation_time: i64,
+    stored_bytes: i64,
+    last_read: Option<i64>,
+    now: i64,
+) -> Recommendation {
+    let age_days = ((now - creation_time) / DAY_MS).max(1);
+    let covered_days = current_days.map_or(age_days, |d| age_days.min(d as i64));
+    let daily_bytes = stored_bytes as f64 / covered_days as f64;
+    let read_days_ago = last_read.map(|t| (now - t) / DAY_MS);
+    let (wanted, reason) = match read_days_ago {
+        Some(days) if days <= RECENTLY_READ_DAYS => {
+            (READ_RETENTION_DAYS, format!("queried {days}d ago"))
+        }
+        _ => (
+            UNREAD_RETENTION_DAYS,
+            format!("no queries in the last {RECENTLY_READ_DAYS}d"),
+        ),
+    };
+    let wanted = retention_at_least(wanted);
+    // only recommend reductions, a retention already at or below ours was chosen on purpose
+    let (recommended_days, reason) = match current_days {
+        Some(days) if days <= wanted => (days, "keep".to_string()),
+        _ => (wanted, reason),
+    };
+    let kept_bytes = (daily_bytes * recommended_days as f64).min(stored_bytes as f64);
+    let saved_gb = (stored_bytes as f64 - kept_bytes) / (1024.0 * 1024.0 * 1024.0);
+    Recommendation {
+        log_group,
+        current_days,
+        recommended_days,
+        stored_bytes,
+        daily_bytes,
+        last_read,
+        monthly_savings_usd: saved_gb * STORAGE_USD_PER_GB_MONTH,
+        reason,
+    }
+}
+
+/// recommendations for every log group (starting with prefix, if given), biggest savings first
+pub async fn recommend_retention(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    prefix: Option<&str>,
+) -> Result<Vec<Recommendation>, AwsLogsError> {
+    info!("building retention recommendations, prefix: {prefix:?}");
+    let last_read = last_query_times(client).await?;
+    let now = now_millis();
+    let mut recommendations = vec![];
+    let mut next_token: Option<String> = None;
+    loop {
+        let response = client
+            .describe_log_groups()
+            .set_log_group_name_prefix(prefix.map(|p| p.to_string()))
+            .set_next_token(next_token)
+            .send()
+            .await
+            .map_err(|e| AwsLogsError::sdk("failed to describe log groups", &e))?;
+        for group in response.log_groups.unwrap_or_default() {
+            let Some(name) = group.log_group_name else {
+                continue;
+            };
+            let read = last_read.get(&name).copied();
+            recommendations.push(recommend(
+                name,
+                group.retention_in_days,
+                group.creation_time.unwrap_or(now),
+                group.stored_bytes.unwrap_or(0),
+                read,
+                now,
+            ));
+        }
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+    recommendations.sort_by(|a, b| b.monthly_savings_usd.total_cmp(&a.monthly_savings_usd));
+    Ok(recommendations)
+}
+
+/// table rows, the first two columns (group, recommended_days) are what a bulk retention
+/// update needs
+pub fn to_rows(recommendations: &[Recommendation]) -> Vec<Row> {
+    recommendations
+        .iter()
+        .map(|r| {
+            vec![
+                ("log_group".to_string(), r.log_group.clone()),
+                (
+                    "recommended_days".to_string(),
+                    r.recommended_days.to_string(),
+                ),
+                (
+                    "current_days".to_string(),
+                    r.current_days
+                        .map_or("never expire".to_string(), |d| d.to_string()),
+                ),
+                ("stored".to_string(), format_bytes(r.stored_bytes as f64)),
+                ("est_daily".to_string(), format_bytes(r.daily_bytes)),
+                (
+                    "last_query".to_string(),
+                    r.last_read
+                        .map(crate::timestamps::format_display)
+                        .unwrap_or_default(),
+                ),
+                (
+                    "savings_usd_month".to_string(),
+                    format!("{:.2}", r.monthly_savings_usd),
+                ),
+                ("reason".to_string(), r.reason.clone()),
+            ]
+        })
+        .collect()
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::level::{detect_level, Level};
+use crate::Event;
+
+/// summaries quote this much of the first line of the repeated message
+const SAMPLE_LEN: usize = 120;
+
+fn variable_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        // uuids, hex ids and numbers, which differ between otherwise identical errors
+        Regex::new(r"[0-9a-fA-F]{8}(?:-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}|0x[0-9a-fA-F]+|\d+")
+            .unwrap()
+    })
+}
+
+/// what repeats of an error have in common: its first line with ids and numbers masked
+fn signature(message: &str) -> String {
+    let first_line = message.trim().lines().next().unwrap_or_default();
+    variable_regex().replace_all(first_line, "#").into_owned()
+}
+
+fn sample(message: &str) -> String {
+    let first_line = message.trim().lines().next().unwrap_or_default();
+    match first_line.char_indices().nth(SAMPLE_LEN) {
+        Some((end, _)) => format!("{}...", &first_line[..end]),
+        None => first_line.to_string(),
+    }
+}
+
+/// an error seen in the current window
+struct Repeated {
+    sample: String,
+    count: usize,
+    /// first time seen, summaries are ordered by it
+    first_seen: i64,
+}
+
+/// collapses repeats of the same error within a window into one summary line. the first
+/// occurrence in each window is passed through, the rest are counted and reported as
+/// `<message> ×<count> in last <window>` when the window ends. windows follow event time, so
+/// the same events roll up the same way however they were fetched
+pub struct Rollup {
+    window: Duration,
+    window_start: Option<i64>,
+    repeated: HashMap<String, Repeated>,
+}
+
+impl Rollup {
+    pub fn new(window: Duration) -> Rollup {
+        Rollup {
+            window,
+            window_start: None,
+            repeated: HashMap::new(),
+        }
+    }
+
+    /// when the current window ends (epoch millis), None before the first event of one
+    pub fn window_end(&self) -> Option<i64> {
+        self.window_start
+            .map(|start| start + self.window.as_millis() as i64)
+    }
+
+    /// the summaries of the current window, as events at `timestamp`, starting a new window
+    pub fn flush(&mut self, timestamp: i64) -> Vec<Event> {
+        self.window_start = None;
+        let mut repeated = self
+            .repeated
+            .drain()
+            .map(|(_, r)| r)
+            .filter(|r| r.count > 1)
+            .collect::<Vec<Repeated>>();
+        repeated.sort_by_key(|r| r.first_seen);
+        let window = humantime::format_duration(self.window);
+        repeated
+            .into_iter()
+            .map(|r| Event {
+                timestamp,
+                message: format!("{} ×{} in last {window}", r.sample, r.count),
+                ingestion_time: timestamp,
+                log_stream: None,
+                log_group: None,
+            })
+            .collect()
+    }
+
+    /// the events to output for event: any summaries of the window it ends, then the event
+    /// itself unless it repeats an error already seen in this window
+    pub fn push(&mut self, event: Event) -> Vec<Event> {
+        let mut out = vec![];
+        let window_ms = self.window.as_millis() as i64;
+        if let Some(start) = self.window_start {
+            if event.timestamp >= start + window_ms {
+                out.extend(self.flush(start + window_ms));
+            }
+        }
+        self.window_start.get_or_insert(event.timestamp);
+        if detect_level(&event.message).is_some_and(|level| level >= Level::Error) {
+            let repeated = self
+                .repeated
+                .entry(signature(&event.message))
+                .or_insert_with(|| Repeated {
+                    sample: sample(&event.message),
+                    count: 0,
+                    first_seen: event.timestamp,
+                });
+            repeated.count += 1;
+            if repeated.count > 1 {
+                return out;
+            }
+        }
+        out.push(event);
+        out
+    }
+}
+
+/// roll up a batch of time sorted events, summarizing the last window at its last event
+pub fn rollup_events(window: Duration, events: Vec<Event>) -> Vec<Event> {
+    let last = events.last().map(|e| e.timestamp);
+    let mut rollup = Rollup::new(window);
+    let mut out = events
+        .into_iter()
+        .flat_map(|event| rollup.push(event))
+        .collect::<Vec<Event>>();
+    if let Some(last) = last {
+        out.extend(rollup.flush(last));
+    }
+    out
+}
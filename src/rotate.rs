@@ -0,0 +1,73 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use log::info;
+
+/// writes to path until it holds max_bytes, then rolls over to `path.1`, `path.2`, ... so a long
+/// dump or follow never grows one file without bound. files only roll over before a write that
+/// follows a newline, so written a line at a time no line is split between two of them (a file
+/// goes over max_bytes by at most a line)
+pub struct RotatingWriter {
+    path: PathBuf,
+    max_bytes: u64,
+    file: BufWriter<File>,
+    /// bytes in the current file
+    written: u64,
+    /// how many times it has rolled over, the current file's suffix
+    rotations: u32,
+    at_line_start: bool,
+}
+
+impl RotatingWriter {
+    /// start writing to path, replacing it
+    pub fn create(path: &Path, max_bytes: u64) -> Result<RotatingWriter, String> {
+        let file =
+            File::create(path).map_err(|e| format!("unable to create {}: {e}", path.display()))?;
+        Ok(RotatingWriter {
+            path: path.to_path_buf(),
+            max_bytes,
+            file: BufWriter::new(file),
+            written: 0,
+            rotations: 0,
+            at_line_start: true,
+        })
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        self.rotations += 1;
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", self.rotations));
+        let next = PathBuf::from(name);
+        info!(
+            "{} is full, continuing in {}",
+            self.path.display(),
+            next.display()
+        );
+        self.file = BufWriter::new(File::create(&next)?);
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.at_line_start && self.written >= self.max_bytes {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        if n > 0 {
+            self.at_line_start = buf[n - 1] == b'\n';
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -0,0 +1,226 @@
+use std::path::Path;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::level::Level;
+use crate::timestamps::format_rfc3339;
+use crate::Event;
+
+/// webhook calls that take longer than this are abandoned
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// alert rules file, e.g.
+///
+/// ```yaml
+/// rules:
+///   - name: oom
+///     pattern: 'OutOfMemoryError'
+///     severity: error
+///     action: [highlight, bell]
+///   - name: page
+///     pattern: 'FATAL'
+///     severity: fatal
+///     action: webhook
+///     webhook: https://hooks.example.com/alert
+///   - name: deployed
+///     pattern: 'deployment complete'
+///     severity: info
+///     action: exit
+/// ```
+#[derive(Deserialize, Debug)]
+struct RulesFile {
+    #[serde(rename = "rules")]
+    rules: Vec<RuleDef>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RuleDef {
+    #[serde(rename = "name")]
+    name: String,
+
+    #[serde(rename = "pattern")]
+    pattern: String,
+
+    #[serde(rename = "severity", default = "default_severity")]
+    severity: String,
+
+    #[serde(rename = "action")]
+    action: OneOrMany,
+
+    #[serde(rename = "webhook")]
+    webhook: Option<String>,
+}
+
+fn default_severity() -> String {
+    "warn".to_string()
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(Action),
+    Many(Vec<Action>),
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// style the matching line so it stands out
+    #[serde(rename = "highlight")]
+    Highlight,
+    /// ring the terminal bell
+    #[serde(rename = "bell")]
+    Bell,
+    /// POST the event as json to the rule's webhook url
+    #[serde(rename = "webhook")]
+    Webhook,
+    /// stop after the matching event, exit status 0 for info and below, 1 for warn and above
+    #[serde(rename = "exit")]
+    Exit,
+}
+
+#[derive(Debug)]
+pub struct Rule {
+    pub name: String,
+    regex: Regex,
+    pub severity: Level,
+    actions: Vec<Action>,
+    webhook: Option<String>,
+}
+
+impl Rule {
+    fn has(&self, action: Action) -> bool {
+        self.actions.contains(&action)
+    }
+}
+
+/// body sent to webhooks
+#[derive(Serialize, Debug)]
+struct WebhookPayload<'a> {
+    #[serde(rename = "rule")]
+    rule: &'a str,
+
+    #[serde(rename = "severity")]
+    severity: String,
+
+    #[serde(rename = "logGroup")]
+    log_group: &'a str,
+
+    #[serde(rename = "logStream")]
+    log_stream: &'a str,
+
+    #[serde(rename = "timestamp")]
+    timestamp: String,
+
+    #[serde(rename = "message")]
+    message: &'a str,
+}
+
+/// what the rules matching an event leave to the caller, their webhooks are already called
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Triggered {
+    /// a bell rule matched
+    pub bell: bool,
+    /// the exit status to stop with, when an exit rule matched
+    pub exit_status: Option<i32>,
+}
+
+/// pattern -> action alert rules evaluated against every event
+#[derive(Debug)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    http: reqwest::Client,
+}
+
+impl RuleSet {
+    pub fn from_file(path: &Path) -> Result<RuleSet, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("unable to read rules {}: {e}", path.display()))?;
+        let file: RulesFile = serde_yaml::from_str(&contents)
+            .map_err(|e| format!("invalid rules {}: {e}", path.display()))?;
+        let mut rules = vec![];
+        for def in file.rules {
+            let regex = Regex::new(&def.pattern)
+                .map_err(|e| format!("invalid pattern for rule '{}': {e}", def.name))?;
+            let severity = Level::from_name(&def.severity).ok_or_else(|| {
+                format!(
+                    "unknown severity '{}' for rule '{}'",
+                    def.severity, def.name
+                )
+            })?;
+            let actions = match def.action {
+                OneOrMany::One(action) => vec![action],
+                OneOrMany::Many(actions) => actions,
+            };
+            if actions.contains(&Action::Webhook) && def.webhook.is_none() {
+                return Err(format!(
+                    "rule '{}' has a webhook action but no webhook url",
+                    def.name
+                ));
+            }
+            rules.push(Rule {
+                name: def.name,
+                regex,
+                severity,
+                actions,
+                webhook: def.webhook,
+            });
+        }
+        info!("loaded {} alert rules", rules.len());
+        Ok(RuleSet {
+            rules,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// the most severe highlight rule matching a message, if any
+    pub fn highlight_severity(&self, message: &str) -> Option<Level> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.has(Action::Highlight) && rule.regex.is_match(message))
+            .map(|rule| rule.severity)
+            .max()
+    }
+
+    /// call the webhooks of every rule matching the event, returning whether a bell rule
+    /// matched and the exit status to stop with if an exit rule did
+    pub async fn apply(&self, event: &Event, log_group: &str, log_stream: &str) -> Triggered {
+        let mut triggered = Triggered::default();
+        for rule in self
+            .rules
+            .iter()
+            .filter(|r| r.regex.is_match(&event.message))
+        {
+            debug!("rule '{}' matched event at {}", rule.name, event.timestamp);
+            triggered.bell |= rule.has(Action::Bell);
+            if let (true, Some(url)) = (rule.has(Action::Webhook), &rule.webhook) {
+                let payload = WebhookPayload {
+                    rule: &rule.name,
+                    severity: format!("{:?}", rule.severity).to_lowercase(),
+                    log_group,
+                    log_stream,
+                    timestamp: format_rfc3339(event.timestamp),
+                    message: event.message.trim(),
+                };
+                let result = self
+                    .http
+                    .post(url)
+                    .json(&payload)
+                    .timeout(WEBHOOK_TIMEOUT)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                if let Err(e) = result {
+                    warn!("webhook for rule '{}' failed: {e}", rule.name);
+                }
+            }
+            if rule.has(Action::Exit) {
+                let status = if rule.severity >= Level::Warn { 1 } else { 0 };
+                triggered.exit_status = Some(triggered.exit_status.unwrap_or(0).max(status));
+            }
+        }
+        triggered
+    }
+}
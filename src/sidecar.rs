@@ -0,0 +1,80 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use log::info;
+use serde::Serialize;
+
+use crate::Event;
+
+/// where one event's line sits in the text output
+#[derive(Serialize, Debug)]
+struct EventMeta<'a> {
+    #[serde(rename = "timestamp")]
+    timestamp: i64,
+
+    #[serde(rename = "ingestionTime")]
+    ingestion_time: i64,
+
+    #[serde(rename = "logStream")]
+    log_stream: &'a str,
+
+    /// byte offset of the line in the text file
+    #[serde(rename = "offset")]
+    offset: u64,
+
+    /// length of the line in bytes, without the newline
+    #[serde(rename = "length")]
+    length: u64,
+}
+
+/// `out.log` -> `out.meta.jsonl`, other names get `.meta.jsonl` appended
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    match path.extension() {
+        Some(ext) if ext == "log" => path.with_extension("meta.jsonl"),
+        _ => {
+            let mut name = path.as_os_str().to_owned();
+            name.push(".meta.jsonl");
+            PathBuf::from(name)
+        }
+    }
+}
+
+/// write one json line per event to the sidecar of a text file. lines are the rendered lines
+/// of events as written (joined by newlines) starting at byte start_offset of the text file.
+/// the sidecar is appended to, unless start_offset is 0 and the text file was just (re)created.
+/// log_stream is recorded for events that aren't labeled with their own stream
+pub fn write_sidecar(
+    text_path: &Path,
+    log_stream: &str,
+    events: &[Event],
+    lines: &[String],
+    start_offset: u64,
+) -> Result<PathBuf, String> {
+    let path = sidecar_path(text_path);
+    info!("writing event metadata to: {}", path.display());
+    let mut offset = start_offset;
+    let mut contents = String::new();
+    for (event, line) in events.iter().zip(lines) {
+        let meta = EventMeta {
+            timestamp: event.timestamp,
+            ingestion_time: event.ingestion_time,
+            log_stream: event.log_stream.as_deref().unwrap_or(log_stream),
+            offset,
+            length: line.len() as u64,
+        };
+        let json = serde_json::to_string(&meta)
+            .map_err(|e| format!("unable to serialize event metadata: {e}"))?;
+        contents.push_str(&json);
+        contents.push('\n');
+        offset += line.len() as u64 + 1;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(start_offset > 0)
+        .truncate(start_offset == 0)
+        .open(&path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|e| format!("unable to write {}: {e}", path.display()))?;
+    Ok(path)
+}
@@ -0,0 +1,205 @@
+use std::str::FromStr;
+
+use aws_sdk_sqs::types::{MessageAttributeValue, SendMessageBatchRequestEntry};
+use log::{debug, info, warn};
+
+use crate::error::AwsLogsError;
+use crate::Event;
+
+/// SendMessageBatch takes at most 10 messages and 256KiB in total, a single message can't be
+/// bigger than that either
+const SQS_BATCH_MESSAGES: usize = 10;
+const SQS_BATCH_BYTES: usize = 256 * 1024;
+
+/// where --sink sends events instead of printing them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SinkKind {
+    Sqs,
+}
+
+impl FromStr for SinkKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sqs" => Ok(SinkKind::Sqs),
+            _ => Err(format!("unknown sink '{s}', expected one of: sqs")),
+        }
+    }
+}
+
+fn string_attribute(data_type: &str, value: String) -> Result<MessageAttributeValue, AwsLogsError> {
+    MessageAttributeValue::builder()
+        .data_type(data_type)
+        .string_value(value)
+        .build()
+        .map_err(|e| AwsLogsError::sdk("unable to build message attribute", &e))
+}
+
+/// the (name, data type, value) message attributes of an event
+fn attributes(
+    event: &Event,
+    log_group: &str,
+    log_stream: &str,
+) -> [(&'static str, &'static str, String); 3] {
+    [
+        (
+            "logGroup",
+            "String",
+            event_group(event, log_group).to_string(),
+        ),
+        (
+            "logStream",
+            "String",
+            event_stream(event, log_stream).to_string(),
+        ),
+        ("timestamp", "Number", event.timestamp.to_string()),
+    ]
+}
+
+/// bytes an entry counts for against the size limits: the body, and the name, data type and
+/// value of each attribute
+fn entry_size(event: &Event, log_group: &str, log_stream: &str) -> usize {
+    let attributes = attributes(event, log_group, log_stream)
+        .iter()
+        .map(|(name, data_type, value)| name.len() + data_type.len() + value.len())
+        .sum::<usize>();
+    event.message.len() + attributes
+}
+
+/// the stream an event is labeled with, or log_stream for unlabeled events
+fn event_stream<'a>(event: &'a Event, log_stream: &'a str) -> &'a str {
+    event.log_stream.as_deref().unwrap_or(log_stream)
+}
+
+/// the group an event is labeled with, or log_group for unlabeled events
+fn event_group<'a>(event: &'a Event, log_group: &'a str) -> &'a str {
+    event.log_group.as_deref().unwrap_or(log_group)
+}
+
+/// the events split into SendMessageBatch calls, within their message count and size limits.
+/// events with an empty message are left out, SQS doesn't take an empty message body, and an
+/// event too big for a message is an error
+fn batches<'a>(
+    events: &'a [Event],
+    log_group: &str,
+    log_stream: &str,
+) -> Result<Vec<Vec<&'a Event>>, AwsLogsError> {
+    let mut batches: Vec<Vec<&Event>> = vec![];
+    let mut batch = vec![];
+    let mut batch_bytes = 0;
+    for event in events.iter().filter(|e| !e.message.is_empty()) {
+        let size = entry_size(event, log_group, log_stream);
+        if size > SQS_BATCH_BYTES {
+            return Err(AwsLogsError::Sdk(format!(
+                "the event at {} is {size} bytes with its attributes, more than the {SQS_BATCH_BYTES} an SQS message can hold",
+                event.timestamp
+            )));
+        }
+        if !batch.is_empty()
+            && (batch.len() == SQS_BATCH_MESSAGES || batch_bytes + size > SQS_BATCH_BYTES)
+        {
+            batches.push(std::mem::take(&mut batch));
+            batch_bytes = 0;
+        }
+        batch.push(event);
+        batch_bytes += size;
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+    Ok(batches)
+}
+
+/// send each event as an SQS message with its group, stream and timestamp as message
+/// attributes, returning how many were sent. events with an empty message are skipped,
+/// messages SQS rejects are an error
+pub async fn send_to_sqs(
+    client: &aws_sdk_sqs::Client,
+    queue_url: &str,
+    log_group: &str,
+    log_stream: &str,
+    events: &[Event],
+) -> Result<usize, AwsLogsError> {
+    info!("sending {} events to {queue_url}", events.len());
+    let batches = batches(events, log_group, log_stream)?;
+    let empty = events.iter().filter(|e| e.message.is_empty()).count();
+    if empty > 0 {
+        warn!(
+            "skipping {empty} events with an empty message, SQS doesn't take an empty message body"
+        );
+    }
+
+    let mut sent = 0;
+    for batch in batches {
+        let entries = batch
+            .iter()
+            .enumerate()
+            .map(|(i, event)| {
+                let mut entry = SendMessageBatchRequestEntry::builder()
+                    .id(i.to_string())
+                    .message_body(event.message.clone());
+                for (name, data_type, value) in attributes(event, log_group, log_stream) {
+                    entry = entry.message_attributes(name, string_attribute(data_type, value)?);
+                }
+                entry
+                    .build()
+                    .map_err(|e| AwsLogsError::sdk("unable to build SQS message", &e))
+            })
+            .collect::<Result<Vec<SendMessageBatchRequestEntry>, AwsLogsError>>()?;
+        let response = client
+            .send_message_batch()
+            .queue_url(queue_url)
+            .set_entries(Some(entries))
+            .send()
+            .await
+            .map_err(|e| {
+                AwsLogsError::sdk(&format!("failed to send messages to {queue_url}"), &e)
+            })?;
+        if let Some(failure) = response.failed().first() {
+            return Err(AwsLogsError::Sdk(format!(
+                "{} of {} messages were rejected by {queue_url} after {sent} were sent, first: {} {}",
+                response.failed().len(),
+                batch.len(),
+                failure.code(),
+                failure.message().unwrap_or_default()
+            )));
+        }
+        sent += batch.len();
+        debug!("sent batch of {} messages", batch.len());
+    }
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_too_big_for_a_message_is_an_error() {
+        let events = [Event::at(1, "ok"), Event::at(2, &"x".repeat(SQS_BATCH_BYTES))];
+        assert!(batches(&events, "/ecs/app", "app/1").is_err());
+    }
+
+    #[test]
+    fn events_with_an_empty_message_are_left_out() {
+        let events = [Event::at(1, "a"), Event::at(2, ""), Event::at(3, "b")];
+        let batches = batches(&events, "/ecs/app", "app/1").unwrap();
+        let timestamps = batches
+            .iter()
+            .map(|batch| batch.iter().map(|e| e.timestamp).collect::<Vec<i64>>())
+            .collect::<Vec<Vec<i64>>>();
+        assert_eq!(timestamps, [[1, 3]]);
+    }
+
+    #[test]
+    fn batches_hold_at_most_ten_messages() {
+        let events = (0..25).map(|i| Event::at(i, "a")).collect::<Vec<Event>>();
+        let sizes = batches(&events, "/ecs/app", "app/1")
+            .unwrap()
+            .iter()
+            .map(Vec::len)
+            .collect::<Vec<usize>>();
+        assert_eq!(sizes, [10, 10, 5]);
+    }
+}
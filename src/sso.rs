@@ -0,0 +1,59 @@
+use std::io::IsTerminal;
+use std::process::Command;
+
+use aws_config::SdkConfig;
+use aws_credential_types::provider::ProvideCredentials;
+use log::{debug, info};
+use rust_aws_logs::error::is_session_error;
+
+/// the command that starts a new SSO session for profile (`PROFILE[@REGION]`)
+fn login_command(profile: Option<&str>) -> String {
+    match profile.map(|p| p.split_once('@').map_or(p, |(name, _)| name)) {
+        Some(name) => format!("aws sso login --profile {name}"),
+        None => "aws sso login".to_string(),
+    }
+}
+
+/// run `aws sso login`, which walks through the device authorization flow in the browser
+fn login(profile: Option<&str>) -> Result<(), String> {
+    let command = login_command(profile);
+    info!("running: {command}");
+    let mut words = command.split_whitespace();
+    let status = Command::new(words.next().unwrap_or("aws"))
+        .args(words)
+        .status()
+        .map_err(|e| format!("unable to run `{command}`: {e}"))?;
+    if !status.success() {
+        return Err(format!("`{command}` exited with {status}"));
+    }
+    Ok(())
+}
+
+/// resolve config's credentials up front, so an expired SSO session is reported before any
+/// request fails deep inside a fetch. on a terminal the session is renewed with `aws sso login`,
+/// returning true when the config has to be loaded again to pick up the new session
+pub async fn check_session(config: &SdkConfig, profile: Option<&str>) -> Result<bool, String> {
+    let Some(provider) = config.credentials_provider() else {
+        return Ok(false);
+    };
+    let error = match provider.provide_credentials().await {
+        Ok(_) => return Ok(false),
+        Err(e) if is_session_error(&e) => e,
+        Err(e) => {
+            // other credential problems still surface, with their details, from the first call
+            debug!("unable to resolve credentials up front: {e}");
+            return Ok(false);
+        }
+    };
+    debug!("SSO session error: {error:?}");
+    let interactive = std::io::stdin().is_terminal() && std::io::stderr().is_terminal();
+    if !interactive {
+        return Err(format!(
+            "the AWS SSO session has expired or was never started, run `{}` and try again",
+            login_command(profile)
+        ));
+    }
+    eprintln!("the AWS SSO session has expired, starting a new one");
+    login(profile)?;
+    Ok(true)
+}
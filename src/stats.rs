@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::error::AwsLogsError;
+use crate::format::format_bytes;
+use crate::insights::{self, Row, StreamSize};
+use crate::store::StoredStream;
+use crate::streams;
+use crate::timestamps::format_display;
+
+/// what `alog stats` reports about a log group
+#[derive(Serialize, Debug)]
+pub struct GroupStats {
+    #[serde(rename = "logGroup")]
+    pub log_group: String,
+
+    /// for the whole group, CloudWatch no longer reports stored bytes per stream
+    #[serde(rename = "storedBytes")]
+    pub stored_bytes: Option<i64>,
+
+    #[serde(rename = "streamCount")]
+    pub stream_count: usize,
+
+    #[serde(rename = "firstEventTime")]
+    pub first_event_time: Option<String>,
+
+    #[serde(rename = "lastEventTime")]
+    pub last_event_time: Option<String>,
+
+    /// what the sampled counts cover, e.g. `1h`, with --with-sizes
+    #[serde(rename = "sampledWindow", skip_serializing_if = "Option::is_none")]
+    pub sampled_window: Option<String>,
+
+    #[serde(rename = "streams")]
+    pub streams: Vec<StreamStats>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StreamStats {
+    #[serde(rename = "name")]
+    pub name: String,
+
+    #[serde(rename = "firstEventTime")]
+    pub first_event_time: Option<String>,
+
+    #[serde(rename = "lastEventTime")]
+    pub last_event_time: Option<String>,
+
+    /// events in the sampled window with --with-sizes, or stored with --offline
+    #[serde(rename = "events", skip_serializing_if = "Option::is_none")]
+    pub events: Option<i64>,
+
+    /// message bytes in the sampled window with --with-sizes, or stored with --offline
+    #[serde(rename = "bytes", skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<i64>,
+}
+
+/// stored bytes of a log group, from DescribeLogGroups
+async fn group_stored_bytes(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+) -> Result<Option<i64>, AwsLogsError> {
+    let response = client
+        .describe_log_groups()
+        .log_group_name_prefix(log_group)
+        .send()
+        .await
+        .map_err(|e| AwsLogsError::sdk(&format!("failed to describe log group {log_group}"), &e))?;
+    response
+        .log_groups
+        .unwrap_or_default()
+        .into_iter()
+        .find(|g| g.log_group_name.as_deref() == Some(log_group))
+        .map(|g| g.stored_bytes)
+        .ok_or_else(|| AwsLogsError::InvalidArgument(format!("no log group named {log_group}")))
+}
+
+/// stream counts and first / last event times from DescribeLogStreams. with sample (a window
+/// of epoch seconds and how to describe it), event counts and sizes per stream over that window
+/// come from one Insights query
+pub async fn group_stats(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    sample: Option<(i64, i64, String)>,
+) -> Result<GroupStats, AwsLogsError> {
+    let stored_bytes = group_stored_bytes(client, log_group).await?;
+    let streams =
+        streams::filtered_log_streams(client, log_group, &streams::StreamFilter::default()).await?;
+    let sizes: HashMap<String, StreamSize> = match sample {
+        Some((start_time, end_time, _)) => {
+            insights::stream_sizes(client, log_group, start_time, end_time).await?
+        }
+        None => HashMap::new(),
+    };
+    let sampled = sample.is_some();
+    let first_event_time = streams.iter().filter_map(|s| s.first_event_time).min();
+    let last_event_time = streams.iter().filter_map(|s| s.last_event_time).max();
+    Ok(GroupStats {
+        log_group: log_group.to_string(),
+        stored_bytes,
+        stream_count: streams.len(),
+        first_event_time: first_event_time.map(format_display),
+        last_event_time: last_event_time.map(format_display),
+        sampled_window: sample.map(|(_, _, window)| window),
+        streams: streams
+            .iter()
+            .map(|stream| {
+                let size = sampled.then(|| sizes.get(&stream.name).copied().unwrap_or_default());
+                StreamStats {
+                    name: stream.name.clone(),
+                    first_event_time: stream.first_event_time.map(format_display),
+                    last_event_time: stream.last_event_time.map(format_display),
+                    events: size.map(|s| s.events),
+                    bytes: size.map(|s| s.bytes),
+                }
+            })
+            .collect(),
+    })
+}
+
+/// group_stats from what the event store holds of a log group, its streams' events and bytes
+/// those stored
+pub fn stored_stats(log_group: &str, streams: &[StoredStream]) -> GroupStats {
+    GroupStats {
+        log_group: log_group.to_string(),
+        stored_bytes: Some(streams.iter().map(|s| s.bytes).sum()),
+        stream_count: streams.len(),
+        first_event_time: streams
+            .iter()
+            .map(|s| s.first_event_time)
+            .min()
+            .map(format_display),
+        last_event_time: streams
+            .iter()
+            .map(|s| s.last_event_time)
+            .max()
+            .map(format_display),
+        sampled_window: None,
+        streams: streams
+            .iter()
+            .map(|stream| StreamStats {
+                name: stream.log_stream.clone(),
+                first_event_time: Some(format_display(stream.first_event_time)),
+                last_event_time: Some(format_display(stream.last_event_time)),
+                events: Some(stream.events),
+                bytes: Some(stream.bytes),
+            })
+            .collect(),
+    }
+}
+
+impl GroupStats {
+    /// a summary of the group followed by a table of its streams
+    pub fn report(&self) -> String {
+        let or_dash = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+        let mut lines = vec![
+            format!("log group:     {}", self.log_group),
+            format!("streams:       {}", self.stream_count),
+            format!(
+                "stored bytes:  {}",
+                self.stored_bytes
+                    .map_or("-".to_string(), |b| format_bytes(b as f64))
+            ),
+            format!("first event:   {}", or_dash(&self.first_event_time)),
+            format!("last event:    {}", or_dash(&self.last_event_time)),
+        ];
+        if let Some(ref window) = self.sampled_window {
+            let events: i64 = self.streams.iter().filter_map(|s| s.events).sum();
+            let bytes: i64 = self.streams.iter().filter_map(|s| s.bytes).sum();
+            lines.push(format!(
+                "last {window}:  {events} events, {}",
+                format_bytes(bytes as f64)
+            ));
+        }
+        let rows = self
+            .streams
+            .iter()
+            .map(|s| {
+                let mut row: Row = vec![
+                    ("stream".to_string(), s.name.clone()),
+                    ("first event".to_string(), or_dash(&s.first_event_time)),
+                    ("last event".to_string(), or_dash(&s.last_event_time)),
+                ];
+                if let (Some(events), Some(bytes)) = (s.events, s.bytes) {
+                    row.push(("events".to_string(), events.to_string()));
+                    row.push(("bytes".to_string(), format_bytes(bytes as f64)));
+                }
+                row
+            })
+            .collect::<Vec<Row>>();
+        if !rows.is_empty() {
+            lines.push(String::new());
+            lines.push(insights::format_table(&rows));
+        }
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,114 @@
+use log::debug;
+
+use crate::Event;
+
+/// CloudWatch's event size limit is 256KB including 26 bytes of per-event overhead
+const MAX_MESSAGE_BYTES: usize = 256 * 1024 - 26;
+
+/// producers split a little under the limit to leave room for their own framing, so a
+/// fragment is any message within this many bytes of it
+const SPLIT_SLACK_BYTES: usize = 1024;
+
+fn is_full_fragment(message: &str) -> bool {
+    message.len() >= MAX_MESSAGE_BYTES - SPLIT_SLACK_BYTES
+}
+
+/// a JSON payload that already parses on its own isn't waiting for more fragments
+fn is_complete_json(message: &str) -> bool {
+    let trimmed = message.trim_start();
+    (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde::de::IgnoredAny>(message).is_ok()
+}
+
+/// rejoin messages a producer split across consecutive events at the size limit. an event is
+/// appended to the previous one when both share a timestamp and the previous one's last
+/// fragment filled the event
+pub fn stitch_split_events(events: Vec<Event>) -> Vec<Event> {
+    let mut stitched: Vec<Event> = Vec::with_capacity(events.len());
+    // whether the last fragment joined into the last stitched event filled its event
+    let mut last_fragment_full = false;
+    let mut joined = 0;
+    for event in events {
+        let continues = match stitched.last() {
+            Some(previous) => {
+                previous.timestamp == event.timestamp
+                    && last_fragment_full
+                    && !is_complete_json(&previous.message)
+            }
+            None => false,
+        };
+        last_fragment_full = is_full_fragment(&event.message);
+        match stitched.last_mut() {
+            Some(previous) if continues => {
+                previous.message.push_str(&event.message);
+                joined += 1;
+            }
+            _ => stitched.push(event),
+        }
+    }
+    debug!("stitched {joined} split event fragments");
+    stitched
+}
+
+/// whether any event looks like the first part of a split message, to warn when
+/// --stitch-split-events wasn't given
+pub fn has_split_events(events: &[Event]) -> bool {
+    events
+        .windows(2)
+        .any(|pair| pair[0].timestamp == pair[1].timestamp && is_full_fragment(&pair[0].message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_fragment(c: char) -> String {
+        c.to_string().repeat(MAX_MESSAGE_BYTES - 100)
+    }
+
+    #[test]
+    fn joins_fragments_of_a_split_message() {
+        let first = format!("{{\"data\":\"{}", full_fragment('a'));
+        let events = vec![
+            Event::at(1, &first),
+            Event::at(1, "b\"}"),
+            Event::at(2, "next"),
+        ];
+        let stitched = stitch_split_events(events);
+        assert_eq!(stitched.len(), 2);
+        assert_eq!(stitched[0].message, format!("{first}b\"}}"));
+        assert_eq!(stitched[1].message, "next");
+    }
+
+    #[test]
+    fn keeps_events_that_were_not_split() {
+        let events = vec![
+            // a short message doesn't continue in the next event
+            Event::at(1, "short"),
+            Event::at(1, "also short"),
+            // a full one at another timestamp isn't continued either
+            Event::at(2, &full_fragment('a')),
+            Event::at(3, "later"),
+        ];
+        assert_eq!(stitch_split_events(events).len(), 4);
+    }
+
+    #[test]
+    fn keeps_full_json_that_parses_on_its_own() {
+        let payload = format!("{{\"data\":\"{}\"}}", full_fragment('a'));
+        let events = vec![Event::at(1, &payload), Event::at(1, "{}")];
+        assert_eq!(stitch_split_events(events).len(), 2);
+    }
+
+    #[test]
+    fn finds_split_events() {
+        assert!(has_split_events(&[
+            Event::at(1, &full_fragment('a')),
+            Event::at(1, "rest"),
+        ]));
+        assert!(!has_split_events(&[
+            Event::at(1, &full_fragment('a')),
+            Event::at(2, "rest"),
+        ]));
+    }
+}
@@ -0,0 +1,680 @@
+use std::path::Path;
+
+use futures::TryStreamExt;
+use log::{debug, info};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AwsLogsError;
+use crate::streams::StreamInfo;
+use crate::{stream_log_pages, Event, Pagination};
+
+/// bumped when the tables change, a cache of an older layout is dropped and fetched again
+const SCHEMA_VERSION: i32 = 2;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    account TEXT NOT NULL,
+    region TEXT NOT NULL,
+    profile TEXT NOT NULL,
+    log_group TEXT NOT NULL,
+    log_stream TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    ingestion_time INTEGER NOT NULL,
+    message TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS events_by_stream ON events (account, region, profile, log_group, log_stream, timestamp);
+CREATE TABLE IF NOT EXISTS streams (
+    account TEXT NOT NULL,
+    region TEXT NOT NULL,
+    profile TEXT NOT NULL,
+    log_group TEXT NOT NULL,
+    log_stream TEXT NOT NULL,
+    covered_from INTEGER,
+    forward_token TEXT NOT NULL,
+    PRIMARY KEY (account, region, profile, log_group, log_stream)
+);
+CREATE VIRTUAL TABLE IF NOT EXISTS events_fts USING fts5(message, content='events', content_rowid='rowid');
+CREATE TRIGGER IF NOT EXISTS events_fts_insert AFTER INSERT ON events BEGIN
+    INSERT INTO events_fts (rowid, message) VALUES (new.rowid, new.message);
+END;
+CREATE TRIGGER IF NOT EXISTS events_fts_delete AFTER DELETE ON events BEGIN
+    INSERT INTO events_fts (events_fts, rowid, message) VALUES ('delete', old.rowid, old.message);
+END;
+";
+
+/// the tables of an older layout
+const DROP_SCHEMA: &str = "
+DROP TRIGGER IF EXISTS events_fts_insert;
+DROP TRIGGER IF EXISTS events_fts_delete;
+DROP TABLE IF EXISTS events_fts;
+DROP TABLE IF EXISTS events;
+DROP TABLE IF EXISTS streams;
+";
+
+/// how much of a stream the store holds without gaps: every event from covered_from (the
+/// head of the stream when None) up to the page forward_token starts
+#[derive(Debug, Clone)]
+pub struct Coverage {
+    pub covered_from: Option<i64>,
+    pub forward_token: String,
+}
+
+impl Coverage {
+    /// whether everything at or after start_time is in the store, up to forward_token
+    fn covers(&self, start_time: Option<i64>) -> bool {
+        match (self.covered_from, start_time) {
+            (None, _) => true,
+            (Some(from), Some(start)) => start >= from,
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// where cached events were fetched from: a stream of the same name in another account or
+/// region, or seen through another profile, is cached apart
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Source {
+    pub account: String,
+    pub region: String,
+    pub profile: String,
+}
+
+impl Source {
+    /// events loaded with `alog import`, exports don't say which account or region they're of
+    pub fn imported() -> Source {
+        Source {
+            profile: "import".to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// a stored event matching a search, with where it came from
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub log_group: String,
+    pub log_stream: String,
+    pub event: Event,
+}
+
+/// what the store holds of a stream from one source
+#[derive(Debug, Clone)]
+pub struct StoredStream {
+    pub source: Source,
+    pub log_stream: String,
+    pub events: i64,
+    /// of the messages
+    pub bytes: i64,
+    pub first_event_time: i64,
+    pub last_event_time: i64,
+}
+
+impl StoredStream {
+    /// the stream as a listing would show it, the store doesn't know when it was created
+    pub fn info(&self) -> StreamInfo {
+        StreamInfo {
+            name: self.log_stream.clone(),
+            creation_time: self.first_event_time,
+            first_event_time: Some(self.first_event_time),
+            last_event_time: Some(self.last_event_time),
+        }
+    }
+}
+
+/// every event fetched with --cache (or loaded with `alog import`), in a local SQLite database keyed by source, group and
+/// stream, with a full-text index of the messages for `alog search`. events are only stored
+/// once because each page is stored with the token after it, so a page is never fetched twice,
+/// and a range fetched again replaces what was stored for it. events that happen to be
+/// identical (same times and message) are all kept
+pub struct EventStore {
+    conn: Connection,
+}
+
+impl EventStore {
+    pub fn open(path: &Path) -> Result<EventStore, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("unable to create {}: {e}", dir.display()))?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| format!("unable to open {}: {e}", path.display()))?;
+        let setup = || -> rusqlite::Result<()> {
+            let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+            if version < SCHEMA_VERSION {
+                conn.execute_batch(DROP_SCHEMA)?;
+            }
+            conn.execute_batch(SCHEMA)?;
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+        };
+        setup().map_err(|e| format!("unable to set up {}: {e}", path.display()))?;
+        Ok(EventStore { conn })
+    }
+
+    pub fn coverage(
+        &self,
+        source: &Source,
+        log_group: &str,
+        log_stream: &str,
+    ) -> Result<Option<Coverage>, String> {
+        self.conn
+            .query_row(
+                "SELECT covered_from, forward_token FROM streams
+                 WHERE account = ?1 AND region = ?2 AND profile = ?3 AND log_group = ?4 AND log_stream = ?5",
+                params![source.account, source.region, source.profile, log_group, log_stream],
+                |row| {
+                    Ok(Coverage {
+                        covered_from: row.get(0)?,
+                        forward_token: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("unable to read the event cache: {e}"))
+    }
+
+    /// forget the coverage of a stream and the events stored for it in [start_time, end_time),
+    /// before fetching that range again
+    pub fn clear_range(
+        &self,
+        source: &Source,
+        log_group: &str,
+        log_stream: &str,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+    ) -> Result<(), String> {
+        let clear = || -> rusqlite::Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute(
+                "DELETE FROM streams
+                 WHERE account = ?1 AND region = ?2 AND profile = ?3 AND log_group = ?4 AND log_stream = ?5",
+                params![source.account, source.region, source.profile, log_group, log_stream],
+            )?;
+            tx.execute(
+                "DELETE FROM events
+                 WHERE account = ?1 AND region = ?2 AND profile = ?3 AND log_group = ?4 AND log_stream = ?5
+                   AND timestamp >= ?6 AND timestamp < ?7",
+                params![
+                    source.account,
+                    source.region,
+                    source.profile,
+                    log_group,
+                    log_stream,
+                    start_time.unwrap_or(i64::MIN),
+                    end_time.unwrap_or(i64::MAX)
+                ],
+            )?;
+            tx.commit()
+        };
+        clear().map_err(|e| format!("unable to write the event cache: {e}"))
+    }
+
+    /// store a page of events of a stream, and with coverage, that the stream is now stored up
+    /// to its token, both or neither
+    pub fn insert_page(
+        &self,
+        source: &Source,
+        log_group: &str,
+        log_stream: &str,
+        events: &[Event],
+        coverage: Option<&Coverage>,
+    ) -> Result<(), String> {
+        let write = || -> rusqlite::Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            {
+                let mut insert = tx.prepare_cached(
+                    "INSERT INTO events (account, region, profile, log_group, log_stream, timestamp, ingestion_time, message)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                )?;
+                for event in events {
+                    insert.execute(params![
+                        source.account,
+                        source.region,
+                        source.profile,
+                        log_group,
+                        log_stream,
+                        event.timestamp,
+                        event.ingestion_time,
+                        event.message
+                    ])?;
+                }
+            }
+            if let Some(coverage) = coverage {
+                tx.execute(
+                    "INSERT OR REPLACE INTO streams (account, region, profile, log_group, log_stream, covered_from, forward_token)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        source.account,
+                        source.region,
+                        source.profile,
+                        log_group,
+                        log_stream,
+                        coverage.covered_from,
+                        coverage.forward_token
+                    ],
+                )?;
+            }
+            tx.commit()
+        };
+        write().map_err(|e| format!("unable to write the event cache: {e}"))
+    }
+
+    /// the stored events of a stream in [start_time, end_time), sorted by timestamp
+    pub fn events(
+        &self,
+        source: &Source,
+        log_group: &str,
+        log_stream: &str,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+    ) -> Result<Vec<Event>, String> {
+        let read = || -> rusqlite::Result<Vec<Event>> {
+            let mut select = self.conn.prepare_cached(
+                "SELECT timestamp, ingestion_time, message FROM events
+                 WHERE account = ?1 AND region = ?2 AND profile = ?3 AND log_group = ?4 AND log_stream = ?5
+                   AND timestamp >= ?6 AND timestamp < ?7
+                 ORDER BY timestamp, rowid",
+            )?;
+            let rows = select.query_map(
+                params![
+                    source.account,
+                    source.region,
+                    source.profile,
+                    log_group,
+                    log_stream,
+                    start_time.unwrap_or(i64::MIN),
+                    end_time.unwrap_or(i64::MAX)
+                ],
+                |row| {
+                    Ok(Event {
+                        timestamp: row.get(0)?,
+                        ingestion_time: row.get(1)?,
+                        message: row.get(2)?,
+                        log_stream: None,
+                        log_group: None,
+                    })
+                },
+            )?;
+            rows.collect()
+        };
+        read().map_err(|e| format!("unable to read the event cache: {e}"))
+    }
+
+    /// the streams of log_group the store holds events of, sorted by name, the sources holding
+    /// most of a stream first
+    pub fn streams(&self, log_group: &str) -> Result<Vec<StoredStream>, String> {
+        let read = || -> rusqlite::Result<Vec<StoredStream>> {
+            let mut select = self.conn.prepare_cached(
+                "SELECT account, region, profile, log_stream, COUNT(*), SUM(LENGTH(CAST(message AS BLOB))),
+                        MIN(timestamp), MAX(timestamp)
+                 FROM events WHERE log_group = ?1
+                 GROUP BY account, region, profile, log_stream
+                 ORDER BY log_stream, COUNT(*) DESC",
+            )?;
+            let rows = select.query_map(params![log_group], |row| {
+                Ok(StoredStream {
+                    source: Source {
+                        account: row.get(0)?,
+                        region: row.get(1)?,
+                        profile: row.get(2)?,
+                    },
+                    log_stream: row.get(3)?,
+                    events: row.get(4)?,
+                    bytes: row.get(5)?,
+                    first_event_time: row.get(6)?,
+                    last_event_time: row.get(7)?,
+                })
+            })?;
+            rows.collect()
+        };
+        read().map_err(|e| format!("unable to read the event cache: {e}"))
+    }
+
+    /// stored events whose message matches query, an FTS5 query like `timeout`, `"connection
+    /// reset"`, `pay* AND NOT healthcheck`. only events of log_group / log_stream when given,
+    /// in [start_time, end_time). with limit, the last limit matches. sorted by timestamp
+    pub fn search(
+        &self,
+        query: &str,
+        log_group: Option<&str>,
+        log_stream: Option<&str>,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        limit: Option<u32>,
+    ) -> Result<Vec<SearchHit>, String> {
+        let read = || -> rusqlite::Result<Vec<SearchHit>> {
+            let mut select = self.conn.prepare_cached(
+                "SELECT e.log_group, e.log_stream, e.timestamp, e.ingestion_time, e.message
+                 FROM events_fts JOIN events e ON e.rowid = events_fts.rowid
+                 WHERE events_fts MATCH ?1
+                   AND (?2 IS NULL OR e.log_group = ?2)
+                   AND (?3 IS NULL OR e.log_stream = ?3)
+                   AND e.timestamp >= ?4 AND e.timestamp < ?5
+                 ORDER BY e.timestamp DESC, e.rowid DESC
+                 LIMIT ?6",
+            )?;
+            let rows = select.query_map(
+                params![
+                    query,
+                    log_group,
+                    log_stream,
+                    start_time.unwrap_or(i64::MIN),
+                    end_time.unwrap_or(i64::MAX),
+                    limit.map_or(-1, i64::from)
+                ],
+                |row| {
+                    Ok(SearchHit {
+                        log_group: row.get(0)?,
+                        log_stream: row.get(1)?,
+                        event: Event {
+                            timestamp: row.get(2)?,
+                            ingestion_time: row.get(3)?,
+                            message: row.get(4)?,
+                            log_stream: None,
+                            log_group: None,
+                        },
+                    })
+                },
+            )?;
+            let mut hits = rows.collect::<rusqlite::Result<Vec<SearchHit>>>()?;
+            hits.reverse();
+            Ok(hits)
+        };
+        read().map_err(|e| format!("unable to search the event cache: {e}"))
+    }
+}
+
+/// stores the objects of an export task as export::download_export hands them over, under
+/// Source::imported. what the store held for the time each object covers is replaced, so an
+/// export imported again isn't stored twice
+pub struct Import<'a> {
+    store: &'a EventStore,
+    log_group: String,
+    /// the stream of the last object and where its events were replaced up to
+    replaced: Option<(String, i64)>,
+}
+
+impl<'a> Import<'a> {
+    pub fn new(store: &'a EventStore, log_group: &str) -> Import<'a> {
+        Import {
+            store,
+            log_group: log_group.to_string(),
+            replaced: None,
+        }
+    }
+
+    /// store the events of an object, labeled with their stream and in time order
+    pub fn add(&mut self, events: &[Event]) -> Result<(), AwsLogsError> {
+        let (Some(first), Some(last)) = (events.first(), events.last()) else {
+            return Ok(());
+        };
+        let log_stream = first.log_stream.as_deref().unwrap_or_default();
+        // the next object of a stream can start at the timestamp the one before ended at,
+        // those events were just stored
+        let from = match self.replaced {
+            Some((ref stream, until)) if stream == log_stream => first.timestamp.max(until),
+            _ => first.timestamp,
+        };
+        let until = (last.timestamp + 1).max(from);
+        let source = Source::imported();
+        self.store
+            .clear_range(
+                &source,
+                &self.log_group,
+                log_stream,
+                Some(from),
+                Some(until),
+            )
+            .and_then(|()| {
+                self.store
+                    .insert_page(&source, &self.log_group, log_stream, events, None)
+            })
+            .map_err(AwsLogsError::Cache)?;
+        self.replaced = Some((log_stream.to_string(), until));
+        Ok(())
+    }
+}
+
+/// events of a stream in [start_time, end_time) like fetch_entire_log, kept in store. when the
+/// store already holds the stream from start_time on, only the pages written since the last
+/// fetch are requested. a fetch bounded by end_time that the store can't answer is stored but
+/// doesn't count as covering the stream, it may stop short of its end
+#[allow(clippy::too_many_arguments)]
+pub async fn fetch_cached_log(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    store: &EventStore,
+    source: &Source,
+    log_group: &str,
+    log_stream: &str,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    grep: Option<&regex::Regex>,
+) -> Result<Vec<Event>, AwsLogsError> {
+    let coverage = store
+        .coverage(source, log_group, log_stream)
+        .map_err(AwsLogsError::Cache)?;
+    let (from, resume_token) = match coverage {
+        Some(coverage) if coverage.covers(start_time) => {
+            info!(
+                "cached from {:?}, fetching what's new in {log_stream}",
+                coverage.covered_from
+            );
+            (coverage.covered_from, Some(coverage.forward_token))
+        }
+        _ => (start_time, None),
+    };
+    let bounded = resume_token.is_none() && end_time.is_some();
+    let fetch_end = if bounded { end_time } else { None };
+    if resume_token.is_none() {
+        // what's stored of the range is fetched again, keeping it would store it twice
+        store
+            .clear_range(source, log_group, log_stream, from, fetch_end)
+            .map_err(AwsLogsError::Cache)?;
+    }
+    let pagination = Pagination {
+        resume_token,
+        ..Default::default()
+    };
+    let pages = stream_log_pages(client, log_group, log_stream, from, fetch_end, &pagination);
+    let mut pages = std::pin::pin!(pages);
+    while let Some(page) = pages.try_next().await? {
+        debug!("caching {} events of {log_stream}", page.events.len());
+        let coverage = Coverage {
+            covered_from: from,
+            forward_token: page.next_forward_token,
+        };
+        store
+            .insert_page(
+                source,
+                log_group,
+                log_stream,
+                &page.events,
+                (!bounded).then_some(&coverage),
+            )
+            .map_err(AwsLogsError::Cache)?;
+    }
+    let mut events = store
+        .events(source, log_group, log_stream, start_time, end_time)
+        .map_err(AwsLogsError::Cache)?;
+    events.retain(|e| crate::grep_matches(grep, e));
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(account: &str) -> Source {
+        Source {
+            account: account.to_string(),
+            region: "us-east-1".to_string(),
+            profile: "default".to_string(),
+        }
+    }
+
+    fn messages(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|e| e.message.as_str()).collect()
+    }
+
+    fn open() -> EventStore {
+        EventStore::open(Path::new(":memory:")).unwrap()
+    }
+
+    #[test]
+    fn keeps_identical_events() {
+        let store = open();
+        let source = source("111122223333");
+        let page = [Event::at(1, "retrying"), Event::at(1, "retrying"), Event::at(2, "done")];
+        store
+            .insert_page(&source, "/ecs/app", "app/a", &page, None)
+            .unwrap();
+        let events = store
+            .events(&source, "/ecs/app", "app/a", None, None)
+            .unwrap();
+        assert_eq!(messages(&events), ["retrying", "retrying", "done"]);
+    }
+
+    #[test]
+    fn clearing_a_range_replaces_what_was_stored_for_it() {
+        let store = open();
+        let source = source("111122223333");
+        let page = [Event::at(1, "a"), Event::at(2, "b"), Event::at(3, "c")];
+        store
+            .insert_page(&source, "/ecs/app", "app/a", &page, None)
+            .unwrap();
+        // fetching [2, 3) again stores its events once, not twice
+        store
+            .clear_range(&source, "/ecs/app", "app/a", Some(2), Some(3))
+            .unwrap();
+        store
+            .insert_page(&source, "/ecs/app", "app/a", &[Event::at(2, "b")], None)
+            .unwrap();
+        let events = store
+            .events(&source, "/ecs/app", "app/a", None, None)
+            .unwrap();
+        assert_eq!(messages(&events), ["a", "b", "c"]);
+        let events = store
+            .events(&source, "/ecs/app", "app/a", Some(2), Some(3))
+            .unwrap();
+        assert_eq!(messages(&events), ["b"]);
+    }
+
+    #[test]
+    fn keeps_sources_apart() {
+        let store = open();
+        let (prod, dev) = (source("111122223333"), source("444455556666"));
+        store
+            .insert_page(&prod, "/ecs/app", "app/a", &[Event::at(1, "prod")], None)
+            .unwrap();
+        store
+            .insert_page(&dev, "/ecs/app", "app/a", &[Event::at(1, "dev")], None)
+            .unwrap();
+        let events = store.events(&dev, "/ecs/app", "app/a", None, None).unwrap();
+        assert_eq!(messages(&events), ["dev"]);
+    }
+
+    #[test]
+    fn stores_coverage_with_the_page() {
+        let store = open();
+        let source = source("111122223333");
+        assert!(store
+            .coverage(&source, "/ecs/app", "app/a")
+            .unwrap()
+            .is_none());
+        let coverage = Coverage {
+            covered_from: Some(1),
+            forward_token: "f/2".to_string(),
+        };
+        store
+            .insert_page(
+                &source,
+                "/ecs/app",
+                "app/a",
+                &[Event::at(1, "a")],
+                Some(&coverage),
+            )
+            .unwrap();
+        let stored = store
+            .coverage(&source, "/ecs/app", "app/a")
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.covered_from, Some(1));
+        assert_eq!(stored.forward_token, "f/2");
+        assert!(stored.covers(Some(5)));
+        assert!(!stored.covers(Some(0)));
+        assert!(!stored.covers(None));
+    }
+
+    #[test]
+    fn importing_again_replaces_the_imported_events() {
+        let store = open();
+        let labeled = |timestamp, message| Event {
+            log_stream: Some("app/a".to_string()),
+            ..Event::at(timestamp, message)
+        };
+        // two objects of a stream meeting at a timestamp
+        let objects = [
+            vec![labeled(1, "a"), labeled(2, "b")],
+            vec![labeled(2, "c"), labeled(3, "d")],
+        ];
+        for _ in 0..2 {
+            let mut import = Import::new(&store, "/ecs/app");
+            for object in &objects {
+                import.add(object).unwrap();
+            }
+        }
+        let events = store
+            .events(&Source::imported(), "/ecs/app", "app/a", None, None)
+            .unwrap();
+        assert_eq!(messages(&events), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn lists_stored_streams_per_source() {
+        let store = open();
+        let (prod, dev) = (source("111122223333"), source("444455556666"));
+        store
+            .insert_page(&dev, "/ecs/app", "app/a", &[Event::at(5, "dev")], None)
+            .unwrap();
+        store
+            .insert_page(
+                &prod,
+                "/ecs/app",
+                "app/a",
+                &[Event::at(1, "a"), Event::at(3, "bc")],
+                None,
+            )
+            .unwrap();
+        store
+            .insert_page(&prod, "/ecs/other", "other/a", &[Event::at(1, "other")], None)
+            .unwrap();
+        let streams = store.streams("/ecs/app").unwrap();
+        assert_eq!(streams.len(), 2);
+        assert_eq!(streams[0].source, prod);
+        assert_eq!((streams[0].events, streams[0].bytes), (2, 3));
+        assert_eq!(
+            (streams[0].first_event_time, streams[0].last_event_time),
+            (1, 3)
+        );
+        assert_eq!(streams[1].source, dev);
+    }
+
+    #[test]
+    fn searches_stored_messages() {
+        let store = open();
+        let source = source("111122223333");
+        let page = [
+            Event::at(1, "connection reset by peer"),
+            Event::at(2, "request completed"),
+            Event::at(3, "connection timed out"),
+        ];
+        store
+            .insert_page(&source, "/ecs/app", "app/a", &page, None)
+            .unwrap();
+        let hits = store
+            .search("connection", None, None, None, None, Some(1))
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].event.message, "connection timed out");
+        assert_eq!(hits[0].log_stream, "app/a");
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use aws_sdk_cloudwatchlogs::types::OrderBy;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use log::debug;
+use regex::Regex;
+
+use crate::error::AwsLogsError;
+use crate::insights::StreamSize;
+
+/// a log stream as listed by DescribeLogStreams
+#[derive(Debug, Clone)]
+pub struct StreamInfo {
+    pub name: String,
+    /// epoch millis
+    pub creation_time: i64,
+    /// epoch millis, None for streams without events
+    pub first_event_time: Option<i64>,
+    /// epoch millis, None for streams without events
+    pub last_event_time: Option<i64>,
+}
+
+/// which streams a listing includes. times are epoch millis
+#[derive(Debug, Clone, Default)]
+pub struct StreamFilter {
+    pub name_prefix: Option<String>,
+    /// done here, CloudWatch only filters by prefix
+    pub name_pattern: Option<Regex>,
+    pub created_after: Option<i64>,
+    pub created_before: Option<i64>,
+    pub last_event_after: Option<i64>,
+}
+
+impl StreamFilter {
+    pub fn matches(&self, stream: &StreamInfo) -> bool {
+        self.name_prefix
+            .as_deref()
+            .is_none_or(|prefix| stream.name.starts_with(prefix))
+            && self
+                .name_pattern
+                .as_ref()
+                .is_none_or(|pattern| pattern.is_match(&stream.name))
+            && self.created_after.is_none_or(|t| stream.creation_time >= t)
+            && self.created_before.is_none_or(|t| stream.creation_time < t)
+            && self
+                .last_event_after
+                .is_none_or(|t| stream.last_event_time.is_some_and(|last| last >= t))
+    }
+}
+
+/// the order DescribeLogStreams lists streams in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamOrder {
+    Name,
+    MostRecentEventFirst,
+}
+
+/// the streams of a log group, fetched a page at a time as the stream is polled, so callers that
+/// only need the first few matches stop paginating as soon as they have them. DescribeLogStreams
+/// can't combine a name prefix with MostRecentEventFirst
+pub fn log_streams<'a>(
+    client: &'a aws_sdk_cloudwatchlogs::Client,
+    log_group: &'a str,
+    prefix: Option<&'a str>,
+    order: StreamOrder,
+) -> impl Stream<Item = Result<StreamInfo, AwsLogsError>> + 'a {
+    // the state is the token of the next page to fetch, None once the last page was fetched
+    stream::try_unfold(Some(None::<String>), move |next_page| async move {
+        let Some(token) = next_page else {
+            return Ok::<_, AwsLogsError>(None);
+        };
+        let mut request = client
+            .describe_log_streams()
+            .log_group_name(log_group)
+            .set_log_stream_name_prefix(prefix.map(|p| p.to_string()))
+            .set_next_token(token);
+        if order == StreamOrder::MostRecentEventFirst {
+            request = request.order_by(OrderBy::LastEventTime).descending(true);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AwsLogsError::sdk("failed to fetch log streams", &e))?;
+        let page = response
+            .log_streams
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|s| {
+                Some(StreamInfo {
+                    name: s.log_stream_name?,
+                    creation_time: s.creation_time.unwrap_or(0),
+                    first_event_time: s.first_event_timestamp,
+                    last_event_time: s.last_event_timestamp,
+                })
+            })
+            .collect::<Vec<StreamInfo>>();
+        debug!("fetched page of {} log streams", page.len());
+        Ok(Some((page, response.next_token.map(Some))))
+    })
+    .map_ok(|page| stream::iter(page.into_iter().map(Ok)))
+    .try_flatten()
+}
+
+/// the order of a group or stream listing, given with --order-by and turned around with --desc
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListOrder {
+    /// most recent event first, streams only
+    LastEvent,
+    /// oldest first
+    Creation,
+    Name,
+    /// smallest first: stored bytes of groups, --with-sizes bytes of streams
+    Size,
+}
+
+impl FromStr for ListOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "last-event" => Ok(ListOrder::LastEvent),
+            "creation" => Ok(ListOrder::Creation),
+            "name" => Ok(ListOrder::Name),
+            "size" => Ok(ListOrder::Size),
+            _ => Err(format!(
+                "unknown order '{s}', expected one of: last-event, creation, name, size"
+            )),
+        }
+    }
+}
+
+/// the first max streams of a listing that match filter
+async fn collect_matching(
+    streams: impl Stream<Item = Result<StreamInfo, AwsLogsError>>,
+    filter: &StreamFilter,
+    max: usize,
+) -> Result<Vec<StreamInfo>, AwsLogsError> {
+    streams
+        .try_filter(|s| futures::future::ready(filter.matches(s)))
+        .take(max)
+        .try_collect()
+        .await
+}
+
+/// the streams matching filter, in order, only the first limit of them when given. when ordered
+/// by last event or with last_event_after (and no name_prefix), CloudWatch lists them most recent
+/// event first, so paging stops at the first stream older than last_event_after. paging also
+/// stops after limit streams when CloudWatch lists them in the order asked for (by last event, or
+/// by name), or when no order is asked for, in which case the first limit listed are returned
+/// oldest first
+pub async fn ordered_log_streams(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    filter: &StreamFilter,
+    order: Option<ListOrder>,
+    limit: Option<usize>,
+) -> Result<Vec<StreamInfo>, AwsLogsError> {
+    let prefix = filter.name_prefix.as_deref();
+    let by_last_event = prefix.is_none()
+        && (order == Some(ListOrder::LastEvent) || filter.last_event_after.is_some());
+    let listed_in_order = match order {
+        None => true,
+        Some(ListOrder::Size) => false,
+        Some(order) if by_last_event => order == ListOrder::LastEvent,
+        Some(order) => order == ListOrder::Name,
+    };
+    let max = limit.filter(|_| listed_in_order).unwrap_or(usize::MAX);
+    let mut streams = if by_last_event {
+        let after = filter.last_event_after;
+        let recent = log_streams(client, log_group, None, StreamOrder::MostRecentEventFirst)
+            .try_take_while(move |s| {
+                let recent =
+                    after.is_none_or(|after| s.last_event_time.is_some_and(|last| last >= after));
+                async move { Ok(recent) }
+            });
+        collect_matching(recent, filter, max).await?
+    } else {
+        collect_matching(
+            log_streams(client, log_group, prefix, StreamOrder::Name),
+            filter,
+            max,
+        )
+        .await?
+    };
+    // sorts are stable, a listing already in order stays as CloudWatch sent it. sizes aren't
+    // known here, streams ordered by size are sorted by the caller
+    let order = order.unwrap_or(ListOrder::Creation);
+    sort_streams(&mut streams, order, false, &HashMap::new());
+    if let Some(limit) = limit {
+        streams.truncate(limit);
+    }
+    Ok(streams)
+}
+
+/// the streams matching filter, oldest first
+pub async fn filtered_log_streams(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+    filter: &StreamFilter,
+) -> Result<Vec<StreamInfo>, AwsLogsError> {
+    ordered_log_streams(client, log_group, filter, Some(ListOrder::Creation), None).await
+}
+
+/// order a listing by --order-by, the other way around with --desc. sizes are those of
+/// --with-sizes, streams without any count as empty. stable, so ties keep the order they were
+/// listed in
+pub fn sort_streams(
+    streams: &mut [StreamInfo],
+    order: ListOrder,
+    descending: bool,
+    sizes: &HashMap<String, StreamSize>,
+) {
+    let bytes = |stream: &StreamInfo| sizes.get(&stream.name).map_or(0, |size| size.bytes);
+    streams.sort_by(|a, b| {
+        let ordering = match order {
+            ListOrder::LastEvent => b.last_event_time.cmp(&a.last_event_time),
+            ListOrder::Creation => a.creation_time.cmp(&b.creation_time),
+            ListOrder::Name => a.name.cmp(&b.name),
+            ListOrder::Size => bytes(a).cmp(&bytes(b)),
+        };
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
@@ -0,0 +1,415 @@
+use std::process::Command;
+
+use log::debug;
+use rust_aws_logs::error::AwsLogsError;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+/// CloudWatch Logs allows this many subscription filters per log group
+const MAX_SUBSCRIPTION_FILTERS: usize = 2;
+
+/// the delivery role the wizard offers to create
+const ROLE_NAME: &str = "CWLtoDestination";
+
+/// the kinds of destination a subscription filter can deliver to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DestinationKind {
+    Kinesis,
+    Firehose,
+}
+
+impl DestinationKind {
+    fn arn_service(&self) -> &'static str {
+        match self {
+            DestinationKind::Kinesis => "kinesis",
+            DestinationKind::Firehose => "firehose",
+        }
+    }
+
+    fn arn_resource(&self) -> &'static str {
+        match self {
+            DestinationKind::Kinesis => "stream/",
+            DestinationKind::Firehose => "deliverystream/",
+        }
+    }
+
+    fn put_actions(&self) -> &'static str {
+        match self {
+            DestinationKind::Kinesis => r#"["kinesis:PutRecord", "kinesis:PutRecords"]"#,
+            DestinationKind::Firehose => r#"["firehose:PutRecord", "firehose:PutRecordBatch"]"#,
+        }
+    }
+}
+
+/// the parts of an ARN the wizard checks: service, region, account and resource
+struct Arn<'a> {
+    service: &'a str,
+    region: &'a str,
+    account: &'a str,
+    resource: &'a str,
+}
+
+fn parse_arn(arn: &str) -> Option<Arn<'_>> {
+    let mut parts = arn.splitn(6, ':');
+    if parts.next()? != "arn" {
+        return None;
+    }
+    let _partition = parts.next()?;
+    Some(Arn {
+        service: parts.next()?,
+        region: parts.next()?,
+        account: parts.next()?,
+        resource: parts.next()?,
+    })
+}
+
+/// read one line, None on ctrl-c / ctrl-d
+fn prompt(editor: &mut DefaultEditor, question: &str) -> Result<Option<String>, AwsLogsError> {
+    match editor.readline(&format!("{question}: ")) {
+        Ok(line) => Ok(Some(line.trim().to_string())),
+        Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => Ok(None),
+        Err(e) => Err(AwsLogsError::Io(format!("unable to read input: {e}"))),
+    }
+}
+
+/// ask until validate accepts the answer, which it can turn into something else
+fn ask<T>(
+    editor: &mut DefaultEditor,
+    question: &str,
+    validate: impl Fn(&str) -> Result<T, String>,
+) -> Result<Option<T>, AwsLogsError> {
+    loop {
+        let Some(answer) = prompt(editor, question)? else {
+            return Ok(None);
+        };
+        match validate(&answer) {
+            Ok(value) => return Ok(Some(value)),
+            Err(e) => println!("  {e}"),
+        }
+    }
+}
+
+fn confirm(editor: &mut DefaultEditor, question: &str) -> Result<bool, AwsLogsError> {
+    let answer = prompt(editor, &format!("{question} [y/N]"))?;
+    Ok(matches!(answer.as_deref(), Some("y") | Some("yes")))
+}
+
+/// the arn of log_group, which also says which account and region it lives in
+async fn log_group_arn(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: &str,
+) -> Result<Option<String>, AwsLogsError> {
+    let response = client
+        .describe_log_groups()
+        .log_group_name_prefix(log_group)
+        .send()
+        .await
+        .map_err(|e| AwsLogsError::sdk("failed to describe log groups", &e))?;
+    Ok(response
+        .log_groups
+        .unwrap_or_default()
+        .into_iter()
+        .find(|g| g.log_group_name.as_deref() == Some(log_group))
+        .and_then(|g| g.arn))
+}
+
+fn trust_policy(region: &str, account: &str) -> String {
+    format!(
+        r#"{{
+  "Version": "2012-10-17",
+  "Statement": [{{
+    "Effect": "Allow",
+    "Principal": {{ "Service": "logs.amazonaws.com" }},
+    "Action": "sts:AssumeRole",
+    "Condition": {{ "StringLike": {{ "aws:SourceArn": "arn:aws:logs:{region}:{account}:*" }} }}
+  }}]
+}}"#
+    )
+}
+
+fn permissions_policy(kind: DestinationKind, destination_arn: &str) -> String {
+    format!(
+        r#"{{
+  "Version": "2012-10-17",
+  "Statement": [{{
+    "Effect": "Allow",
+    "Action": {},
+    "Resource": "{destination_arn}"
+  }}]
+}}"#,
+        kind.put_actions()
+    )
+}
+
+/// run an aws cli command with the profile alog was given, returning what it printed
+fn run_aws(profile: Option<&str>, args: &[&str]) -> Result<String, AwsLogsError> {
+    let mut cmd = Command::new("aws");
+    if let Some(profile) = profile {
+        cmd.args(["--profile", profile]);
+    }
+    debug!("running aws {}", args.join(" "));
+    let output = cmd
+        .args(args)
+        .output()
+        .map_err(|e| AwsLogsError::Io(format!("unable to run the aws cli: {e}")))?;
+    if !output.status.success() {
+        return Err(AwsLogsError::Io(format!(
+            "aws {} failed: {}",
+            args[..2].join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// create the delivery role and its permissions with the aws cli, returning the role's arn
+fn create_role(
+    profile: Option<&str>,
+    trust_policy: &str,
+    permissions_policy: &str,
+) -> Result<String, AwsLogsError> {
+    let role_arn = run_aws(
+        profile,
+        &[
+            "iam",
+            "create-role",
+            "--role-name",
+            ROLE_NAME,
+            "--assume-role-policy-document",
+            trust_policy,
+            "--query",
+            "Role.Arn",
+            "--output",
+            "text",
+        ],
+    )?;
+    run_aws(
+        profile,
+        &[
+            "iam",
+            "put-role-policy",
+            "--role-name",
+            ROLE_NAME,
+            "--policy-name",
+            "put-records",
+            "--policy-document",
+            permissions_policy,
+        ],
+    )?;
+    Ok(role_arn)
+}
+
+/// walk through subscribing a log group to a Kinesis stream or Firehose delivery stream:
+/// pick and check the destination, set up (or point at) the role CloudWatch Logs delivers
+/// with, check the filter pattern, then create the subscription filter. the role is created
+/// with the aws cli (using profile) when asked to, its policies and commands are printed either
+/// way
+pub async fn run_wizard(
+    client: &aws_sdk_cloudwatchlogs::Client,
+    log_group: Option<String>,
+    profile: Option<&str>,
+) -> Result<(), AwsLogsError> {
+    let mut editor = DefaultEditor::new()
+        .map_err(|e| AwsLogsError::Io(format!("unable to start interactive prompt: {e}")))?;
+    println!("subscribe a log group to a Kinesis or Firehose destination (ctrl-c to quit)\n");
+
+    // 1. the log group
+    let (log_group, group_arn) = loop {
+        let name = match log_group {
+            Some(ref name) => name.clone(),
+            None => match prompt(&mut editor, "log group")? {
+                Some(name) if !name.is_empty() => name,
+                Some(_) => continue,
+                None => return Ok(()),
+            },
+        };
+        match log_group_arn(client, &name).await? {
+            Some(arn) => break (name, arn),
+            None if log_group.is_some() => {
+                return Err(AwsLogsError::InvalidArgument(format!(
+                    "no log group named {name}"
+                )))
+            }
+            None => println!("  no log group named {name}"),
+        }
+    };
+    let Some(group) = parse_arn(&group_arn) else {
+        return Err(AwsLogsError::Sdk(format!(
+            "unexpected log group arn: {group_arn}"
+        )));
+    };
+    let (region, account) = (group.region.to_string(), group.account.to_string());
+    debug!("log group {log_group} is in account {account}, region {region}");
+
+    let existing = client
+        .describe_subscription_filters()
+        .log_group_name(&log_group)
+        .send()
+        .await
+        .map_err(|e| AwsLogsError::sdk("failed to describe subscription filters", &e))?
+        .subscription_filters
+        .unwrap_or_default();
+    for filter in &existing {
+        println!(
+            "  existing subscription: {} -> {}",
+            filter.filter_name.as_deref().unwrap_or_default(),
+            filter.destination_arn.as_deref().unwrap_or_default()
+        );
+    }
+    if existing.len() >= MAX_SUBSCRIPTION_FILTERS {
+        println!("  {log_group} already has {MAX_SUBSCRIPTION_FILTERS} subscription filters, new ones must reuse one of their names");
+    }
+
+    // 2. the destination
+    let Some(kind) = ask(
+        &mut editor,
+        "destination type (kinesis/firehose)",
+        |answer| match answer {
+            "kinesis" => Ok(DestinationKind::Kinesis),
+            "firehose" => Ok(DestinationKind::Firehose),
+            _ => Err("expected kinesis or firehose".to_string()),
+        },
+    )?
+    else {
+        return Ok(());
+    };
+    let Some(destination_arn) = ask(&mut editor, "destination arn", |answer| {
+        let arn = parse_arn(answer).ok_or("not an arn")?;
+        if arn.service != kind.arn_service() || !arn.resource.starts_with(kind.arn_resource()) {
+            return Err(format!(
+                "expected arn:aws:{}:<region>:<account>:{}<name>",
+                kind.arn_service(),
+                kind.arn_resource()
+            ));
+        }
+        if arn.region != region {
+            return Err(format!(
+                "the destination must be in the log group's region ({region})"
+            ));
+        }
+        if arn.account != account {
+            return Err(format!(
+                "the destination is in account {}, cross-account delivery needs a CloudWatch Logs destination created in that account, see `aws logs put-destination`",
+                arn.account
+            ));
+        }
+        Ok(answer.to_string())
+    })?
+    else {
+        return Ok(());
+    };
+    if kind == DestinationKind::Kinesis {
+        println!("  make sure the stream has enough shards for the group's peak ingestion, deliveries are throttled otherwise");
+    }
+
+    // 3. the role CloudWatch Logs assumes to put records
+    println!(
+        "\nCloudWatch Logs needs a role it can assume to write to the destination. to create one:\n"
+    );
+    let trust = trust_policy(&region, &account);
+    let permissions = permissions_policy(kind, &destination_arn);
+    println!("trust-policy.json:\n{trust}\n");
+    println!("permissions.json:\n{permissions}\n");
+    println!("❯ aws iam create-role --role-name {ROLE_NAME} --assume-role-policy-document file://trust-policy.json");
+    println!("❯ aws iam put-role-policy --role-name {ROLE_NAME} --policy-name put-records --policy-document file://permissions.json\n");
+    let mut created_role = None;
+    if confirm(&mut editor, "run these commands now?")? {
+        match create_role(profile, &trust, &permissions) {
+            Ok(arn) => {
+                println!("  created {arn}");
+                created_role = Some(arn);
+            }
+            // e.g. the role exists already, its arn can still be given
+            Err(e) => println!("  {e}"),
+        }
+    }
+    let role_arn = match created_role {
+        Some(arn) => arn,
+        None => {
+            let Some(role_arn) = ask(&mut editor, "role arn", |answer| match parse_arn(answer) {
+                Some(arn) if arn.service == "iam" && arn.resource.starts_with("role/") => {
+                    Ok(answer.to_string())
+                }
+                _ => Err("expected arn:aws:iam::<account>:role/<name>".to_string()),
+            })?
+            else {
+                return Ok(());
+            };
+            role_arn
+        }
+    };
+
+    // 4. which events are delivered
+    let Some(filter_pattern) = ask(
+        &mut editor,
+        "filter pattern (empty for every event)",
+        |answer| Ok(answer.to_string()),
+    )?
+    else {
+        return Ok(());
+    };
+    if !filter_pattern.is_empty() {
+        // TestMetricFilter rejects patterns with invalid syntax, the same ones a subscription would
+        if let Err(e) = client
+            .test_metric_filter()
+            .filter_pattern(&filter_pattern)
+            .log_event_messages("alog filter pattern check")
+            .send()
+            .await
+        {
+            return Err(AwsLogsError::sdk(
+                &format!("invalid filter pattern '{filter_pattern}'"),
+                &e,
+            ));
+        }
+    }
+    let default_name = format!("alog-{}", kind.arn_service());
+    let Some(filter_name) = prompt(
+        &mut editor,
+        &format!("subscription filter name [{default_name}]"),
+    )?
+    else {
+        return Ok(());
+    };
+    let filter_name = if filter_name.is_empty() {
+        default_name
+    } else {
+        filter_name
+    };
+
+    // 5. create it. CloudWatch Logs test-delivers to the destination, which fails until the
+    // role's trust and permissions have propagated, so failures can be retried
+    println!("\n  log group:    {log_group}\n  destination:  {destination_arn}\n  role:         {role_arn}\n  pattern:      {filter_pattern:?}\n  filter name:  {filter_name}");
+    if !confirm(&mut editor, "create the subscription filter?")? {
+        return Ok(());
+    }
+    loop {
+        let result = client
+            .put_subscription_filter()
+            .log_group_name(&log_group)
+            .filter_name(&filter_name)
+            .filter_pattern(&filter_pattern)
+            .destination_arn(&destination_arn)
+            .role_arn(&role_arn)
+            .send()
+            .await;
+        match result {
+            Ok(_) => {
+                println!("subscribed {log_group} to {destination_arn}");
+                return Ok(());
+            }
+            Err(e) => {
+                println!(
+                    "  failed to create the subscription filter: {}",
+                    aws_sdk_cloudwatchlogs::error::DisplayErrorContext(&e)
+                );
+                println!("  check the role's trust policy and permissions above, a new role can take a minute to be usable");
+                if !confirm(&mut editor, "retry?")? {
+                    return Err(AwsLogsError::Sdk(
+                        "subscription filter not created".to_string(),
+                    ));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use jiff::fmt::strtime;
+use jiff::tz::TimeZone;
+use jiff::{Timestamp, Zoned};
+
+/// the time zone rendered times are shown in, UTC until --timezone sets another one
+static DISPLAY_ZONE: OnceLock<TimeZone> = OnceLock::new();
+
+/// how event timestamps are rendered in front of each line
+#[derive(Debug, Clone)]
+pub enum TimeFormat {
+    /// absolute time, e.g. 2024-03-13T16:28:28.123Z, or with an offset outside UTC
+    Rfc3339,
+    /// age relative to now, e.g. -3m12s
+    Relative,
+    /// offset from the first event, e.g. +3m12s
+    SinceStart,
+    /// absolute time, from a strftime pattern like `%Y-%m-%d %H:%M:%S`, kept in jiff's spelling
+    Strftime(String),
+}
+
+impl FromStr for TimeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rfc3339" => Ok(TimeFormat::Rfc3339),
+            "relative" => Ok(TimeFormat::Relative),
+            "since-start" => Ok(TimeFormat::SinceStart),
+            _ if s.contains('%') => parse_strftime(s).map(TimeFormat::Strftime),
+            _ => Err(format!(
+                "unknown time format '{s}', expected one of: rfc3339, relative, since-start, or a strftime pattern"
+            )),
+        }
+    }
+}
+
+/// the jiff conversion equivalent to a strftime conversion specifier
+fn strftime_component(specifier: char) -> Option<&'static str> {
+    let component = match specifier {
+        'Y' => "%Y",
+        'y' => "%y",
+        'm' => "%m",
+        'b' | 'h' => "%b",
+        'B' => "%B",
+        'd' => "%d",
+        'e' => "%e",
+        'j' => "%j",
+        'a' => "%a",
+        'A' => "%A",
+        'H' => "%H",
+        'I' => "%I",
+        'p' => "%p",
+        'M' => "%M",
+        'S' => "%S",
+        'L' => "%3f",
+        'f' => "%6f",
+        'z' => "%z",
+        'Z' => "%:z",
+        's' => "%s",
+        'F' => "%F",
+        'T' => "%T",
+        'R' => "%R",
+        '%' => "%%",
+        _ => return None,
+    };
+    Some(component)
+}
+
+/// parse a strftime pattern, e.g. `%Y-%m-%d %H:%M:%S.%L`. %L is milliseconds and %f microseconds
+fn parse_strftime(pattern: &str) -> Result<String, String> {
+    let mut converted = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => {
+                let specifier = chars
+                    .next()
+                    .ok_or_else(|| format!("time format '{pattern}' ends with a lone %"))?;
+                let component = strftime_component(specifier).ok_or_else(|| {
+                    format!("unsupported conversion %{specifier} in time format '{pattern}'")
+                })?;
+                converted.push_str(component);
+            }
+            c => converted.push(c),
+        }
+    }
+    Ok(converted)
+}
+
+pub fn now_millis() -> i64 {
+    Timestamp::now().as_millisecond()
+}
+
+/// parse a --timezone value: UTC, local (the system time zone) or an IANA name like Europe/Berlin
+pub fn parse_time_zone(name: &str) -> Result<TimeZone, String> {
+    match name {
+        "UTC" | "utc" => Ok(TimeZone::UTC),
+        "local" => TimeZone::try_system()
+            .map_err(|e| format!("failed to determine the local time zone: {e}")),
+        _ => TimeZone::get(name).map_err(|e| format!("unknown time zone '{name}': {e}")),
+    }
+}
+
+/// show rendered times in zone from now on, only the first call has an effect
+pub fn set_display_zone(zone: TimeZone) {
+    let _ = DISPLAY_ZONE.set(zone);
+}
+
+/// epoch millis as a date time in the display zone
+fn display_time(millis: i64) -> Option<Zoned> {
+    let zone = DISPLAY_ZONE.get().cloned().unwrap_or(TimeZone::UTC);
+    Timestamp::from_millisecond(millis)
+        .ok()
+        .map(|t| t.to_zoned(zone))
+}
+
+/// format epoch millis as an RFC3339 timestamp in the display zone, for times shown to the user.
+/// a zero offset is written Z
+pub fn format_display(millis: i64) -> String {
+    match display_time(millis) {
+        Some(t) if t.offset().is_zero() => t.timestamp().to_string(),
+        Some(t) => t.timestamp().display_with_offset(t.offset()).to_string(),
+        None => millis.to_string(),
+    }
+}
+
+/// format epoch millis as an RFC3339 timestamp in UTC
+pub fn format_rfc3339(millis: i64) -> String {
+    Timestamp::from_millisecond(millis)
+        .map(|t| t.to_string())
+        .unwrap_or_else(|_| millis.to_string())
+}
+
+/// parse a point in time given as RFC3339 (2024-05-01T13:00:00Z) or epoch millis
+pub fn parse_time(value: &str) -> Result<i64, String> {
+    if let Ok(millis) = value.parse::<i64>() {
+        return Ok(millis);
+    }
+    value
+        .parse::<Timestamp>()
+        .map(|t| t.as_millisecond())
+        .map_err(|e| format!("invalid time '{value}', expected RFC3339 or epoch millis: {e}"))
+}
+
+/// format a signed duration compactly, e.g. -3m12s, +1h05m00s, -2d03h, -250ms
+pub fn format_duration(delta_ms: i64) -> String {
+    let sign = if delta_ms < 0 { '-' } else { '+' };
+    let ms = delta_ms.unsigned_abs();
+    if ms < 1000 {
+        return format!("{sign}{ms}ms");
+    }
+    let secs = ms / 1000;
+    let (days, hours, mins, secs) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{sign}{days}d{hours:02}h")
+    } else if hours > 0 {
+        format!("{sign}{hours}h{mins:02}m{secs:02}s")
+    } else if mins > 0 {
+        format!("{sign}{mins}m{secs:02}s")
+    } else {
+        format!("{sign}{secs}s")
+    }
+}
+
+/// renders timestamps for a batch of events, relative formats are anchored once per batch
+#[derive(Debug, Clone, Copy)]
+pub struct TimestampRenderer<'a> {
+    format: &'a TimeFormat,
+    reference_ms: i64,
+}
+
+impl<'a> TimestampRenderer<'a> {
+    /// first_event_ms is the anchor for since-start, ignored by the other formats
+    pub fn new(format: &'a TimeFormat, first_event_ms: Option<i64>) -> TimestampRenderer<'a> {
+        let reference_ms = match format {
+            TimeFormat::SinceStart => first_event_ms.unwrap_or(0),
+            _ => now_millis(),
+        };
+        TimestampRenderer {
+            format,
+            reference_ms,
+        }
+    }
+
+    pub fn render(&self, timestamp_ms: i64) -> String {
+        match self.format {
+            TimeFormat::Rfc3339 => format_display(timestamp_ms),
+            TimeFormat::Relative | TimeFormat::SinceStart => {
+                format_duration(timestamp_ms - self.reference_ms)
+            }
+            TimeFormat::Strftime(ref pattern) => display_time(timestamp_ms)
+                .and_then(|t| strtime::format(pattern, &t).ok())
+                .unwrap_or_else(|| timestamp_ms.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-03-13T16:28:28.123Z
+    const TIMESTAMP_MS: i64 = 1_710_347_308_123;
+
+    fn render(format: &str) -> String {
+        let format = format.parse::<TimeFormat>().unwrap();
+        TimestampRenderer::new(&format, None).render(TIMESTAMP_MS)
+    }
+
+    #[test]
+    fn renders_strftime_patterns() {
+        assert_eq!(render("%Y-%m-%d %H:%M:%S.%L"), "2024-03-13 16:28:28.123");
+        assert_eq!(render("%F %T"), "2024-03-13 16:28:28");
+        assert_eq!(render("%d %b %y %I:%M %p"), "13 Mar 24 04:28 PM");
+        assert_eq!(render("[%H:%M] 100%%"), "[16:28] 100%");
+    }
+
+    #[test]
+    fn rejects_unsupported_strftime_patterns() {
+        assert!("%Y-%Q".parse::<TimeFormat>().is_err());
+        assert!("%H:%M %".parse::<TimeFormat>().is_err());
+        assert!("iso".parse::<TimeFormat>().is_err());
+    }
+
+    #[test]
+    fn renders_since_start() {
+        let format = TimeFormat::SinceStart;
+        let renderer = TimestampRenderer::new(&format, Some(TIMESTAMP_MS - 192_000));
+        assert_eq!(renderer.render(TIMESTAMP_MS), "+3m12s");
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(-250), "-250ms");
+        assert_eq!(format_duration(-192_000), "-3m12s");
+        assert_eq!(format_duration(3_900_000), "+1h05m00s");
+        assert_eq!(format_duration(-183_600_000), "-2d03h");
+    }
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(parse_time("2024-03-13T16:28:28.123Z"), Ok(TIMESTAMP_MS));
+        assert_eq!(parse_time("1710347308123"), Ok(TIMESTAMP_MS));
+        assert!(parse_time("yesterday").is_err());
+    }
+}